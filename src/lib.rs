@@ -4,26 +4,213 @@ use rmcp::{
     handler::server::{router::tool::ToolRouter, wrapper::Parameters},
     model::*,
     schemars,
-    service::RequestContext,
+    service::{Peer, RequestContext},
     tool, tool_handler, tool_router, ErrorData as McpError, RoleServer, ServerHandler,
 };
 use serde_json::json;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::task::JoinHandle;
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct DomainParam {
     pub domain: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct DnsLookupParam {
+    pub domain: String,
+    /// Also fetch the SMTP STARTTLS certificate of every MX host on port 25 (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check_certificates: Option<bool>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct DomainsParam {
     pub domains: Vec<String>,
+    /// Index of the first domain to check, for paging through long lists (default: 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    /// Maximum number of domains to check in this call; the result carries `next_offset` when more remain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct ExpiredDomainsParam {
+    /// Keywords to look for in domain names; each one is searched with every TLD
     pub keywords: Vec<String>,
+    /// TLDs to search, e.g. ["com", "net"] (default: any)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tlds: Option<Vec<String>>,
+    /// Maximum number of domains to return (default: 100); the result carries `next_offset` when more remain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Index of the first domain to return, for paging through the results (default: 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    /// Sort by "end_time", "appraisal", "length", "age" or "score" (default: source order)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<String>,
+    /// "asc" or "desc" (default: soonest end_time, highest appraisal, shortest length, oldest age, best score first)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+    /// Minimum length of the name before the TLD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// Maximum length of the name before the TLD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Exclude names containing hyphens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_hyphens: Option<bool>,
+    /// Exclude names containing digits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_digits: Option<bool>,
+    /// Only names made entirely of dictionary words, e.g. "cloudshop"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dictionary_words_only: Option<bool>,
+    /// Regular expression the name before the TLD must match, e.g. "^[a-z]{4}$"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Only domains first registered at least this many days ago
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_age_days: Option<i64>,
+    /// Only domains with a known starting price at or below this amount
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_price: Option<f64>,
+    /// Only domains that had DNS records (true) or had none (false). Drop-list matches are looked up, which is slow for broad searches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_dns: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ImportDropListParam {
+    /// Name for the list, used as its source name in search results, e.g. "my-auctions"
+    pub name: String,
+    /// Path to a CSV or plain text file with one domain per line, in the server's import directory; relative paths start there
+    pub path: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct WatchDomainParam {
+    /// Domain to watch, e.g. "example.com"
+    pub domain: String,
+    /// Why the domain is wanted, e.g. "Bid up to $500"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Stop watching the domain instead (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SuggestDomainsParam {
+    /// Seed keywords, e.g. ["cloud", "kitchen"]
+    pub keywords: Vec<String>,
+    /// TLDs to try, most preferred first (default: com, io, co, net, app, dev)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tlds: Option<Vec<String>>,
+    /// Maximum number of available suggestions to return (default: 20)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Maximum number of generated candidates to check (default: 60)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_candidates: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct NameAcrossTldsParam {
+    /// Second-level label without a TLD, e.g. "acme"
+    pub name: String,
+    /// Explicit list of TLDs to check, e.g. ["com", "io"]; not combined with tld_set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tlds: Option<Vec<String>>,
+    /// Named TLD group: "popular" (default), "ccTLDs", "new gTLDs" or "all" (every TLD in the IANA RDAP bootstrap); not combined with tlds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tld_set: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SslCertificateParam {
+    pub domain: String,
+    /// TCP port (default: the protocol's usual port, 443 for plain TLS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// "tls" (default), or a STARTTLS service: "smtp", "submission", "imap", "pop3", "ftp", "xmpp", "ldap", "postgres". Implicit-TLS names such as "smtps" and "imaps" select their own port.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    /// Connect to this IP address instead of resolving the domain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    /// Server name to send in SNI and validate against (default: the domain)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sni: Option<String>,
+    /// Fetch the certificate from every A and AAAA address of the domain and report whether they all serve the same one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub all_addresses: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CtSearchParam {
+    /// Domain whose certificates and subdomains to list, e.g. "example.com"
+    pub domain: String,
+    /// "crtsh" (default), "log" for an RFC 6962 log, or "jsonl" for the dump at DOMAIN_MCP_CT_JSONL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Base URL of the RFC 6962 log, e.g. "https://ct.googleapis.com/logs/us1/argon2025h2" (log source only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_url: Option<String>,
+    /// First log entry to scan (log source only; default: the newest entries)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<u64>,
+    /// Number of log entries to scan (log source only; default: 1000, max: 10000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    /// Maximum number of certificates to return, newest first (default: 100)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CaaCheckParam {
+    /// Domain whose HTTPS certificate to check against CAA, e.g. "example.com"
+    pub domain: String,
+    /// Also recommend a CAA RRset allowing every CA seen in Certificate Transparency (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommend: Option<bool>,
+    /// CT source for the recommendation: "crtsh" (default) or "jsonl"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CertificateExpiryReportParam {
+    /// Endpoints to check, as "host" or "host:port" (default port: 443), e.g. ["example.com", "mail.example.com:8443"]
+    pub targets: Vec<String>,
+    /// Flag certificates expiring within this many days as warnings (default: 30)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warn_days: Option<i64>,
+    /// Flag certificates expiring within this many days as critical (default: 7)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub critical_days: Option<i64>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct HstsPreloadParam {
+    /// Apex domain to check, e.g. "example.com"
+    pub domain: String,
+    /// Extra subdomains to require HTTPS on, as labels or full names (www and names in crt.sh certificates are always checked)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subdomains: Option<Vec<String>>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct TlsScanParam {
+    /// Hostname or IP address to scan
+    pub host: String,
+    /// TCP port (default: 443)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
 }
 
 #[derive(Clone)]
@@ -63,12 +250,23 @@ impl DomainServer {
         }
     }
 
-    #[tool(description = "Perform DNS lookup for a domain")]
+    #[tool(
+        description = "Perform DNS lookup for a domain. With check_certificates, also check the SMTP STARTTLS certificate of every MX host, so expired or mismatched mail server certificates show up before they break delivery"
+    )]
     async fn dns_lookup(
         &self,
-        Parameters(DomainParam { domain }): Parameters<DomainParam>,
+        Parameters(DnsLookupParam {
+            domain,
+            check_certificates,
+        }): Parameters<DnsLookupParam>,
     ) -> Result<CallToolResult, McpError> {
-        match tools::dns::lookup(&domain).await {
+        let result = if check_certificates.unwrap_or(false) {
+            tools::dns::lookup_with_mx_certificates(&domain).await
+        } else {
+            tools::dns::lookup(&domain).await
+        };
+
+        match result {
             Ok(result) => {
                 let text = serde_json::to_string_pretty(&result)
                     .unwrap_or_else(|_| "Error formatting result".to_string());
@@ -81,7 +279,9 @@ impl DomainServer {
         }
     }
 
-    #[tool(description = "Check if a domain is available for registration")]
+    #[tool(
+        description = "Check if a domain is available for registration. Returns a verdict (registered, available, reserved, premium, unknown) with a confidence score and the evidence behind it"
+    )]
     async fn check_domain_availability(
         &self,
         Parameters(DomainParam { domain }): Parameters<DomainParam>,
@@ -99,40 +299,326 @@ impl DomainServer {
         }
     }
 
-    #[tool(description = "Get SSL certificate information for a domain")]
+    #[tool(
+        description = "Get SSL certificate information for a domain on any port, upgrading with STARTTLS first for SMTP, IMAP, POP3, FTP, XMPP, LDAP and PostgreSQL. Can connect to a specific IP with any SNI, or compare the certificates served by every A/AAAA address"
+    )]
     async fn ssl_certificate_info(
+        &self,
+        Parameters(SslCertificateParam {
+            domain,
+            port,
+            protocol,
+            ip,
+            sni,
+            all_addresses,
+        }): Parameters<SslCertificateParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let (protocol, default_port) =
+            match tools::starttls::Protocol::parse(protocol.as_deref().unwrap_or("tls")) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    return Err(McpError::invalid_params(
+                        "invalid_protocol",
+                        Some(json!({ "error": e.to_string() })),
+                    ))
+                }
+            };
+        let port = port.unwrap_or(default_port);
+
+        let mut endpoint = tools::cert_chain::Endpoint::new(&domain, port, protocol);
+        if let Some(sni) = sni {
+            endpoint.server_name = sni;
+        }
+        let all_addresses = all_addresses.unwrap_or(false);
+        if let Some(ip) = ip {
+            if all_addresses {
+                return Err(McpError::invalid_params(
+                    "invalid_address",
+                    Some(json!({ "error": "Use either ip or all_addresses, not both" })),
+                ));
+            }
+            match ip.parse() {
+                Ok(address) => endpoint = endpoint.with_address(address),
+                Err(_) => {
+                    return Err(McpError::invalid_params(
+                        "invalid_address",
+                        Some(json!({ "error": format!("'{}' is not an IP address", ip) })),
+                    ))
+                }
+            }
+        }
+
+        let result = if all_addresses {
+            tools::ssl::check_all_addresses(&domain, &endpoint)
+                .await
+                .map(|report| serde_json::to_string_pretty(&report))
+        } else {
+            tools::ssl::get_certificate_info(&endpoint)
+                .await
+                .map(|cert| serde_json::to_string_pretty(&cert))
+        };
+
+        match result {
+            Ok(text) => {
+                let text = text.unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "ssl_info_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Analyze the certificate chain a domain presents: every certificate, the path to a trusted root, missing intermediates, wrong order, expired certificates and the specific validation error"
+    )]
+    async fn analyze_certificate_chain(
         &self,
         Parameters(DomainParam { domain }): Parameters<DomainParam>,
     ) -> Result<CallToolResult, McpError> {
-        match tools::ssl::get_certificate_info(&domain).await {
+        match tools::cert_chain::analyze_certificate_chain(&domain).await {
             Ok(result) => {
                 let text = serde_json::to_string_pretty(&result)
                     .unwrap_or_else(|_| "Error formatting result".to_string());
                 Ok(CallToolResult::success(vec![Content::text(text)]))
             }
             Err(e) => Err(McpError::internal_error(
-                "ssl_info_failed",
+                "chain_analysis_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Search Certificate Transparency logs for certificates issued for a domain and its subdomains, via crt.sh, an RFC 6962 log or a local JSONL dump. Also lists every subdomain seen"
+    )]
+    async fn ct_search(
+        &self,
+        Parameters(CtSearchParam {
+            domain,
+            source,
+            log_url,
+            start,
+            count,
+            limit,
+        }): Parameters<CtSearchParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let source =
+            match tools::ct::source_from(source.as_deref(), log_url.as_deref(), start, count) {
+                Ok(source) => source,
+                Err(e) => {
+                    return Err(McpError::invalid_params(
+                        "invalid_ct_source",
+                        Some(json!({ "error": e.to_string() })),
+                    ))
+                }
+            };
+
+        match tools::ct::search(&domain, source.as_ref(), limit.unwrap_or(100)).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "ct_search_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Check whether CAA records allow the CA that issued a domain's current certificate to issue for each of its names (issue, issuewild, iodef), so renewals are not refused. Can recommend a CAA RRset from the CAs seen in Certificate Transparency"
+    )]
+    async fn caa_check(
+        &self,
+        Parameters(CaaCheckParam {
+            domain,
+            recommend,
+            source,
+        }): Parameters<CaaCheckParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let source = if recommend.unwrap_or(false) {
+            match tools::ct::source_from(source.as_deref(), None, None, None) {
+                Ok(source) => Some(source),
+                Err(e) => {
+                    return Err(McpError::invalid_params(
+                        "invalid_ct_source",
+                        Some(json!({ "error": e.to_string() })),
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+
+        match tools::caa::check(&domain, source.as_deref()).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "caa_check_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Fetch the certificates of many host[:port] endpoints concurrently and list them soonest expiry first, flagging those inside the warn and critical thresholds, hostname mismatches and weak keys"
+    )]
+    async fn certificate_expiry_report(
+        &self,
+        Parameters(CertificateExpiryReportParam {
+            targets,
+            warn_days,
+            critical_days,
+        }): Parameters<CertificateExpiryReportParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let thresholds = tools::expiry::ExpiryThresholds {
+            warn_days: warn_days.unwrap_or(tools::expiry::DEFAULT_WARN_DAYS),
+            critical_days: critical_days.unwrap_or(tools::expiry::DEFAULT_CRITICAL_DAYS),
+        };
+        if targets.is_empty() || thresholds.critical_days > thresholds.warn_days {
+            return Err(McpError::invalid_params(
+                "invalid_expiry_report",
+                Some(json!({
+                    "error": "Give at least one target, and critical_days no larger than warn_days"
+                })),
+            ));
+        }
+
+        let result = tools::expiry::report(&targets, thresholds).await;
+        let text = serde_json::to_string_pretty(&result)
+            .unwrap_or_else(|_| "Error formatting result".to_string());
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        description = "Follow the redirect chains from http:// and https:// for a domain, recording each hop's status, Location and timing, check that HTTPS is enforced and grade the security headers (HSTS, CSP, X-Frame-Options, Referrer-Policy, Permissions-Policy)"
+    )]
+    async fn http_probe(
+        &self,
+        Parameters(DomainParam { domain }): Parameters<DomainParam>,
+    ) -> Result<CallToolResult, McpError> {
+        match tools::http_probe::probe(&domain).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "http_probe_failed",
                 Some(json!({ "error": e.to_string() })),
             )),
         }
     }
 
-    #[tool(description = "Search for expired domains based on keywords")]
+    #[tool(
+        description = "Check a domain against every hstspreload.org requirement: valid certificate, HTTP to HTTPS redirect on the same host, HSTS on the apex with max-age of a year, includeSubDomains and preload, and valid HTTPS on every resolving subdomain seen in crt.sh certificates. Explains each failing requirement"
+    )]
+    async fn hsts_preload_check(
+        &self,
+        Parameters(HstsPreloadParam { domain, subdomains }): Parameters<HstsPreloadParam>,
+    ) -> Result<CallToolResult, McpError> {
+        match tools::hsts_preload::check(&domain, &subdomains.unwrap_or_default()).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "hsts_preload_check_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Scan a TLS endpoint for supported protocol versions (SSLv3 through TLS 1.3), cipher suites in server preference order, ALPN protocols, key exchange groups, OCSP stapling and session resumption"
+    )]
+    async fn tls_scan(
+        &self,
+        Parameters(TlsScanParam { host, port }): Parameters<TlsScanParam>,
+    ) -> Result<CallToolResult, McpError> {
+        match tools::tls_scan::scan(&host, port.unwrap_or(443)).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "tls_scan_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Search for expired domains based on keywords, across every keyword and TLD combination, reporting which keywords matched each domain. Filter by length, hyphens, digits, dictionary words, a pattern, age, price or DNS; sort by end_time, appraisal, length, age or score; and page through the results with offset and limit."
+    )]
     async fn search_expired_domains(
         &self,
-        Parameters(ExpiredDomainsParam { keywords, tlds }): Parameters<ExpiredDomainsParam>,
+        Parameters(ExpiredDomainsParam {
+            keywords,
+            tlds,
+            limit,
+            offset,
+            sort_by,
+            order,
+            min_length,
+            max_length,
+            no_hyphens,
+            no_digits,
+            dictionary_words_only,
+            pattern,
+            min_age_days,
+            max_price,
+            has_dns,
+        }): Parameters<ExpiredDomainsParam>,
     ) -> Result<CallToolResult, McpError> {
-        // If keywords provided, use the first one (matching Python behavior)
-        let keyword = keywords.first().map(|s| s.as_str()).unwrap_or("");
+        let pattern = match pattern.as_deref().map(regex::Regex::new).transpose() {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                return Err(McpError::invalid_params(
+                    "invalid_expired_search",
+                    Some(json!({ "error": format!("Invalid pattern: {}", e) })),
+                ))
+            }
+        };
+        let filters = tools::expired::ExpiredFilters {
+            min_length,
+            max_length,
+            no_hyphens: no_hyphens.unwrap_or(false),
+            no_digits: no_digits.unwrap_or(false),
+            dictionary_words_only: dictionary_words_only.unwrap_or(false),
+            pattern,
+            min_age_days,
+            max_price,
+            has_dns,
+        };
 
-        // If TLDs provided, use the first one (matching Python behavior)
-        let tld = tlds
-            .as_ref()
-            .and_then(|t| t.first())
-            .map(|s| s.as_str())
-            .unwrap_or("");
+        let options = match tools::expired::ExpiredSearchOptions::new(
+            filters,
+            sort_by.as_deref(),
+            order.as_deref(),
+            offset.unwrap_or(0),
+            limit,
+        ) {
+            Ok(options) => options,
+            Err(e) => {
+                return Err(McpError::invalid_params(
+                    "invalid_expired_search",
+                    Some(json!({ "error": e.to_string() })),
+                ))
+            }
+        };
 
-        match tools::expired::search_expired_domains(keyword, tld).await {
+        match tools::expired::search_expired_domains(&keywords, &tlds.unwrap_or_default(), &options)
+            .await
+        {
             Ok(result) => {
                 let text = serde_json::to_string_pretty(&result)
                     .unwrap_or_else(|_| "Error formatting result".to_string());
@@ -145,6 +631,95 @@ impl DomainServer {
         }
     }
 
+    #[tool(
+        description = "Import a drop list from a CSV or text file in the server's import directory. It is cached and searched by search_expired_domains alongside the built-in sources; importing the same name again replaces it"
+    )]
+    async fn import_drop_list(
+        &self,
+        Parameters(ImportDropListParam { name, path }): Parameters<ImportDropListParam>,
+    ) -> Result<CallToolResult, McpError> {
+        match tools::expired::import_drop_list(&name, &path).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::invalid_params(
+                "invalid_drop_list",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Add a domain you want to acquire to the watchlist, update its note, or remove it. The watchlist is kept on disk; use check_watches to re-check the watched domains"
+    )]
+    async fn watch_domain(
+        &self,
+        Parameters(WatchDomainParam {
+            domain,
+            note,
+            remove,
+        }): Parameters<WatchDomainParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let store = tools::watchlist::WatchStore::from_env();
+        let result = if remove.unwrap_or(false) {
+            tools::watchlist::unwatch(&store, &domain)
+                .await
+                .map(|removed| json!({ "domain": domain, "removed": removed }))
+        } else {
+            tools::watchlist::watch(&store, &domain, note)
+                .await
+                .and_then(|watch| Ok(serde_json::to_value(watch)?))
+        };
+
+        match result {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::invalid_params(
+                "invalid_watch",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(description = "List watched domains with their status as of the last check")]
+    async fn list_watches(&self) -> Result<CallToolResult, McpError> {
+        let store = tools::watchlist::WatchStore::from_env();
+        match tools::watchlist::list(&store).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "list_watches_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Re-check every watched domain's lifecycle status via RDAP (whois as fallback) and its presence on drop lists, and report what changed since the last check, e.g. a domain entering pending delete or appearing on a drop list"
+    )]
+    async fn check_watches(&self) -> Result<CallToolResult, McpError> {
+        let store = tools::watchlist::WatchStore::from_env();
+        match tools::watchlist::check(&store).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "check_watches_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
     #[tool(description = "Check the age of a domain")]
     async fn domain_age_check(
         &self,
@@ -163,13 +738,49 @@ impl DomainServer {
         }
     }
 
-    #[tool(description = "Check availability of multiple domains at once")]
+    #[tool(
+        description = "Check availability of multiple domains at once. When the request carries a progress token, a progress notification is sent as each domain completes, with that domain's result as JSON in the message. Use offset and limit to check long lists page by page."
+    )]
     async fn bulk_domain_check(
         &self,
-        Parameters(DomainsParam { domains }): Parameters<DomainsParam>,
+        Parameters(DomainsParam {
+            domains,
+            offset,
+            limit,
+        }): Parameters<DomainsParam>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        match tools::domain::bulk_check(domains).await {
-            Ok(result) => {
+        let (domains, page) = if offset.is_some() || limit.is_some() {
+            match tools::domain::paginate(domains, offset.unwrap_or(0), limit) {
+                Ok((domains, page)) => (domains, Some(page)),
+                Err(e) => {
+                    return Err(McpError::invalid_params(
+                        "invalid_page",
+                        Some(json!({ "error": e.to_string() })),
+                    ))
+                }
+            }
+        } else {
+            (domains, None)
+        };
+
+        let (progress, forwarder) = match context.meta.get_progress_token() {
+            Some(token) => {
+                let (sender, forwarder) = forward_progress(context.peer.clone(), token);
+                (Some(sender), Some(forwarder))
+            }
+            None => (None, None),
+        };
+
+        let result = tools::domain::bulk_check_with_progress(domains, progress, &context.ct).await;
+        if let Some(forwarder) = forwarder {
+            // Flush outstanding notifications before the final response.
+            let _ = forwarder.await;
+        }
+
+        match result {
+            Ok(mut result) => {
+                result.page = page;
                 let text = serde_json::to_string_pretty(&result)
                     .unwrap_or_else(|_| "Error formatting result".to_string());
                 Ok(CallToolResult::success(vec![Content::text(text)]))
@@ -181,6 +792,69 @@ impl DomainServer {
         }
     }
 
+    #[tool(
+        description = "Suggest available domain names from seed keywords using prefixes, suffixes, TLD swaps, hyphenation, plurals, blends and synonyms"
+    )]
+    async fn suggest_domains(
+        &self,
+        Parameters(SuggestDomainsParam {
+            keywords,
+            tlds,
+            limit,
+            max_candidates,
+        }): Parameters<SuggestDomainsParam>,
+    ) -> Result<CallToolResult, McpError> {
+        match tools::suggest::suggest_domains(&keywords, tlds.as_deref(), limit, max_candidates)
+            .await
+        {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "suggest_domains_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Check a single name (e.g. \"acme\") across many TLDs, given as an explicit list or a named group, with per-registry rate limiting"
+    )]
+    async fn check_name_across_tlds(
+        &self,
+        Parameters(NameAcrossTldsParam {
+            name,
+            tlds,
+            tld_set,
+        }): Parameters<NameAcrossTldsParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let (name, tld_set) = match tools::domain::parse_label(&name).and_then(|name| {
+            tools::tlds::TldSet::from_params(tlds, tld_set.as_deref()).map(|set| (name, set))
+        }) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                return Err(McpError::invalid_params(
+                    "invalid_name_across_tlds",
+                    Some(json!({ "error": e.to_string() })),
+                ))
+            }
+        };
+
+        match tools::domain::check_name_across_tlds(&name, &tld_set).await {
+            Ok(result) => {
+                let text = serde_json::to_string_pretty(&result)
+                    .unwrap_or_else(|_| "Error formatting result".to_string());
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Err(McpError::internal_error(
+                "name_across_tlds_failed",
+                Some(json!({ "error": e.to_string() })),
+            )),
+        }
+    }
+
     #[tool(description = "Get all DNS records for a domain")]
     async fn get_dns_records(
         &self,
@@ -200,6 +874,31 @@ impl DomainServer {
     }
 }
 
+/// Relay bulk check progress to the client as `notifications/progress`. The
+/// returned task finishes once the sender is dropped and the queue is drained.
+fn forward_progress(
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+) -> (UnboundedSender<tools::domain::BulkProgress>, JoinHandle<()>) {
+    let (sender, mut receiver) = unbounded_channel::<tools::domain::BulkProgress>();
+    let forwarder = tokio::spawn(async move {
+        while let Some(progress) = receiver.recv().await {
+            let notification = ProgressNotificationParam {
+                progress_token: token.clone(),
+                progress: progress.completed as f64,
+                total: Some(progress.total as f64),
+                message: serde_json::to_string(&progress.result).ok(),
+            };
+            if let Err(e) = peer.notify_progress(notification).await {
+                tracing::debug!("Failed to send progress notification: {}", e);
+                break;
+            }
+        }
+    });
+
+    (sender, forwarder)
+}
+
 #[tool_handler]
 impl ServerHandler for DomainServer {
     fn get_info(&self) -> ServerInfo {
@@ -214,7 +913,11 @@ impl ServerHandler for DomainServer {
                 "Domain MCP Server - Tools for domain name analysis and availability checking. \
                 Available tools: whois_lookup, dns_lookup, check_domain_availability, \
                 ssl_certificate_info, search_expired_domains, domain_age_check, \
-                bulk_domain_check, get_dns_records"
+                bulk_domain_check, get_dns_records, suggest_domains, \
+                check_name_across_tlds, analyze_certificate_chain, \
+                tls_scan, ct_search, caa_check, \
+                certificate_expiry_report, http_probe, hsts_preload_check, \
+                import_drop_list, watch_domain, list_watches, check_watches"
                     .to_string(),
             ),
         }
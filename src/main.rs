@@ -15,6 +15,7 @@ async fn main() -> Result<()> {
     tracing::info!("Starting Domain MCP Server");
 
     let controller = DomainServer::new();
+    let _refresh = domain_mcp::tools::expired::spawn_drop_list_refresh();
 
     let service = controller.serve(stdio()).await.inspect_err(|e| {
         tracing::error!("serving error: {:?}", e);
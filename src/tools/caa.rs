@@ -0,0 +1,747 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use super::cert_chain::Endpoint;
+use super::ct::{self, CtSource};
+use super::dns;
+use super::ssl;
+use super::starttls::Protocol;
+
+/// CAA property tags a CA is expected to understand (RFC 8659, RFC 8657 and
+/// the CA/B Forum registry). A critical record with any other tag forbids
+/// issuance outright.
+const KNOWN_TAGS: &[&str] = &[
+    "issue",
+    "issuewild",
+    "iodef",
+    "contactemail",
+    "contactphone",
+    "issuemail",
+    "issuevmc",
+];
+
+/// CAA identifiers published by the larger public CAs, keyed by a fragment
+/// of the issuer DN. The first identifier is the one to recommend.
+const CA_IDENTIFIERS: &[(&str, &[&str])] = &[
+    ("let's encrypt", &["letsencrypt.org"]),
+    ("google trust services", &["pki.goog"]),
+    (
+        "digicert",
+        &[
+            "digicert.com",
+            "www.digicert.com",
+            "symantec.com",
+            "geotrust.com",
+            "rapidssl.com",
+            "thawte.com",
+        ],
+    ),
+    ("geotrust", &["digicert.com", "geotrust.com"]),
+    ("rapidssl", &["digicert.com", "rapidssl.com"]),
+    ("thawte", &["digicert.com", "thawte.com"]),
+    ("zerossl", &["sectigo.com"]),
+    (
+        "sectigo",
+        &["sectigo.com", "comodoca.com", "comodo.com", "usertrust.com"],
+    ),
+    ("comodo", &["sectigo.com", "comodoca.com", "comodo.com"]),
+    (
+        "amazon",
+        &[
+            "amazon.com",
+            "amazontrust.com",
+            "awstrust.com",
+            "amazonaws.com",
+        ],
+    ),
+    ("globalsign", &["globalsign.com"]),
+    ("entrust", &["entrust.net", "affirmtrust.com"]),
+    ("ssl corporation", &["ssl.com"]),
+    ("ssl.com", &["ssl.com"]),
+    ("buypass", &["buypass.com", "buypass.no"]),
+    ("certainly", &["certainly.com"]),
+    ("godaddy", &["godaddy.com", "starfieldtech.com"]),
+    ("starfield", &["starfieldtech.com", "godaddy.com"]),
+    ("actalis", &["actalis.it"]),
+    ("harica", &["harica.gr"]),
+    ("hellenic academic", &["harica.gr"]),
+];
+
+/// One CAA resource record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaaRecord {
+    pub flags: u8,
+    pub tag: String,
+    pub value: String,
+}
+
+impl CaaRecord {
+    /// Parse DoH answer data, either in presentation form
+    /// (`0 issue "letsencrypt.org"`) or RFC 3597 generic form (`\# 22 00 05 ...`).
+    pub fn parse(data: &str) -> Result<Self> {
+        let data = data.trim();
+        if let Some(generic) = data.strip_prefix("\\#") {
+            return Self::parse_generic(generic);
+        }
+
+        let mut parts = data.splitn(3, char::is_whitespace);
+        let flags = parts
+            .next()
+            .and_then(|flags| flags.parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("Bad CAA flags in '{}'", data))?;
+        let tag = parts
+            .next()
+            .filter(|tag| !tag.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing CAA tag in '{}'", data))?;
+        let value = parts.next().unwrap_or("").trim();
+        let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+            None => value.to_string(),
+        };
+
+        Ok(CaaRecord {
+            flags,
+            tag: tag.to_lowercase(),
+            value,
+        })
+    }
+
+    fn parse_generic(generic: &str) -> Result<Self> {
+        let mut tokens = generic.split_whitespace();
+        let length: usize = tokens
+            .next()
+            .and_then(|length| length.parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("Bad generic CAA length"))?;
+        let hex: String = tokens.collect();
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| anyhow::anyhow!("Bad hex in generic CAA record"))
+            })
+            .collect::<Result<Vec<u8>>>()?;
+        if bytes.len() != length || bytes.len() < 2 {
+            return Err(anyhow::anyhow!("Generic CAA record has the wrong length"));
+        }
+
+        let tag_len = bytes[1] as usize;
+        let tag = bytes
+            .get(2..2 + tag_len)
+            .ok_or_else(|| anyhow::anyhow!("Generic CAA tag runs past the record"))?;
+        Ok(CaaRecord {
+            flags: bytes[0],
+            tag: String::from_utf8_lossy(tag).to_lowercase(),
+            value: String::from_utf8_lossy(&bytes[2 + tag_len..]).into_owned(),
+        })
+    }
+
+    /// The issuer critical flag (bit 0, i.e. 128).
+    pub fn critical(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    /// The CA identifier of an issue/issuewild value; empty means "no CA".
+    fn identifier(&self) -> String {
+        self.value
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .trim_end_matches('.')
+            .to_lowercase()
+    }
+
+    /// Whether the value carries parameters such as accounturi.
+    fn has_parameters(&self) -> bool {
+        self.value
+            .split_once(';')
+            .is_some_and(|(_, params)| !params.trim().is_empty())
+    }
+}
+
+/// The CAA RRset that governs a name: the closest one found walking up the tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaaRrset {
+    pub name: String,
+    pub records: Vec<CaaRecord>,
+}
+
+/// Whether the issuing CA may issue for one certificate name.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaaNameCheck {
+    pub name: String,
+    /// Where the relevant RRset was found; absent when no CAA exists up to the TLD.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relevant_name: Option<String>,
+    /// The property that decided: "issue" or "issuewild".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub property: Option<String>,
+    /// CA identifiers that property authorizes.
+    pub authorized: Vec<String>,
+    /// None when the issuer's CAA identifier is not known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed: Option<bool>,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaaReport {
+    pub domain: String,
+    /// Issuer DN of the certificate the server presents.
+    pub issuer: String,
+    /// CAA identifiers that issuer answers to.
+    pub issuer_identifiers: Vec<String>,
+    /// One entry per DNS name in the certificate.
+    pub names: Vec<CaaNameCheck>,
+    /// Each distinct RRset that applies to one of the names.
+    pub rrsets: Vec<CaaRrset>,
+    /// Where CAs report refused requests (iodef URLs).
+    pub iodef: Vec<String>,
+    /// True when CAA forbids the current CA for any name, i.e. a renewal
+    /// with the same CA will be refused.
+    pub renewal_blocked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<CaaRecommendation>,
+}
+
+/// A CAA RRset that allows every CA seen issuing for the domain in CT.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaaRecommendation {
+    /// Zone file lines for the domain.
+    pub records: Vec<String>,
+    /// CT certificates, valid within the last year, the records are based on.
+    pub certificates_considered: usize,
+    /// Issuers whose CAA identifier is not known; add them by hand.
+    pub unknown_issuers: Vec<String>,
+}
+
+/// Where CAA records come from.
+#[async_trait]
+pub trait CaaResolver: Send + Sync {
+    /// The CAA records at exactly `name` (empty when there are none).
+    async fn records(&self, name: &str) -> Result<Vec<CaaRecord>>;
+}
+
+/// Looks CAA up over DNS-over-HTTPS.
+pub struct DohResolver;
+
+#[async_trait]
+impl CaaResolver for DohResolver {
+    async fn records(&self, name: &str) -> Result<Vec<CaaRecord>> {
+        dns::query(name, "CAA", 257)
+            .await?
+            .iter()
+            .map(|data| CaaRecord::parse(data))
+            .collect()
+    }
+}
+
+/// CAA identifiers for an issuer DN, or None for a CA we don't know.
+pub fn issuer_identifiers(issuer: &str) -> Option<&'static [&'static str]> {
+    let issuer = issuer.to_lowercase();
+    CA_IDENTIFIERS
+        .iter()
+        .find(|(fragment, _)| issuer.contains(fragment))
+        .map(|(_, identifiers)| *identifiers)
+}
+
+/// Check the certificate `domain` serves on port 443 against its CAA records,
+/// optionally recommending an RRset built from the CAs in `ct_source`.
+pub async fn check(domain: &str, ct_source: Option<&dyn CtSource>) -> Result<CaaReport> {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+    let certificate =
+        ssl::get_certificate_info(&Endpoint::new(&domain, 443, Protocol::Tls)).await?;
+
+    let mut names = certificate.san_domains.clone();
+    if !names.iter().any(|name| name.eq_ignore_ascii_case(&domain)) {
+        names.insert(0, domain.clone());
+    }
+
+    let mut report = evaluate(&domain, &certificate.issuer.text, &names, &DohResolver).await;
+    if let Some(source) = ct_source {
+        let found = ct::search(&domain, source, usize::MAX).await?;
+        report.recommendation = Some(recommend(&report, &found.certificates, Utc::now()));
+    }
+    Ok(report)
+}
+
+/// Decide, for each name, whether CAA lets the CA behind `issuer` issue for it.
+pub async fn evaluate(
+    domain: &str,
+    issuer: &str,
+    names: &[String],
+    resolver: &dyn CaaResolver,
+) -> CaaReport {
+    let identifiers = issuer_identifiers(issuer).unwrap_or(&[]);
+    let mut cache: HashMap<String, Vec<CaaRecord>> = HashMap::new();
+    let mut rrsets: Vec<CaaRrset> = Vec::new();
+    let mut checks = Vec::new();
+
+    for name in names {
+        let name = name.trim_end_matches('.').to_lowercase();
+        let check = match relevant_rrset(&name, resolver, &mut cache).await {
+            Ok(rrset) => {
+                let check = check_name(&name, rrset.as_ref(), identifiers);
+                if let Some(rrset) = rrset {
+                    if !rrsets.contains(&rrset) {
+                        rrsets.push(rrset);
+                    }
+                }
+                check
+            }
+            // A CA must treat a failed lookup as a refusal.
+            Err(e) => CaaNameCheck {
+                name,
+                relevant_name: None,
+                property: None,
+                authorized: Vec::new(),
+                allowed: Some(false),
+                reason: format!("CAA lookup failed, so CAs will refuse to issue: {}", e),
+            },
+        };
+        checks.push(check);
+    }
+
+    let iodef = rrsets
+        .iter()
+        .flat_map(|rrset| &rrset.records)
+        .filter(|record| record.tag == "iodef")
+        .map(|record| record.value.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    CaaReport {
+        domain: domain.to_string(),
+        issuer: issuer.to_string(),
+        issuer_identifiers: identifiers.iter().map(|id| id.to_string()).collect(),
+        renewal_blocked: checks.iter().any(|check| check.allowed == Some(false)),
+        names: checks,
+        rrsets,
+        iodef,
+        recommendation: None,
+    }
+}
+
+/// Walk from the name (the base domain, for a wildcard) towards the TLD and
+/// return the first non-empty CAA RRset.
+async fn relevant_rrset(
+    name: &str,
+    resolver: &dyn CaaResolver,
+    cache: &mut HashMap<String, Vec<CaaRecord>>,
+) -> Result<Option<CaaRrset>> {
+    let mut current = name.trim_start_matches("*.");
+    loop {
+        let records = match cache.get(current) {
+            Some(records) => records.clone(),
+            None => {
+                let records = resolver.records(current).await?;
+                cache.insert(current.to_string(), records.clone());
+                records
+            }
+        };
+        if !records.is_empty() {
+            return Ok(Some(CaaRrset {
+                name: current.to_string(),
+                records,
+            }));
+        }
+        match current.split_once('.') {
+            Some((_, parent)) if !parent.is_empty() => current = parent,
+            _ => return Ok(None),
+        }
+    }
+}
+
+fn check_name(name: &str, rrset: Option<&CaaRrset>, identifiers: &[&str]) -> CaaNameCheck {
+    let mut check = CaaNameCheck {
+        name: name.to_string(),
+        relevant_name: rrset.map(|rrset| rrset.name.clone()),
+        property: None,
+        authorized: Vec::new(),
+        allowed: Some(true),
+        reason: String::new(),
+    };
+
+    let Some(rrset) = rrset else {
+        check.reason = "No CAA records up to the TLD; any CA may issue".to_string();
+        return check;
+    };
+
+    if let Some(record) = rrset
+        .records
+        .iter()
+        .find(|record| record.critical() && !KNOWN_TAGS.contains(&record.tag.as_str()))
+    {
+        check.allowed = Some(false);
+        check.reason = format!(
+            "Critical CAA property '{}' at {} is not understood by CAs, so none may issue",
+            record.tag, rrset.name
+        );
+        return check;
+    }
+
+    // Wildcards use issuewild when present and fall back to issue.
+    let wildcard = name.starts_with("*.");
+    let property = if wildcard && rrset.records.iter().any(|r| r.tag == "issuewild") {
+        "issuewild"
+    } else {
+        "issue"
+    };
+    let properties: Vec<&CaaRecord> = rrset
+        .records
+        .iter()
+        .filter(|record| record.tag == property)
+        .collect();
+    if properties.is_empty() {
+        check.reason = format!(
+            "CAA at {} has no {} property; any CA may issue",
+            rrset.name, property
+        );
+        return check;
+    }
+
+    check.property = Some(property.to_string());
+    check.authorized = properties
+        .iter()
+        .map(|record| record.identifier())
+        .filter(|id| !id.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    if identifiers.is_empty() {
+        check.allowed = None;
+        check.reason = format!(
+            "The issuer's CAA identifier is not known; compare it with {} at {}",
+            property, rrset.name
+        );
+        return check;
+    }
+
+    match properties
+        .iter()
+        .find(|record| identifiers.contains(&record.identifier().as_str()))
+    {
+        Some(record) if record.has_parameters() => {
+            check.reason = format!(
+                "{} at {} allows the issuer with parameters ({}); make sure they match the account used",
+                property,
+                rrset.name,
+                record.value.split_once(';').map(|(_, p)| p.trim()).unwrap_or("")
+            );
+        }
+        Some(_) => {
+            check.reason = format!("{} at {} allows the issuer", property, rrset.name);
+        }
+        None if check.authorized.is_empty() => {
+            check.allowed = Some(false);
+            check.reason = format!("{} at {} forbids every CA", property, rrset.name);
+        }
+        None => {
+            check.allowed = Some(false);
+            check.reason = format!(
+                "{} at {} only allows {}",
+                property,
+                rrset.name,
+                check.authorized.join(", ")
+            );
+        }
+    }
+    check
+}
+
+/// Build an RRset for the domain that allows the current issuer and every CA
+/// seen in CT within the last year, keeping existing iodef records.
+fn recommend(
+    report: &CaaReport,
+    certificates: &[ct::CtCertificate],
+    now: DateTime<Utc>,
+) -> CaaRecommendation {
+    let recent: Vec<&ct::CtCertificate> = certificates
+        .iter()
+        .filter(|cert| cert.not_after >= now - Duration::days(365))
+        .collect();
+
+    let mut issue = BTreeSet::new();
+    let mut issuewild = BTreeSet::new();
+    let mut unknown_issuers = BTreeSet::new();
+    if let Some(id) = report.issuer_identifiers.first() {
+        issue.insert(id.clone());
+    }
+    if report.issuer_identifiers.is_empty() {
+        unknown_issuers.insert(report.issuer.clone());
+    }
+    for cert in &recent {
+        match issuer_identifiers(&cert.issuer).and_then(|ids| ids.first()) {
+            Some(id) => {
+                issue.insert(id.to_string());
+                if cert.names.iter().any(|name| name.starts_with("*.")) {
+                    issuewild.insert(id.to_string());
+                }
+            }
+            None => {
+                unknown_issuers.insert(cert.issuer.clone());
+            }
+        }
+    }
+    if report
+        .names
+        .iter()
+        .any(|check| check.name.starts_with("*."))
+    {
+        if let Some(id) = report.issuer_identifiers.first() {
+            issuewild.insert(id.clone());
+        }
+    }
+
+    let owner = format!("{}.", report.domain);
+    let mut records: Vec<String> = issue
+        .iter()
+        .map(|id| format!("{} CAA 0 issue \"{}\"", owner, id))
+        .collect();
+    // issuewild replaces issue for wildcards, so it must list every wildcard CA.
+    records.extend(
+        issuewild
+            .iter()
+            .map(|id| format!("{} CAA 0 issuewild \"{}\"", owner, id)),
+    );
+    records.extend(
+        report
+            .iodef
+            .iter()
+            .map(|url| format!("{} CAA 0 iodef \"{}\"", owner, url)),
+    );
+
+    CaaRecommendation {
+        records,
+        certificates_considered: recent.len(),
+        unknown_issuers: unknown_issuers.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LETS_ENCRYPT: &str = "C=US, O=Let's Encrypt, CN=R11";
+
+    struct StaticResolver(HashMap<&'static str, Vec<&'static str>>);
+
+    #[async_trait]
+    impl CaaResolver for StaticResolver {
+        async fn records(&self, name: &str) -> Result<Vec<CaaRecord>> {
+            match self.0.get(name) {
+                Some(records) if records.first() == Some(&"SERVFAIL") => {
+                    Err(anyhow::anyhow!("SERVFAIL"))
+                }
+                Some(records) => records.iter().map(|data| CaaRecord::parse(data)).collect(),
+                None => Ok(Vec::new()),
+            }
+        }
+    }
+
+    fn resolver(zone: &[(&'static str, &[&'static str])]) -> StaticResolver {
+        StaticResolver(
+            zone.iter()
+                .map(|(name, records)| (*name, records.to_vec()))
+                .collect(),
+        )
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_presentation_and_generic_records() {
+        assert_eq!(
+            CaaRecord::parse("0 issue \"letsencrypt.org; accounturi=https://acme/acct/1\"")
+                .unwrap(),
+            CaaRecord {
+                flags: 0,
+                tag: "issue".to_string(),
+                value: "letsencrypt.org; accounturi=https://acme/acct/1".to_string(),
+            }
+        );
+
+        // 0 issue "pki.goog" in RFC 3597 form.
+        let generic = CaaRecord::parse("\\# 15 00 05 6973737565 706b692e676f6f67").unwrap();
+        assert_eq!(generic.tag, "issue");
+        assert_eq!(generic.value, "pki.goog");
+        assert!(!generic.critical());
+
+        assert!(CaaRecord::parse("128 tbs \"x\"").unwrap().critical());
+        assert!(CaaRecord::parse("\\# 9 00 05 6973737565").is_err());
+    }
+
+    #[tokio::test]
+    async fn closest_rrset_decides() {
+        let zone = resolver(&[
+            (
+                "example.com",
+                &[
+                    "0 issue \"letsencrypt.org\"",
+                    "0 iodef \"mailto:sec@example.com\"",
+                ],
+            ),
+            ("shop.example.com", &["0 issue \"digicert.com\""]),
+        ]);
+        let report = evaluate(
+            "example.com",
+            LETS_ENCRYPT,
+            &names(&["example.com", "www.example.com", "shop.example.com"]),
+            &zone,
+        )
+        .await;
+
+        assert_eq!(report.issuer_identifiers, vec!["letsencrypt.org"]);
+        assert_eq!(report.names[0].allowed, Some(true));
+        assert_eq!(
+            report.names[1].relevant_name.as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(report.names[1].allowed, Some(true));
+        assert_eq!(
+            report.names[2].relevant_name.as_deref(),
+            Some("shop.example.com")
+        );
+        assert_eq!(report.names[2].allowed, Some(false));
+        assert_eq!(report.names[2].authorized, vec!["digicert.com"]);
+        assert_eq!(report.rrsets.len(), 2);
+        assert_eq!(report.iodef, vec!["mailto:sec@example.com"]);
+        assert!(report.renewal_blocked);
+    }
+
+    #[tokio::test]
+    async fn wildcards_prefer_issuewild() {
+        let zone = resolver(&[(
+            "example.com",
+            &["0 issue \"letsencrypt.org\"", "0 issuewild \";\""],
+        )]);
+        let report = evaluate(
+            "example.com",
+            LETS_ENCRYPT,
+            &names(&["example.com", "*.example.com"]),
+            &zone,
+        )
+        .await;
+
+        assert_eq!(report.names[0].allowed, Some(true));
+        assert_eq!(report.names[1].property.as_deref(), Some("issuewild"));
+        assert_eq!(report.names[1].allowed, Some(false));
+        assert!(report.names[1].reason.contains("forbids every CA"));
+
+        // Without issuewild, wildcards fall back to issue.
+        let zone = resolver(&[("example.com", &["0 issue \"letsencrypt.org\""])]);
+        let report = evaluate(
+            "example.com",
+            LETS_ENCRYPT,
+            &names(&["*.example.com"]),
+            &zone,
+        )
+        .await;
+        assert_eq!(report.names[0].property.as_deref(), Some("issue"));
+        assert!(!report.renewal_blocked);
+    }
+
+    #[tokio::test]
+    async fn no_records_critical_tags_and_failures() {
+        let report = evaluate(
+            "example.com",
+            LETS_ENCRYPT,
+            &names(&["example.com"]),
+            &resolver(&[("com", &["0 iodef \"mailto:x@example.net\""])]),
+        )
+        .await;
+        // An RRset with only iodef restricts nothing.
+        assert_eq!(report.names[0].relevant_name.as_deref(), Some("com"));
+        assert_eq!(report.names[0].allowed, Some(true));
+
+        let report = evaluate(
+            "example.com",
+            LETS_ENCRYPT,
+            &names(&["example.com"]),
+            &resolver(&[(
+                "example.com",
+                &["0 issue \"letsencrypt.org\"", "128 tbs \"x\""],
+            )]),
+        )
+        .await;
+        assert_eq!(report.names[0].allowed, Some(false));
+
+        let report = evaluate(
+            "example.com",
+            LETS_ENCRYPT,
+            &names(&["a.example.com"]),
+            &resolver(&[("example.com", &["SERVFAIL"])]),
+        )
+        .await;
+        assert_eq!(report.names[0].allowed, Some(false));
+        assert!(report.renewal_blocked);
+
+        let report = evaluate(
+            "example.com",
+            "CN=Internal Issuing CA",
+            &names(&["example.com"]),
+            &resolver(&[("example.com", &["0 issue \"letsencrypt.org\""])]),
+        )
+        .await;
+        assert_eq!(report.names[0].allowed, None);
+        assert!(!report.renewal_blocked);
+    }
+
+    #[tokio::test]
+    async fn recommends_every_ca_seen() {
+        let now = Utc::now();
+        let cert = |issuer: &str, names: &[&str], not_after: DateTime<Utc>| ct::CtCertificate {
+            id: None,
+            issuer: issuer.to_string(),
+            common_name: None,
+            names: names.iter().map(|n| n.to_string()).collect(),
+            serial_number: "01".to_string(),
+            not_before: not_after - Duration::days(90),
+            not_after,
+            logged_at: None,
+        };
+        let certificates = vec![
+            cert(LETS_ENCRYPT, &["example.com"], now + Duration::days(30)),
+            cert(
+                "C=US, O=Google Trust Services, CN=WR1",
+                &["*.example.com"],
+                now,
+            ),
+            cert(
+                "C=US, O=DigiCert Inc, CN=Old",
+                &["example.com"],
+                now - Duration::days(800),
+            ),
+            cert("CN=Corp CA", &["intranet.example.com"], now),
+        ];
+
+        let report = evaluate(
+            "example.com",
+            LETS_ENCRYPT,
+            &names(&["example.com"]),
+            &resolver(&[("example.com", &["0 iodef \"mailto:sec@example.com\""])]),
+        )
+        .await;
+        let recommendation = recommend(&report, &certificates, now);
+
+        assert_eq!(recommendation.certificates_considered, 3);
+        assert_eq!(
+            recommendation.records,
+            vec![
+                "example.com. CAA 0 issue \"letsencrypt.org\"",
+                "example.com. CAA 0 issue \"pki.goog\"",
+                "example.com. CAA 0 issuewild \"pki.goog\"",
+                "example.com. CAA 0 iodef \"mailto:sec@example.com\"",
+            ]
+        );
+        assert_eq!(recommendation.unknown_issuers, vec!["CN=Corp CA"]);
+    }
+}
@@ -0,0 +1,769 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{lookup_host, TcpStream};
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use x509_parser::der_parser::asn1_rs::Header;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use super::ssl::{self, SslCertificateInfo, SubjectAltName};
+use super::starttls::{self, Protocol};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChainAnalysis {
+    pub domain: String,
+    /// Whether the presented chain validates against the Mozilla root store.
+    pub trusted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_error: Option<ValidationError>,
+    pub hostname_matches: bool,
+    /// Certificates in the order the server sent them, leaf first.
+    pub certificates: Vec<ChainCertificate>,
+    /// The path from the leaf towards a root, as far as it could be built.
+    pub path_to_root: Vec<PathStep>,
+    pub issues: Vec<ChainIssue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChainCertificate {
+    pub position: usize,
+    pub self_signed: bool,
+    pub expired: bool,
+    pub not_yet_valid: bool,
+    #[serde(flatten)]
+    pub certificate: SslCertificateInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathStep {
+    pub subject: String,
+    /// Position in the presented chain; absent for a root taken from the trust store.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    pub trust_anchor: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationErrorKind {
+    HostnameMismatch,
+    UnknownCa,
+    Expired,
+    NotYetValid,
+    Revoked,
+    BadSignature,
+    UnsupportedAlgorithm,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationError {
+    pub kind: ValidationErrorKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+pub enum ChainIssue {
+    /// The issuer of the last certificate on the path was neither presented
+    /// nor found in the trust store.
+    MissingIntermediate {
+        issuer: String,
+    },
+    /// The certificate at `position` is not followed by its issuer.
+    WrongOrder {
+        position: usize,
+        expected_issuer: String,
+    },
+    ExpiredCertificate {
+        position: usize,
+        subject: String,
+        not_after: DateTime<Utc>,
+    },
+    NotYetValid {
+        position: usize,
+        subject: String,
+        not_before: DateTime<Utc>,
+    },
+    /// Presented, but not part of the path from the leaf.
+    UnusedCertificate {
+        position: usize,
+        subject: String,
+    },
+    /// A self-signed root was sent along; harmless, but wasted bytes.
+    RootIncluded {
+        position: usize,
+        subject: String,
+    },
+    /// The path ends in a self-signed certificate that is not a trusted root.
+    UntrustedRoot {
+        subject: String,
+    },
+    HostnameMismatch {
+        names: Vec<String>,
+    },
+}
+
+/// Accepts whatever chain the server presents and keeps a copy, so a broken
+/// chain can still be inspected. Handshake signatures are still verified.
+#[derive(Debug)]
+pub(crate) struct ChainRecorder {
+    provider: Arc<CryptoProvider>,
+    presented: Mutex<Presented>,
+}
+
+/// What the server sent during the handshake.
+#[derive(Debug, Default)]
+pub(crate) struct Presented {
+    /// Leaf first.
+    pub chain: Vec<CertificateDer<'static>>,
+    /// Stapled OCSP response; empty when the server didn't staple one.
+    pub ocsp_response: Vec<u8>,
+}
+
+impl ChainRecorder {
+    pub(crate) fn new(provider: Arc<CryptoProvider>) -> Self {
+        ChainRecorder {
+            provider,
+            presented: Mutex::new(Presented::default()),
+        }
+    }
+
+    pub(crate) fn take(&self) -> Presented {
+        std::mem::take(&mut *self.presented.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl ServerCertVerifier for ChainRecorder {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let chain = std::iter::once(end_entity)
+            .chain(intermediates)
+            .map(|cert| cert.clone().into_owned())
+            .collect();
+        *self.presented.lock().unwrap_or_else(|e| e.into_inner()) = Presented {
+            chain,
+            ocsp_response: ocsp_response.to_vec(),
+        };
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+pub(crate) fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+/// The Mozilla root store bundled through `webpki-roots`.
+pub fn web_pki_roots() -> Arc<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    Arc::new(roots)
+}
+
+pub async fn analyze_certificate_chain(domain: &str) -> Result<ChainAnalysis> {
+    let presented = fetch_presented_chain(&Endpoint::new(domain, 443, Protocol::Tls)).await?;
+    analyze_chain(domain, &presented.chain, &web_pki_roots(), Utc::now())
+}
+
+/// Where to open a TLS connection and which name to ask the server for.
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    /// Name sent in SNI and checked against the certificate.
+    pub server_name: String,
+    /// Host name or IP address to connect to.
+    pub host: String,
+    pub port: u16,
+    pub protocol: Protocol,
+}
+
+impl Endpoint {
+    pub fn new(server_name: &str, port: u16, protocol: Protocol) -> Self {
+        Endpoint {
+            server_name: server_name.to_string(),
+            host: server_name.to_string(),
+            port,
+            protocol,
+        }
+    }
+
+    /// Connect to `address` instead of resolving the server name.
+    pub fn with_address(mut self, address: IpAddr) -> Self {
+        self.host = address.to_string();
+        self
+    }
+}
+
+/// Connect to `endpoint`, run the STARTTLS upgrade for its protocol if it has
+/// one, complete a handshake and return the certificates the server
+/// presented, leaf first, whether or not they validate, along with any
+/// stapled OCSP response.
+pub(crate) async fn fetch_presented_chain(endpoint: &Endpoint) -> Result<Presented> {
+    let provider = crypto_provider();
+    let recorder = Arc::new(ChainRecorder::new(provider.clone()));
+
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(recorder.clone())
+        .with_no_client_auth();
+    let connector = TlsConnector::from(Arc::new(config));
+    let server_name = ServerName::try_from(endpoint.server_name.clone())?;
+
+    let mut stream = connect(&endpoint.host, endpoint.port).await?;
+    timeout(HANDSHAKE_TIMEOUT, async {
+        starttls::upgrade(&mut stream, endpoint.protocol, &endpoint.server_name).await?;
+        connector.connect(server_name, stream).await?;
+        Ok::<_, anyhow::Error>(())
+    })
+    .await
+    .map_err(|_| {
+        anyhow::anyhow!(
+            "TLS handshake with {}:{} timed out after {}s",
+            endpoint.host,
+            endpoint.port,
+            HANDSHAKE_TIMEOUT.as_secs()
+        )
+    })??;
+
+    let presented = recorder.take();
+    if presented.chain.is_empty() {
+        return Err(anyhow::anyhow!("Certificate chain is empty"));
+    }
+
+    Ok(presented)
+}
+
+/// Open a TCP connection to the first address of `host` that answers within
+/// the connect timeout.
+async fn connect(host: &str, port: u16) -> Result<TcpStream> {
+    let addresses = timeout(CONNECT_TIMEOUT, lookup_host((host, port)))
+        .await
+        .map_err(|_| anyhow::anyhow!("Resolving {} timed out", host))??;
+
+    let mut last_error = None;
+    for address in addresses {
+        match timeout(CONNECT_TIMEOUT, TcpStream::connect(address)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => last_error = Some(e.to_string()),
+            Err(_) => last_error = Some(format!("timed out after {}s", CONNECT_TIMEOUT.as_secs())),
+        }
+    }
+    Err(match last_error {
+        Some(e) => anyhow::anyhow!("Failed to connect to {}:{}: {}", host, port, e),
+        None => anyhow::anyhow!("No addresses found for {}", host),
+    })
+}
+
+/// Validate `chain` for `domain` the way a browser would. `None` means trusted.
+pub(crate) fn validate_chain(
+    domain: &str,
+    chain: &[CertificateDer<'_>],
+    roots: &Arc<RootCertStore>,
+    now: DateTime<Utc>,
+) -> Option<ValidationError> {
+    let (end_entity, intermediates) = chain.split_first()?;
+
+    let verifier =
+        match WebPkiServerVerifier::builder_with_provider(roots.clone(), crypto_provider()).build()
+        {
+            Ok(verifier) => verifier,
+            Err(e) => {
+                return Some(ValidationError {
+                    kind: ValidationErrorKind::Other,
+                    message: e.to_string(),
+                })
+            }
+        };
+
+    let server_name = match ServerName::try_from(domain.to_string()) {
+        Ok(server_name) => server_name,
+        Err(e) => {
+            return Some(ValidationError {
+                kind: ValidationErrorKind::Other,
+                message: e.to_string(),
+            })
+        }
+    };
+
+    let now = UnixTime::since_unix_epoch(Duration::from_secs(now.timestamp().max(0) as u64));
+    verifier
+        .verify_server_cert(end_entity, intermediates, &server_name, &[], now)
+        .err()
+        .map(|e| ValidationError {
+            kind: validation_error_kind(&e),
+            message: e.to_string(),
+        })
+}
+
+fn validation_error_kind(error: &rustls::Error) -> ValidationErrorKind {
+    let rustls::Error::InvalidCertificate(error) = error else {
+        return ValidationErrorKind::Other;
+    };
+
+    match error {
+        CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. } => {
+            ValidationErrorKind::HostnameMismatch
+        }
+        CertificateError::UnknownIssuer => ValidationErrorKind::UnknownCa,
+        CertificateError::Expired | CertificateError::ExpiredContext { .. } => {
+            ValidationErrorKind::Expired
+        }
+        CertificateError::NotValidYet | CertificateError::NotValidYetContext { .. } => {
+            ValidationErrorKind::NotYetValid
+        }
+        CertificateError::Revoked => ValidationErrorKind::Revoked,
+        CertificateError::BadSignature => ValidationErrorKind::BadSignature,
+        CertificateError::UnsupportedSignatureAlgorithmContext { .. }
+        | CertificateError::UnsupportedSignatureAlgorithmForPublicKeyContext { .. } => {
+            ValidationErrorKind::UnsupportedAlgorithm
+        }
+        _ => ValidationErrorKind::Other,
+    }
+}
+
+struct ParsedCertificate {
+    subject_raw: Vec<u8>,
+    issuer_raw: Vec<u8>,
+    issuer: String,
+    info: SslCertificateInfo,
+}
+
+impl ParsedCertificate {
+    fn parse(der: &[u8]) -> Result<Self> {
+        let (_, cert) = X509Certificate::from_der(der)
+            .map_err(|e| anyhow::anyhow!("Failed to parse certificate: {}", e))?;
+
+        Ok(ParsedCertificate {
+            subject_raw: cert.subject().as_raw().to_vec(),
+            issuer_raw: cert.issuer().as_raw().to_vec(),
+            issuer: cert.issuer().to_string(),
+            info: ssl::parse_x509_certificate(der)?,
+        })
+    }
+
+    fn self_signed(&self) -> bool {
+        self.subject_raw == self.issuer_raw
+    }
+}
+
+/// Trust anchors store the subject without its outer SEQUENCE header.
+fn name_contents(raw: &[u8]) -> &[u8] {
+    match Header::from_der(raw) {
+        Ok((contents, _)) => contents,
+        Err(_) => raw,
+    }
+}
+
+fn is_trust_anchor(roots: &RootCertStore, name_raw: &[u8]) -> bool {
+    let contents = name_contents(name_raw);
+    roots
+        .roots
+        .iter()
+        .any(|anchor| anchor.subject.as_ref() == contents)
+}
+
+pub fn analyze_chain(
+    domain: &str,
+    chain: &[CertificateDer<'_>],
+    roots: &Arc<RootCertStore>,
+    now: DateTime<Utc>,
+) -> Result<ChainAnalysis> {
+    if chain.is_empty() {
+        return Err(anyhow::anyhow!("Certificate chain is empty"));
+    }
+
+    let parsed = chain
+        .iter()
+        .map(|cert| ParsedCertificate::parse(cert.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut issues = Vec::new();
+
+    for (position, cert) in parsed.iter().enumerate() {
+        if cert.info.not_after < now {
+            issues.push(ChainIssue::ExpiredCertificate {
+                position,
+                subject: cert.info.subject.text.clone(),
+                not_after: cert.info.not_after,
+            });
+        }
+        if cert.info.not_before > now {
+            issues.push(ChainIssue::NotYetValid {
+                position,
+                subject: cert.info.subject.text.clone(),
+                not_before: cert.info.not_before,
+            });
+        }
+    }
+
+    let mut used = vec![false; parsed.len()];
+    used[0] = true;
+    let mut path = vec![PathStep {
+        subject: parsed[0].info.subject.text.clone(),
+        position: Some(0),
+        trust_anchor: false,
+    }];
+    let mut current = 0;
+
+    loop {
+        let cert = &parsed[current];
+
+        if cert.self_signed() {
+            let trusted_root = is_trust_anchor(roots, &cert.subject_raw);
+            if let Some(step) = path.last_mut() {
+                step.trust_anchor = trusted_root;
+            }
+            if !trusted_root {
+                issues.push(ChainIssue::UntrustedRoot {
+                    subject: cert.info.subject.text.clone(),
+                });
+            }
+            break;
+        }
+
+        let issuer = (0..parsed.len()).find(|&candidate| {
+            !used[candidate] && parsed[candidate].subject_raw == cert.issuer_raw
+        });
+
+        if let Some(next) = issuer {
+            if next != current + 1 {
+                issues.push(ChainIssue::WrongOrder {
+                    position: current,
+                    expected_issuer: cert.issuer.clone(),
+                });
+            }
+            used[next] = true;
+            path.push(PathStep {
+                subject: parsed[next].info.subject.text.clone(),
+                position: Some(next),
+                trust_anchor: false,
+            });
+            current = next;
+            continue;
+        }
+
+        if is_trust_anchor(roots, &cert.issuer_raw) {
+            path.push(PathStep {
+                subject: cert.issuer.clone(),
+                position: None,
+                trust_anchor: true,
+            });
+        } else {
+            issues.push(ChainIssue::MissingIntermediate {
+                issuer: cert.issuer.clone(),
+            });
+        }
+        break;
+    }
+
+    for (position, cert) in parsed.iter().enumerate() {
+        if position > 0 && cert.self_signed() {
+            issues.push(ChainIssue::RootIncluded {
+                position,
+                subject: cert.info.subject.text.clone(),
+            });
+        } else if !used[position] {
+            issues.push(ChainIssue::UnusedCertificate {
+                position,
+                subject: cert.info.subject.text.clone(),
+            });
+        }
+    }
+
+    let hostname_matches = matches_hostname(domain, &parsed[0].info);
+    if !hostname_matches {
+        issues.push(ChainIssue::HostnameMismatch {
+            names: parsed[0].info.san_domains.clone(),
+        });
+    }
+
+    let validation_error = validate_chain(domain, chain, roots, now);
+
+    let certificates = parsed
+        .into_iter()
+        .enumerate()
+        .map(|(position, cert)| ChainCertificate {
+            position,
+            self_signed: cert.self_signed(),
+            expired: cert.info.not_after < now,
+            not_yet_valid: cert.info.not_before > now,
+            certificate: cert.info,
+        })
+        .collect();
+
+    Ok(ChainAnalysis {
+        domain: domain.to_string(),
+        trusted: validation_error.is_none(),
+        validation_error,
+        hostname_matches,
+        certificates,
+        path_to_root: path,
+        issues,
+    })
+}
+
+/// RFC 6125 matching: exact names, or a wildcard covering exactly one
+/// leftmost label. The common name is only consulted when there are no SANs.
+pub(crate) fn matches_hostname(domain: &str, leaf: &SslCertificateInfo) -> bool {
+    let domain = domain.trim_end_matches('.').to_lowercase();
+
+    if let Ok(ip) = domain.parse::<IpAddr>() {
+        return leaf.subject_alt_names.iter().any(|name| match name {
+            SubjectAltName::Ip(address) => address.parse::<IpAddr>().ok() == Some(ip),
+            _ => false,
+        });
+    }
+
+    let names: Vec<&str> = if leaf.san_domains.is_empty() {
+        vec![leaf.domain.as_str()]
+    } else {
+        leaf.san_domains.iter().map(String::as_str).collect()
+    };
+
+    names.iter().any(|name| {
+        let name = name.trim_end_matches('.').to_lowercase();
+        match name.strip_prefix("*.") {
+            Some(suffix) => domain
+                .split_once('.')
+                .is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+            None => name == domain,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &[u8] = include_bytes!("../../tests/fixtures/example-root-ca.der");
+    const ISSUING_CA: &[u8] = include_bytes!("../../tests/fixtures/example-issuing-ca.der");
+    const SHOP_LEAF: &[u8] = include_bytes!("../../tests/fixtures/shop-example-com-leaf.der");
+    const EXPIRED_CA: &[u8] = include_bytes!("../../tests/fixtures/expired-legacy-ca.der");
+    const LEGACY_LEAF: &[u8] = include_bytes!("../../tests/fixtures/legacy-example-com-leaf.der");
+
+    fn test_roots() -> Arc<RootCertStore> {
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from(ROOT)).unwrap();
+        Arc::new(roots)
+    }
+
+    fn chain(certs: &[&'static [u8]]) -> Vec<CertificateDer<'static>> {
+        certs.iter().map(|der| CertificateDer::from(*der)).collect()
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2030-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn complete_chain_test() {
+        let analysis = analyze_chain(
+            "shop.example.com",
+            &chain(&[SHOP_LEAF, ISSUING_CA]),
+            &test_roots(),
+            now(),
+        )
+        .unwrap();
+
+        assert!(analysis.trusted);
+        assert!(analysis.validation_error.is_none());
+        assert!(analysis.hostname_matches);
+        assert!(analysis.issues.is_empty());
+        assert_eq!(analysis.certificates.len(), 2);
+        assert_eq!(
+            analysis.path_to_root,
+            vec![
+                PathStep {
+                    subject: "CN=shop.example.com".to_string(),
+                    position: Some(0),
+                    trust_anchor: false,
+                },
+                PathStep {
+                    subject: "C=US, O=Example Trust, CN=Example Issuing CA".to_string(),
+                    position: Some(1),
+                    trust_anchor: false,
+                },
+                PathStep {
+                    subject: "C=US, O=Example Trust, CN=Example Root CA".to_string(),
+                    position: None,
+                    trust_anchor: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_intermediate_test() {
+        let analysis = analyze_chain(
+            "shop.example.com",
+            &chain(&[SHOP_LEAF]),
+            &test_roots(),
+            now(),
+        )
+        .unwrap();
+
+        assert!(!analysis.trusted);
+        assert_eq!(
+            analysis.validation_error.unwrap().kind,
+            ValidationErrorKind::UnknownCa
+        );
+        assert_eq!(
+            analysis.issues,
+            vec![ChainIssue::MissingIntermediate {
+                issuer: "C=US, O=Example Trust, CN=Example Issuing CA".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn wrong_order_and_root_included_test() {
+        let analysis = analyze_chain(
+            "www.shop.example.com",
+            &chain(&[SHOP_LEAF, ROOT, ISSUING_CA]),
+            &test_roots(),
+            now(),
+        )
+        .unwrap();
+
+        assert!(analysis.trusted);
+        assert!(analysis.hostname_matches);
+        assert_eq!(analysis.path_to_root.len(), 3);
+        assert!(analysis.path_to_root[2].trust_anchor);
+        assert!(analysis.issues.contains(&ChainIssue::WrongOrder {
+            position: 0,
+            expected_issuer: "C=US, O=Example Trust, CN=Example Issuing CA".to_string(),
+        }));
+        assert!(analysis.issues.contains(&ChainIssue::RootIncluded {
+            position: 1,
+            subject: "C=US, O=Example Trust, CN=Example Root CA".to_string(),
+        }));
+    }
+
+    #[test]
+    fn expired_intermediate_test() {
+        let analysis = analyze_chain(
+            "legacy.example.com",
+            &chain(&[LEGACY_LEAF, EXPIRED_CA]),
+            &test_roots(),
+            now(),
+        )
+        .unwrap();
+
+        assert!(!analysis.trusted);
+        assert_eq!(
+            analysis.validation_error.unwrap().kind,
+            ValidationErrorKind::Expired
+        );
+        assert!(analysis.certificates[1].expired);
+        assert!(matches!(
+            analysis.issues.as_slice(),
+            [ChainIssue::ExpiredCertificate { position: 1, .. }]
+        ));
+    }
+
+    #[test]
+    fn hostname_mismatch_test() {
+        let analysis = analyze_chain(
+            "example.org",
+            &chain(&[SHOP_LEAF, ISSUING_CA]),
+            &test_roots(),
+            now(),
+        )
+        .unwrap();
+
+        assert!(!analysis.hostname_matches);
+        assert_eq!(
+            analysis.validation_error.unwrap().kind,
+            ValidationErrorKind::HostnameMismatch
+        );
+        assert!(analysis.issues.contains(&ChainIssue::HostnameMismatch {
+            names: vec![
+                "shop.example.com".to_string(),
+                "*.shop.example.com".to_string()
+            ],
+        }));
+    }
+
+    #[test]
+    fn untrusted_root_and_unused_certificate_test() {
+        let analysis = analyze_chain(
+            "shop.example.com",
+            &chain(&[SHOP_LEAF, ISSUING_CA, ROOT, EXPIRED_CA]),
+            &Arc::new(RootCertStore::empty()),
+            now(),
+        )
+        .unwrap();
+
+        assert!(!analysis.trusted);
+        assert!(analysis.issues.contains(&ChainIssue::UntrustedRoot {
+            subject: "C=US, O=Example Trust, CN=Example Root CA".to_string(),
+        }));
+        assert!(analysis.issues.contains(&ChainIssue::UnusedCertificate {
+            position: 3,
+            subject: "C=US, O=Example Trust, CN=Example Legacy CA".to_string(),
+        }));
+    }
+
+    #[test]
+    fn matches_hostname_test() {
+        let leaf = ssl::parse_x509_certificate(SHOP_LEAF).unwrap();
+        assert!(matches_hostname("shop.example.com", &leaf));
+        assert!(matches_hostname("API.shop.example.com.", &leaf));
+        assert!(!matches_hostname("a.b.shop.example.com", &leaf));
+        assert!(!matches_hostname("example.com", &leaf));
+    }
+}
@@ -0,0 +1,695 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashSet};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use x509_parser::certificate::TbsCertificate;
+use x509_parser::extensions::{GeneralName, SignedCertificateTimestamp};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use super::scheduler::Scheduler;
+
+/// Most entries a single log scan will read.
+const MAX_LOG_ENTRIES: u64 = 10_000;
+
+/// Entries scanned from the end of the log when no start index is given.
+const DEFAULT_LOG_ENTRIES: u64 = 1_000;
+
+/// A certificate (or precertificate) found in a CT log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CtCertificate {
+    /// crt.sh certificate ID, or the entry index for RFC 6962 logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub issuer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub common_name: Option<String>,
+    /// Every DNS name the certificate covers, lowercased.
+    pub names: Vec<String>,
+    pub serial_number: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// When the log accepted the entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CtSearchResult {
+    pub domain: String,
+    pub source: String,
+    /// Matching certificates, newest first, up to the requested limit.
+    pub certificates: Vec<CtCertificate>,
+    pub total_certificates: usize,
+    /// Every distinct name at or under the domain seen in any certificate.
+    pub subdomains: Vec<String>,
+}
+
+/// An SCT embedded in a certificate (RFC 6962 section 3.3).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddedSct {
+    pub version: u8,
+    /// Base64 SHA-256 hash of the log's public key, as log lists publish it.
+    pub log_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub signature_algorithm: String,
+}
+
+/// Somewhere to look up logged certificates.
+#[async_trait]
+pub trait CtSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Certificates naming `domain` or any name under it. Sources may return
+    /// extra entries; `search` filters them.
+    async fn certificates(&self, domain: &str) -> Result<Vec<CtCertificate>>;
+}
+
+/// Pick a source by name: "crtsh" (the default), "log" for an RFC 6962 log at
+/// `log_url`, or "jsonl" for the dump named by `DOMAIN_MCP_CT_JSONL`.
+pub fn source_from(
+    name: Option<&str>,
+    log_url: Option<&str>,
+    start: Option<u64>,
+    count: Option<u64>,
+) -> Result<Box<dyn CtSource>> {
+    match name.unwrap_or("crtsh").to_lowercase().as_str() {
+        "crtsh" | "crt.sh" => Ok(Box::new(CrtShSource::new(
+            &std::env::var("DOMAIN_MCP_CT_API_URL")
+                .unwrap_or_else(|_| "https://crt.sh".to_string()),
+        ))),
+        "log" => {
+            let log_url =
+                log_url.ok_or_else(|| anyhow::anyhow!("The log source needs a log_url"))?;
+            Ok(Box::new(LogSource::new(log_url, start, count)))
+        }
+        "jsonl" => {
+            let path = std::env::var("DOMAIN_MCP_CT_JSONL").map_err(|_| {
+                anyhow::anyhow!("Set DOMAIN_MCP_CT_JSONL to the path of a JSONL dump")
+            })?;
+            Ok(Box::new(JsonlSource::new(&path)))
+        }
+        other => Err(anyhow::anyhow!(
+            "Unknown CT source '{}'. Use crtsh, log or jsonl",
+            other
+        )),
+    }
+}
+
+pub async fn search(domain: &str, source: &dyn CtSource, limit: usize) -> Result<CtSearchResult> {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+
+    let mut seen = HashSet::new();
+    let mut certificates: Vec<CtCertificate> = source
+        .certificates(&domain)
+        .await?
+        .into_iter()
+        .filter(|cert| cert.names.iter().any(|name| under_domain(name, &domain)))
+        // crt.sh lists a precertificate and its final certificate separately.
+        .filter(|cert| seen.insert((cert.issuer.clone(), cert.serial_number.clone())))
+        .collect();
+    certificates.sort_by_key(|cert| Reverse(cert.not_before));
+
+    let subdomains: BTreeSet<String> = certificates
+        .iter()
+        .flat_map(|cert| &cert.names)
+        .map(|name| name.trim_start_matches("*.").to_string())
+        .filter(|name| under_domain(name, &domain))
+        .collect();
+
+    let total_certificates = certificates.len();
+    certificates.truncate(limit);
+
+    Ok(CtSearchResult {
+        domain,
+        source: source.name().to_string(),
+        certificates,
+        total_certificates,
+        subdomains: subdomains.into_iter().collect(),
+    })
+}
+
+fn under_domain(name: &str, domain: &str) -> bool {
+    let name = name.trim_start_matches("*.");
+    name == domain || name.ends_with(&format!(".{}", domain))
+}
+
+/// One row of crt.sh's JSON output. JSONL dumps use the same shape.
+#[derive(Debug, Deserialize)]
+struct CrtShEntry {
+    id: Option<u64>,
+    issuer_name: String,
+    common_name: Option<String>,
+    /// Newline separated identities.
+    name_value: String,
+    serial_number: String,
+    not_before: String,
+    not_after: String,
+    entry_timestamp: Option<String>,
+}
+
+impl CrtShEntry {
+    fn into_certificate(self) -> Result<CtCertificate> {
+        let mut names: Vec<String> = self
+            .name_value
+            .lines()
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty() && !name.contains('@'))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        Ok(CtCertificate {
+            id: self.id,
+            issuer: self.issuer_name,
+            common_name: self.common_name,
+            names,
+            serial_number: colon_hex(&self.serial_number),
+            not_before: crtsh_time(&self.not_before)?,
+            not_after: crtsh_time(&self.not_after)?,
+            logged_at: self
+                .entry_timestamp
+                .as_deref()
+                .map(crtsh_time)
+                .transpose()?,
+        })
+    }
+}
+
+fn crtsh_time(text: &str) -> Result<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text.trim_end_matches('Z'), "%Y-%m-%dT%H:%M:%S%.f")
+        .map(|time| time.and_utc())
+        .map_err(|_| anyhow::anyhow!("Invalid timestamp '{}'", text))
+}
+
+/// "0A1B2C" -> "0a:1b:2c", matching `SslCertificateInfo::serial_number`.
+fn colon_hex(hex: &str) -> String {
+    let hex = hex.to_lowercase();
+    let hex = if hex.len() % 2 == 1 {
+        format!("0{}", hex)
+    } else {
+        hex
+    };
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| String::from_utf8_lossy(pair).into_owned())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// crt.sh style JSON search API.
+pub struct CrtShSource {
+    client: Client,
+    base_url: String,
+}
+
+impl CrtShSource {
+    pub fn new(base_url: &str) -> Self {
+        CrtShSource {
+            client: Client::builder()
+                .user_agent("Domain-MCP-Rust/1.0")
+                .timeout(std::time::Duration::from_secs(60))
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl CtSource for CrtShSource {
+    fn name(&self) -> &str {
+        "crt.sh"
+    }
+
+    /// `%.domain` only matches names under the domain, so the domain itself
+    /// is queried too; `search` drops the certificates both queries return.
+    async fn certificates(&self, domain: &str) -> Result<Vec<CtCertificate>> {
+        let (apex, under) = futures::try_join!(
+            self.query(domain.to_string()),
+            self.query(format!("%.{}", domain))
+        )?;
+        Ok(apex.into_iter().chain(under).collect())
+    }
+}
+
+impl CrtShSource {
+    async fn query(&self, pattern: String) -> Result<Vec<CtCertificate>> {
+        let request = self
+            .client
+            .get(format!("{}/", self.base_url))
+            .query(&[("q", pattern), ("output", "json".to_string())])
+            .build()?;
+        let response = Scheduler::global().execute(&self.client, request).await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "CT search returned HTTP {}",
+                response.status()
+            ));
+        }
+
+        let entries: Vec<CrtShEntry> = response.json().await?;
+        entries
+            .into_iter()
+            .map(CrtShEntry::into_certificate)
+            .collect()
+    }
+}
+
+/// A local JSONL dump of crt.sh style rows, for offline use.
+pub struct JsonlSource {
+    path: String,
+}
+
+impl JsonlSource {
+    pub fn new(path: &str) -> Self {
+        JsonlSource {
+            path: path.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl CtSource for JsonlSource {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    async fn certificates(&self, _domain: &str) -> Result<Vec<CtCertificate>> {
+        let file = tokio::fs::File::open(&self.path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", self.path, e))?;
+        let mut lines = BufReader::new(file).lines();
+
+        let mut certificates = Vec::new();
+        let mut line_number = 0;
+        while let Some(line) = lines.next_line().await? {
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: CrtShEntry = serde_json::from_str(&line)
+                .map_err(|e| anyhow::anyhow!("{} line {}: {}", self.path, line_number, e))?;
+            certificates.push(entry.into_certificate()?);
+        }
+        Ok(certificates)
+    }
+}
+
+/// Scans a range of an RFC 6962 log with get-entries.
+pub struct LogSource {
+    client: Client,
+    log_url: String,
+    start: Option<u64>,
+    count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignedTreeHead {
+    tree_size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetEntries {
+    entries: Vec<LogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogEntry {
+    leaf_input: String,
+}
+
+impl LogSource {
+    /// Scan `count` entries from `start`, or the newest `count` entries when
+    /// `start` is `None`.
+    pub fn new(log_url: &str, start: Option<u64>, count: Option<u64>) -> Self {
+        LogSource {
+            client: Client::builder()
+                .user_agent("Domain-MCP-Rust/1.0")
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+            log_url: log_url.trim_end_matches('/').to_string(),
+            start,
+            count: count
+                .unwrap_or(DEFAULT_LOG_ENTRIES)
+                .clamp(1, MAX_LOG_ENTRIES),
+        }
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let request = self
+            .client
+            .get(format!("{}/ct/v1/{}", self.log_url, path))
+            .build()?;
+        let response = Scheduler::global().execute(&self.client, request).await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "CT log returned HTTP {}",
+                response.status()
+            ));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl CtSource for LogSource {
+    fn name(&self) -> &str {
+        &self.log_url
+    }
+
+    async fn certificates(&self, _domain: &str) -> Result<Vec<CtCertificate>> {
+        let head: SignedTreeHead = self.get("get-sth").await?;
+        let start = self
+            .start
+            .unwrap_or_else(|| head.tree_size.saturating_sub(self.count));
+        if start >= head.tree_size {
+            return Err(anyhow::anyhow!(
+                "Start {} is past the end of the log ({} entries)",
+                start,
+                head.tree_size
+            ));
+        }
+        let end = (start + self.count).min(head.tree_size) - 1;
+
+        let mut certificates = Vec::new();
+        let mut next = start;
+        while next <= end {
+            // Logs cap the batch size, so ask for the rest and take what comes.
+            let batch: GetEntries = self
+                .get(&format!("get-entries?start={}&end={}", next, end))
+                .await?;
+            if batch.entries.is_empty() {
+                break;
+            }
+            for entry in &batch.entries {
+                let leaf_input = BASE64.decode(&entry.leaf_input)?;
+                // Entries that aren't certificates are skipped, not fatal.
+                if let Ok(certificate) = parse_leaf_input(next, &leaf_input) {
+                    certificates.push(certificate);
+                }
+                next += 1;
+            }
+        }
+        Ok(certificates)
+    }
+}
+
+/// Decode a MerkleTreeLeaf (RFC 6962 section 3.4) holding an X.509 or
+/// precertificate entry.
+fn parse_leaf_input(index: u64, leaf: &[u8]) -> Result<CtCertificate> {
+    let malformed = || anyhow::anyhow!("Malformed log entry {}", index);
+    if leaf.len() < 12 || leaf[0] != 0 || leaf[1] != 0 {
+        return Err(malformed());
+    }
+    let timestamp = u64::from_be_bytes(leaf[2..10].try_into()?);
+    let entry_type = u16::from_be_bytes([leaf[10], leaf[11]]);
+    let logged_at = DateTime::from_timestamp_millis(timestamp as i64);
+
+    let body = match entry_type {
+        0 => &leaf[12..],
+        // Precertificates carry the issuer key hash before the TBSCertificate.
+        1 => leaf.get(44..).ok_or_else(malformed)?,
+        _ => return Err(anyhow::anyhow!("Unknown entry type {}", entry_type)),
+    };
+    let length = body
+        .get(..3)
+        .map(|length| u32::from_be_bytes([0, length[0], length[1], length[2]]) as usize)
+        .ok_or_else(malformed)?;
+    let der = body.get(3..3 + length).ok_or_else(malformed)?;
+
+    let mut certificate = if entry_type == 0 {
+        let (_, cert) = X509Certificate::from_der(der).map_err(|_| malformed())?;
+        logged_certificate(&cert)?
+    } else {
+        let (_, tbs) = TbsCertificate::from_der(der).map_err(|_| malformed())?;
+        logged_certificate(&tbs)?
+    };
+    certificate.id = Some(index);
+    certificate.logged_at = logged_at;
+    Ok(certificate)
+}
+
+fn logged_certificate(tbs: &TbsCertificate) -> Result<CtCertificate> {
+    let common_name = tbs
+        .subject()
+        .iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .map(|cn| cn.to_lowercase());
+
+    let mut names: Vec<String> = common_name.iter().cloned().collect();
+    if let Ok(Some(san)) = tbs.subject_alternative_name() {
+        for name in &san.value.general_names {
+            if let GeneralName::DNSName(dns) = name {
+                let dns = dns.to_lowercase();
+                if !names.contains(&dns) {
+                    names.push(dns);
+                }
+            }
+        }
+    }
+
+    let timestamp = |time: i64| {
+        DateTime::from_timestamp(time, 0).ok_or_else(|| anyhow::anyhow!("Invalid timestamp"))
+    };
+
+    Ok(CtCertificate {
+        id: None,
+        issuer: tbs.issuer().to_string(),
+        common_name,
+        names,
+        serial_number: tbs.raw_serial_as_string(),
+        not_before: timestamp(tbs.validity().not_before.timestamp())?,
+        not_after: timestamp(tbs.validity().not_after.timestamp())?,
+        logged_at: None,
+    })
+}
+
+pub(crate) fn embedded_scts(scts: &[SignedCertificateTimestamp]) -> Vec<EmbeddedSct> {
+    scts.iter()
+        .map(|sct| EmbeddedSct {
+            version: sct.version.0 + 1,
+            log_id: BASE64.encode(sct.id.key_id),
+            timestamp: DateTime::from_timestamp_millis(sct.timestamp as i64).unwrap_or_default(),
+            signature_algorithm: sct_signature_algorithm(
+                sct.signature.hash_alg_id,
+                sct.signature.sign_alg_id,
+            ),
+        })
+        .collect()
+}
+
+/// TLS HashAlgorithm and SignatureAlgorithm codes (RFC 5246 section 7.4.1.4.1).
+fn sct_signature_algorithm(hash: u8, signature: u8) -> String {
+    let hash = match hash {
+        2 => "SHA1".to_string(),
+        4 => "SHA256".to_string(),
+        5 => "SHA384".to_string(),
+        6 => "SHA512".to_string(),
+        other => format!("hash{}", other),
+    };
+    let signature = match signature {
+        1 => "rsa".to_string(),
+        3 => "ecdsa".to_string(),
+        other => format!("signature{}", other),
+    };
+    format!("{}-with-{}", signature, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEAF: &[u8] = include_bytes!("../../tests/fixtures/example-com-leaf.der");
+    const CT_LEAF: &[u8] = include_bytes!("../../tests/fixtures/ct-example-com-leaf.der");
+
+    fn crtsh_row(id: u64, serial: &str, names: &str, not_before: &str) -> String {
+        serde_json::json!({
+            "issuer_ca_id": 183267,
+            "issuer_name": "C=US, O=Let's Encrypt, CN=R11",
+            "common_name": names.lines().next(),
+            "name_value": names,
+            "id": id,
+            "entry_timestamp": "2025-06-01T10:15:30.123",
+            "not_before": not_before,
+            "not_after": "2099-01-01T00:00:00",
+            "serial_number": serial,
+            "result_count": 2
+        })
+        .to_string()
+    }
+
+    /// Wraps a DER body in a MerkleTreeLeaf of the given entry type.
+    fn leaf_input(entry_type: u16, prefix: &[u8], der: &[u8]) -> Vec<u8> {
+        let mut leaf = vec![0, 0];
+        leaf.extend(1_700_000_000_000u64.to_be_bytes());
+        leaf.extend(entry_type.to_be_bytes());
+        leaf.extend(prefix);
+        leaf.extend(&(der.len() as u32).to_be_bytes()[1..]);
+        leaf.extend(der);
+        leaf.extend([0, 0]);
+        leaf
+    }
+
+    #[test]
+    fn crtsh_entry_test() {
+        let row = crtsh_row(
+            42,
+            "03a1b2c3d4",
+            "example.com\nWWW.example.com\nexample.com\nhostmaster@example.com",
+            "2025-06-01T00:00:00",
+        );
+        let entry: CrtShEntry = serde_json::from_str(&row).unwrap();
+        let cert = entry.into_certificate().unwrap();
+
+        assert_eq!(cert.id, Some(42));
+        assert_eq!(cert.names, vec!["example.com", "www.example.com"]);
+        assert_eq!(cert.serial_number, "03:a1:b2:c3:d4");
+        assert_eq!(cert.not_before.to_rfc3339(), "2025-06-01T00:00:00+00:00");
+        assert_eq!(
+            cert.logged_at.unwrap().to_rfc3339(),
+            "2025-06-01T10:15:30.123+00:00"
+        );
+        assert_eq!(colon_hex("abc"), "0a:bc");
+    }
+
+    #[test]
+    fn parse_leaf_input_test() {
+        let cert = parse_leaf_input(7, &leaf_input(0, &[], LEAF)).unwrap();
+        assert_eq!(cert.id, Some(7));
+        assert_eq!(cert.common_name.as_deref(), Some("example.com"));
+        assert_eq!(
+            cert.names,
+            vec!["example.com", "www.example.com", "*.api.example.com"]
+        );
+        assert_eq!(cert.serial_number, "1a:2b:3c:4d");
+        assert_eq!(cert.issuer, "C=US, O=Example Trust, CN=Example Root CA");
+        assert_eq!(cert.logged_at.unwrap().timestamp(), 1_700_000_000);
+
+        let (_, ct_leaf) = X509Certificate::from_der(CT_LEAF).unwrap();
+        let precert = leaf_input(1, &[0xaa; 32], ct_leaf.tbs_certificate.as_ref());
+        let cert = parse_leaf_input(8, &precert).unwrap();
+        assert_eq!(cert.names, vec!["ct.example.com"]);
+
+        assert!(parse_leaf_input(9, &leaf_input(0, &[], &LEAF[..100])).is_err());
+        assert!(parse_leaf_input(9, &[0, 0, 1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn jsonl_search_test() {
+        let rows = [
+            crtsh_row(
+                1,
+                "01",
+                "example.com\nwww.example.com",
+                "2025-01-01T00:00:00",
+            ),
+            // The precertificate of the same serial is folded into one entry.
+            crtsh_row(
+                2,
+                "01",
+                "example.com\nwww.example.com",
+                "2025-01-01T00:00:00",
+            ),
+            crtsh_row(
+                3,
+                "02",
+                "*.dev.example.com\nshadow.example.com",
+                "2025-03-01T00:00:00",
+            ),
+            crtsh_row(
+                4,
+                "03",
+                "example.org\nnotexample.com",
+                "2025-04-01T00:00:00",
+            ),
+            String::new(),
+        ];
+        let path = std::env::temp_dir().join(format!("ct-search-{}.jsonl", std::process::id()));
+        tokio::fs::write(&path, rows.join("\n")).await.unwrap();
+
+        let source = JsonlSource::new(path.to_str().unwrap());
+        let result = search("Example.com.", &source, 1).await.unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(result.domain, "example.com");
+        assert_eq!(result.source, "jsonl");
+        assert_eq!(result.total_certificates, 2);
+        assert_eq!(result.certificates.len(), 1);
+        assert_eq!(result.certificates[0].id, Some(3));
+        assert_eq!(
+            result.subdomains,
+            vec![
+                "dev.example.com",
+                "example.com",
+                "shadow.example.com",
+                "www.example.com"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn crtsh_search_includes_apex_only_certificates_test() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Like crt.sh, "%.example.com" leaves out a certificate that only
+        // names the apex.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let apex = format!(
+            "[{}]",
+            crtsh_row(1, "01", "example.com", "2025-01-01T00:00:00")
+        );
+        let under = format!(
+            "[{}]",
+            crtsh_row(2, "02", "www.example.com", "2025-02-01T00:00:00")
+        );
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buffer = [0u8; 2048];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+                let body = if request.contains("q=%25.example.com") {
+                    &under
+                } else {
+                    &apex
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let source = CrtShSource::new(&format!("http://{}", addr));
+        let result = search("example.com", &source, 10).await.unwrap();
+        let ids: Vec<Option<u64>> = result.certificates.iter().map(|cert| cert.id).collect();
+        assert_eq!(ids, vec![Some(2), Some(1)]);
+        assert_eq!(result.subdomains, vec!["example.com", "www.example.com"]);
+    }
+
+    #[test]
+    fn source_from_test() {
+        assert_eq!(
+            source_from(None, None, None, None).unwrap().name(),
+            "crt.sh"
+        );
+        assert_eq!(
+            source_from(Some("log"), Some("https://ct.example/log/"), None, None)
+                .unwrap()
+                .name(),
+            "https://ct.example/log"
+        );
+        assert!(source_from(Some("log"), None, None, None).is_err());
+        assert!(source_from(Some("censys"), None, None, None).is_err());
+    }
+}
@@ -3,6 +3,9 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::scheduler::Scheduler;
+use super::ssl::{self, SslCertificateInfo};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DnsLookupResult {
     pub domain: String,
@@ -13,12 +16,22 @@ pub struct DnsLookupResult {
     pub ns_records: Vec<String>,
     pub cname_records: Vec<String>,
     pub soa_record: Option<SoaRecord>,
+    /// With `check_certificates`, true when every MX host presented a
+    /// certificate that validates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mx_certificates_valid: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MxRecord {
     pub priority: u16,
     pub exchange: String,
+    /// The host's SMTP STARTTLS certificate, with `check_certificates`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<SslCertificateInfo>,
+    /// Why the certificate could not be fetched, e.g. no STARTTLS offered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate_error: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,6 +55,8 @@ pub struct DnsRecord {
 
 #[derive(Debug, Deserialize)]
 struct CloudflareAnswer {
+    #[serde(rename = "type")]
+    record_type: Option<u16>,
     data: String,
     #[serde(rename = "TTL")]
     ttl: Option<u32>,
@@ -49,6 +64,8 @@ struct CloudflareAnswer {
 
 #[derive(Debug, Deserialize)]
 struct CloudflareResponse {
+    #[serde(rename = "Status")]
+    status: Option<u32>,
     #[serde(rename = "Answer")]
     answer: Option<Vec<CloudflareAnswer>>,
 }
@@ -63,12 +80,12 @@ async fn cloudflare_dns_lookup(
     params.insert("name", domain);
     params.insert("type", record_type);
 
-    let response = client
+    let request = client
         .get("https://cloudflare-dns.com/dns-query")
         .query(&params)
         .header("Accept", "application/dns-json")
-        .send()
-        .await?;
+        .build()?;
+    let response = Scheduler::global().execute(&client, request).await?;
 
     if response.status().is_success() {
         let dns_response: CloudflareResponse = response.json().await?;
@@ -86,6 +103,56 @@ async fn cloudflare_dns_lookup(
     }
 }
 
+/// Records of one type held at exactly `name`. The resolver follows CNAMEs,
+/// so only answers of `type_code` are kept. NXDOMAIN is an empty answer, but
+/// other failures (e.g. SERVFAIL) are errors rather than "no records".
+pub(crate) async fn query(name: &str, record_type: &str, type_code: u16) -> Result<Vec<String>> {
+    let client = Client::new();
+
+    let request = client
+        .get("https://cloudflare-dns.com/dns-query")
+        .query(&[("name", name), ("type", record_type)])
+        .header("Accept", "application/dns-json")
+        .build()?;
+    let response = Scheduler::global().execute(&client, request).await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "DNS query for {} {} returned HTTP {}",
+            name,
+            record_type,
+            response.status()
+        ));
+    }
+
+    let dns_response: CloudflareResponse = response.json().await?;
+    match dns_response.status.unwrap_or(0) {
+        0 | 3 => {}
+        rcode => {
+            return Err(anyhow::anyhow!(
+                "DNS query for {} {} failed with rcode {}",
+                name,
+                record_type,
+                rcode
+            ))
+        }
+    }
+
+    Ok(dns_response
+        .answer
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|answer| answer.record_type == Some(type_code))
+        .map(|answer| answer.data)
+        .collect())
+}
+
+/// Whether `domain` has NS records and A records, or `None` when either
+/// lookup failed.
+pub(crate) async fn ns_and_a_present(domain: &str) -> Option<(bool, bool)> {
+    let (ns, a) = futures::join!(query(domain, "NS", 2), query(domain, "A", 1));
+    Some((!ns.ok()?.is_empty(), !a.ok()?.is_empty()))
+}
+
 pub async fn lookup(domain: &str) -> Result<DnsLookupResult> {
     let a_records = cloudflare_dns_lookup(domain, "A")
         .await
@@ -110,6 +177,8 @@ pub async fn lookup(domain: &str) -> Result<DnsLookupResult> {
                         Some(MxRecord {
                             priority,
                             exchange: parts[1].to_string(),
+                            certificate: None,
+                            certificate_error: None,
                         })
                     } else {
                         None
@@ -174,9 +243,19 @@ pub async fn lookup(domain: &str) -> Result<DnsLookupResult> {
         ns_records,
         cname_records,
         soa_record,
+        mx_certificates_valid: None,
     })
 }
 
+/// Like [`lookup`], and also fetch the SMTP STARTTLS certificate of every MX
+/// host, so expired or mismatched mail server certificates show up.
+pub async fn lookup_with_mx_certificates(domain: &str) -> Result<DnsLookupResult> {
+    let mut result = lookup(domain).await?;
+    result.mx_records.sort_by_key(|mx| mx.priority);
+    result.mx_certificates_valid = ssl::check_mx_certificates(&mut result.mx_records).await;
+    Ok(result)
+}
+
 pub async fn get_dns_records(domain: &str) -> Result<Vec<DnsRecord>> {
     let mut records = Vec::new();
     let lookup_result = lookup(domain).await?;
@@ -265,6 +344,8 @@ mod tests {
         let mx_record = MxRecord {
             priority: 10,
             exchange: "mail.example.com".to_string(),
+            certificate: None,
+            certificate_error: None,
         };
 
         let soa_record = SoaRecord {
@@ -286,6 +367,7 @@ mod tests {
             ns_records: vec!["ns1.example.com".to_string(), "ns2.example.com".to_string()],
             cname_records: vec!["www.example.com".to_string()],
             soa_record: Some(soa_record),
+            mx_certificates_valid: None,
         };
 
         let serialized = serde_json::to_string(&dns_result).unwrap();
@@ -307,6 +389,8 @@ mod tests {
         let mx_record = MxRecord {
             priority: 20,
             exchange: "backup.mail.example.com".to_string(),
+            certificate: None,
+            certificate_error: None,
         };
 
         let serialized = serde_json::to_string(&mx_record).unwrap();
@@ -381,11 +465,15 @@ mod tests {
         let high_priority = MxRecord {
             priority: 5,
             exchange: "primary.mail.example.com".to_string(),
+            certificate: None,
+            certificate_error: None,
         };
 
         let low_priority = MxRecord {
             priority: 10,
             exchange: "backup.mail.example.com".to_string(),
+            certificate: None,
+            certificate_error: None,
         };
 
         assert!(high_priority.priority < low_priority.priority);
@@ -402,6 +490,7 @@ mod tests {
             ns_records: Vec::new(),
             cname_records: Vec::new(),
             soa_record: None,
+            mx_certificates_valid: None,
         };
 
         assert!(empty_result.a_records.is_empty());
@@ -1,23 +1,115 @@
 use anyhow::Result;
-use futures::future::join_all;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
 
 use super::dns;
+use super::rdap::{self, RdapClient, RdapLookup};
+use super::registry_check::{self, AvailabilityProvider, RegistryCheck, RegistryStatus};
+use super::scheduler::Scheduler;
+use super::tlds::TldSet;
 use super::whois;
 
+/// Overall availability verdict for a domain, derived from the collected evidence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AvailabilityVerdict {
+    Registered,
+    Available,
+    Reserved,
+    Premium,
+    Unknown,
+}
+
+/// A single observation that contributed to the verdict.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "signal", rename_all = "snake_case")]
+pub enum Evidence {
+    RegistryCheck {
+        provider: String,
+        check: RegistryCheck,
+    },
+    RegistryCheckFailed {
+        provider: String,
+        error: String,
+    },
+    RdapRecordFound {
+        status: Vec<String>,
+    },
+    RdapNotFound {
+        server: String,
+    },
+    RdapLookupFailed {
+        error: String,
+    },
+    WhoisNotFoundPattern {
+        pattern: String,
+    },
+    WhoisReservedPattern {
+        pattern: String,
+    },
+    WhoisPremiumPattern {
+        pattern: String,
+    },
+    WhoisRegistrarPresent {
+        registrar: String,
+    },
+    WhoisStatusPresent {
+        status: Vec<String>,
+    },
+    WhoisInconclusive,
+    NameserversPresent {
+        nameservers: Vec<String>,
+    },
+    AddressRecordsPresent {
+        count: usize,
+    },
+    NoDnsRecords,
+    DnsLookupFailed {
+        error: String,
+    },
+    TimedOut {
+        after_secs: u64,
+    },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DomainAvailability {
     pub domain: String,
+    pub verdict: AvailabilityVerdict,
     pub available: bool,
+    pub confidence: f64,
     pub reason: String,
-    pub whois_available: Option<bool>,
-    pub dns_available: Option<bool>,
+    pub evidence: Vec<Evidence>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BulkCheckResult {
     pub domains: Vec<DomainAvailability>,
     pub summary: BulkCheckSummary,
+    /// Set when only one page of the requested domains was checked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<BulkPage>,
+}
+
+/// Where a page sits within the full list of requested domains.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkPage {
+    pub offset: usize,
+    pub limit: usize,
+    pub total: usize,
+    /// Offset of the next page, or `None` once the list is exhausted.
+    pub next_offset: Option<usize>,
+}
+
+/// Sent as each domain of a bulk check completes.
+#[derive(Debug, Clone)]
+pub struct BulkProgress {
+    pub completed: usize,
+    pub total: usize,
+    pub result: DomainAvailability,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,107 +117,521 @@ pub struct BulkCheckSummary {
     pub total: usize,
     pub available: usize,
     pub taken: usize,
+    pub reserved: usize,
+    pub premium: usize,
+    pub unknown: usize,
     pub errors: usize,
 }
 
+const NOT_FOUND_PATTERNS: &[&str] = &[
+    "no matching record",
+    "not found",
+    "no data found",
+    "domain name not known",
+    "no match for",
+    "no entries found",
+    "status: free",
+    "status: available",
+    "is available for registration",
+];
+
+const RESERVED_PATTERNS: &[&str] = &[
+    "reserved domain",
+    "reserved by the registry",
+    "reserved name",
+    "this name is reserved",
+    "status: reserved",
+    "registry reserved",
+];
+
+const PREMIUM_PATTERNS: &[&str] = &["premium domain", "premium name", "is a premium"];
+
 pub async fn check_availability(domain: &str) -> Result<DomainAvailability> {
+    let provider = registry_check::configured_provider();
+    check_availability_with(domain, provider.as_deref()).await
+}
+
+/// Check availability, consulting `provider` (a registrar API or EPP account)
+/// in addition to RDAP and DNS when one is given.
+pub async fn check_availability_with(
+    domain: &str,
+    provider: Option<&dyn AvailabilityProvider>,
+) -> Result<DomainAvailability> {
     let domain = normalize_domain(domain);
+    let provider = provider.filter(|provider| provider.covers(&domain));
 
-    let whois_future = whois::lookup(&domain);
+    let rdap_client = RdapClient::new();
+    let rdap_future = rdap_client.lookup(&domain);
     let dns_future = dns::lookup(&domain);
+    let registry_future = async {
+        match provider {
+            Some(provider) => Some((provider.name().to_string(), provider.check(&domain).await)),
+            None => None,
+        }
+    };
 
-    let (whois_result, dns_result) = tokio::join!(whois_future, dns_future);
-
-    let whois_available = whois_result
-        .as_ref()
-        .map(|info| {
-            info.raw_data.contains("No matching record")
-                || info.raw_data.contains("NOT FOUND")
-                || info.raw_data.contains("No Data Found")
-                || info.raw_data.contains("domain name not known")
-                || info.registrar.is_none()
-        })
-        .unwrap_or(true);
+    let (rdap_result, dns_result, registry_result) =
+        tokio::join!(rdap_future, dns_future, registry_future);
 
-    let dns_available = dns_result
-        .as_ref()
-        .map(|info| {
-            info.a_records.is_empty() && info.aaaa_records.is_empty() && info.ns_records.is_empty()
-        })
-        .unwrap_or(true);
+    let mut evidence = Vec::new();
 
-    let available = whois_available || dns_available;
+    match registry_result {
+        Some((provider, Ok(check))) => evidence.push(Evidence::RegistryCheck { provider, check }),
+        Some((provider, Err(e))) => evidence.push(Evidence::RegistryCheckFailed {
+            provider,
+            error: e.to_string(),
+        }),
+        None => {}
+    }
 
-    let reason = if available {
-        if whois_available && dns_available {
-            "Domain appears to be completely available (no WHOIS or DNS records)".to_string()
-        } else if whois_available {
-            "Domain has no WHOIS record but has DNS entries".to_string()
-        } else {
-            "Domain has WHOIS record but no DNS entries".to_string()
+    match rdap_result {
+        Ok(RdapLookup::Found(rdap_domain)) => {
+            evidence.push(Evidence::RdapRecordFound {
+                status: rdap::extract_status(&rdap_domain),
+            });
+            if let Some(registrar) = rdap::extract_registrar(&rdap_domain) {
+                evidence.push(Evidence::WhoisRegistrarPresent { registrar });
+            }
         }
-    } else {
-        "Domain is registered and active".to_string()
-    };
+        Ok(RdapLookup::NotFound { server }) => evidence.push(Evidence::RdapNotFound { server }),
+        Err(e) => {
+            evidence.push(Evidence::RdapLookupFailed {
+                error: e.to_string(),
+            });
+            // Without RDAP, fall back to the text WHOIS protocol, where pattern
+            // matching is the only option.
+            match whois::lookup_command_line_whois(&domain).await {
+                Ok(info) => evidence.extend(whois_evidence(&info)),
+                Err(_) => evidence.push(Evidence::WhoisInconclusive),
+            }
+        }
+    }
+
+    match &dns_result {
+        Ok(info) => evidence.extend(dns_evidence(info)),
+        Err(e) => evidence.push(Evidence::DnsLookupFailed {
+            error: e.to_string(),
+        }),
+    }
+
+    let (verdict, confidence, reason) = assess(&evidence);
 
     Ok(DomainAvailability {
         domain,
-        available,
+        verdict,
+        available: verdict == AvailabilityVerdict::Available,
+        confidence,
         reason,
-        whois_available: Some(whois_available),
-        dns_available: Some(dns_available),
+        evidence,
     })
 }
 
 pub async fn bulk_check(domains: Vec<String>) -> Result<BulkCheckResult> {
-    let mut futures = Vec::new();
+    bulk_check_with_progress(domains, None, &CancellationToken::new()).await
+}
+
+/// Like [`bulk_check`], but reports each domain on `progress` as soon as it
+/// completes and gives up, dropping the in-flight lookups, once `cancel` fires.
+pub async fn bulk_check_with_progress(
+    domains: Vec<String>,
+    progress: Option<UnboundedSender<BulkProgress>>,
+    cancel: &CancellationToken,
+) -> Result<BulkCheckResult> {
+    let provider = registry_check::configured_provider();
+    let results = check_bounded(&domains, provider.as_deref(), progress.as_ref(), cancel).await?;
 
-    for domain in &domains {
-        futures.push(check_availability(domain));
+    Ok(collect_results(&domains, results))
+}
+
+/// Select the `limit` domains starting at `offset`. Without a limit the rest
+/// of the list is returned.
+pub fn paginate<T>(
+    mut domains: Vec<T>,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<(Vec<T>, BulkPage)> {
+    let total = domains.len();
+    if offset > total {
+        return Err(anyhow::anyhow!(
+            "Offset {} is past the end of the {} domains",
+            offset,
+            total
+        ));
+    }
+
+    if limit == Some(0) {
+        return Err(anyhow::anyhow!("Limit must be at least 1"));
     }
+    // Without a limit the page is the rest of the list, which is empty when
+    // the offset is at the end.
+    let limit = limit.unwrap_or(total - offset);
 
-    let results = join_all(futures).await;
+    let end = offset.saturating_add(limit).min(total);
+    let page = BulkPage {
+        offset,
+        limit,
+        total,
+        next_offset: (end < total).then_some(end),
+    };
 
-    let mut available_count = 0;
-    let mut taken_count = 0;
-    let mut error_count = 0;
+    domains.truncate(end);
+    Ok((domains.split_off(offset), page))
+}
+
+/// Normalize a second-level label such as "Acme", rejecting full domains.
+pub fn parse_label(label: &str) -> Result<String> {
+    let label = normalize_domain(label);
+    if label.is_empty() || label.contains('.') {
+        return Err(anyhow::anyhow!(
+            "Expected a second-level label without a TLD, such as \"acme\", got \"{}\"",
+            label
+        ));
+    }
+    Ok(label)
+}
+
+/// Check `label` under every TLD in `tld_set`. Requests to each registry are
+/// paced by the shared scheduler's per-host limits.
+pub async fn check_name_across_tlds(label: &str, tld_set: &TldSet) -> Result<BulkCheckResult> {
+    let label = parse_label(label)?;
+
+    let bootstrap = RdapClient::new().bootstrap_registry().await.ok();
+    let tlds = tld_set.resolve(bootstrap)?;
+    let domains: Vec<String> = tlds
+        .iter()
+        .map(|tld| format!("{}.{}", label, tld))
+        .collect();
+
+    bulk_check(domains).await
+}
+
+/// Check domains with at most `domain_concurrency` in flight, each bounded by
+/// the per-domain timeout. Results are returned in input order, while progress
+/// is reported in completion order.
+async fn check_bounded(
+    domains: &[String],
+    provider: Option<&dyn AvailabilityProvider>,
+    progress: Option<&UnboundedSender<BulkProgress>>,
+    cancel: &CancellationToken,
+) -> Result<Vec<Result<DomainAvailability>>> {
+    let config = Scheduler::global().config();
+    let timeout = config.domain_timeout;
+    let total = domains.len();
+
+    let mut checks = stream::iter(domains.iter().cloned().enumerate())
+        .map(|(index, domain)| async move {
+            let result =
+                match tokio::time::timeout(timeout, check_availability_with(&domain, provider))
+                    .await
+                {
+                    Ok(result) => result,
+                    Err(_) => Ok(timed_out(&domain, timeout)),
+                };
+            (index, domain, result)
+        })
+        .buffer_unordered(config.domain_concurrency.max(1));
+
+    let mut results: Vec<Option<Result<DomainAvailability>>> = (0..total).map(|_| None).collect();
+    let mut completed = 0;
+
+    loop {
+        let next = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => {
+                return Err(anyhow::anyhow!(
+                    "Bulk check cancelled after {} of {} domains",
+                    completed,
+                    total
+                ));
+            }
+            next = checks.next() => next,
+        };
+        let Some((index, domain, result)) = next else {
+            break;
+        };
+
+        completed += 1;
+        if let Some(progress) = progress {
+            let reported = match &result {
+                Ok(availability) => availability.clone(),
+                Err(e) => check_failed(&domain, e),
+            };
+            // The receiver going away only means nobody is listening any more.
+            let _ = progress.send(BulkProgress {
+                completed,
+                total,
+                result: reported,
+            });
+        }
+        results[index] = Some(result);
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|result| result.expect("every domain yields a result"))
+        .collect())
+}
+
+fn timed_out(domain: &str, timeout: Duration) -> DomainAvailability {
+    let evidence = vec![Evidence::TimedOut {
+        after_secs: timeout.as_secs(),
+    }];
+    let (verdict, confidence, _) = assess(&evidence);
+
+    DomainAvailability {
+        domain: normalize_domain(domain),
+        verdict,
+        available: false,
+        confidence,
+        reason: format!(
+            "Check did not finish within {}s; availability is unknown",
+            timeout.as_secs()
+        ),
+        evidence,
+    }
+}
+
+fn collect_results(
+    domains: &[String],
+    results: Vec<Result<DomainAvailability>>,
+) -> BulkCheckResult {
     let mut domain_results: Vec<DomainAvailability> = Vec::new();
+    let mut error_count = 0;
 
-    for (domain, result) in domains.iter().zip(results.iter()) {
+    for (domain, result) in domains.iter().zip(results) {
         match result {
-            Ok(availability) => {
-                if availability.available {
-                    available_count += 1;
-                } else {
-                    taken_count += 1;
-                }
-                domain_results.push(availability.clone());
-            }
-            Err(_) => {
+            Ok(availability) => domain_results.push(availability),
+            Err(e) => {
                 error_count += 1;
-                domain_results.push(DomainAvailability {
-                    domain: domain.clone(),
-                    available: false,
-                    reason: "Error checking domain".to_string(),
-                    whois_available: None,
-                    dns_available: None,
-                });
+                domain_results.push(check_failed(domain, &e));
             }
         }
     }
 
-    Ok(BulkCheckResult {
+    let count = |verdict: AvailabilityVerdict| {
+        domain_results
+            .iter()
+            .filter(|d| d.verdict == verdict)
+            .count()
+    };
+
+    let summary = BulkCheckSummary {
+        total: domains.len(),
+        available: count(AvailabilityVerdict::Available),
+        taken: count(AvailabilityVerdict::Registered),
+        reserved: count(AvailabilityVerdict::Reserved),
+        premium: count(AvailabilityVerdict::Premium),
+        unknown: count(AvailabilityVerdict::Unknown) - error_count,
+        errors: error_count,
+    };
+
+    BulkCheckResult {
         domains: domain_results,
-        summary: BulkCheckSummary {
-            total: domains.len(),
-            available: available_count,
-            taken: taken_count,
-            errors: error_count,
-        },
-    })
+        summary,
+        page: None,
+    }
+}
+
+fn check_failed(domain: &str, error: &anyhow::Error) -> DomainAvailability {
+    DomainAvailability {
+        domain: domain.to_string(),
+        verdict: AvailabilityVerdict::Unknown,
+        available: false,
+        confidence: 0.0,
+        reason: format!("Error checking domain: {}", error),
+        evidence: Vec::new(),
+    }
+}
+
+fn whois_evidence(info: &whois::WhoisInfo) -> Vec<Evidence> {
+    let mut evidence = Vec::new();
+
+    let raw = info.raw_data.to_lowercase();
+    let first_match = |patterns: &[&str]| {
+        patterns
+            .iter()
+            .find(|pattern| raw.contains(*pattern))
+            .map(|pattern| pattern.to_string())
+    };
+
+    if let Some(pattern) = first_match(NOT_FOUND_PATTERNS) {
+        evidence.push(Evidence::WhoisNotFoundPattern { pattern });
+    }
+    if let Some(pattern) = first_match(RESERVED_PATTERNS) {
+        evidence.push(Evidence::WhoisReservedPattern { pattern });
+    }
+    if let Some(pattern) = first_match(PREMIUM_PATTERNS) {
+        evidence.push(Evidence::WhoisPremiumPattern { pattern });
+    }
+
+    let matched_pattern = !evidence.is_empty();
+
+    if let Some(registrar) = &info.registrar {
+        evidence.push(Evidence::WhoisRegistrarPresent {
+            registrar: registrar.clone(),
+        });
+    }
+
+    // Some registries answer unregistered names with "Status: free" or
+    // "Status: reserved"; only treat status lines as registration data when no
+    // availability pattern matched.
+    if !matched_pattern && !info.status.is_empty() {
+        evidence.push(Evidence::WhoisStatusPresent {
+            status: info.status.clone(),
+        });
+    }
+
+    if evidence.is_empty() {
+        evidence.push(Evidence::WhoisInconclusive);
+    }
+
+    evidence
+}
+
+fn dns_evidence(info: &dns::DnsLookupResult) -> Vec<Evidence> {
+    let mut evidence = Vec::new();
+
+    if !info.ns_records.is_empty() {
+        evidence.push(Evidence::NameserversPresent {
+            nameservers: info.ns_records.clone(),
+        });
+    }
+
+    let address_count = info.a_records.len() + info.aaaa_records.len();
+    if address_count > 0 {
+        evidence.push(Evidence::AddressRecordsPresent {
+            count: address_count,
+        });
+    }
+
+    if evidence.is_empty() {
+        evidence.push(Evidence::NoDnsRecords);
+    }
+
+    evidence
+}
+
+/// Combine independent signals of the same kind: `1 - Π(1 - w)`.
+fn combine(weights: impl Iterator<Item = f64>) -> f64 {
+    1.0 - weights.fold(1.0, |acc, w| acc * (1.0 - w))
+}
+
+/// Turn the evidence list into a verdict, a confidence in `[0, 1]` and a
+/// human-readable reason.
+///
+/// A missing DNS delegation on its own never makes a domain available: plenty
+/// of registered domains are parked without nameservers. `Available` requires
+/// a positive "not found" signal and no signal of registration.
+pub fn assess(evidence: &[Evidence]) -> (AvailabilityVerdict, f64, String) {
+    // A registry check is authoritative and is the only source that can tell
+    // premium and reserved names apart from ordinary ones.
+    let registry_check = evidence.iter().find_map(|e| match e {
+        Evidence::RegistryCheck { provider, check } => Some((provider, check)),
+        _ => None,
+    });
+    if let Some((provider, check)) = registry_check {
+        let verdict = match check.status {
+            RegistryStatus::Available => AvailabilityVerdict::Available,
+            RegistryStatus::Registered => AvailabilityVerdict::Registered,
+            RegistryStatus::Reserved => AvailabilityVerdict::Reserved,
+            RegistryStatus::Premium => AvailabilityVerdict::Premium,
+        };
+        let mut reason = format!("Registry check via {} reports {:?}", provider, check.status);
+        if let (Some(price), Some(currency)) = (&check.price, &check.currency) {
+            reason.push_str(&format!(" ({} {})", price, currency));
+        }
+        if let Some(detail) = &check.reason {
+            reason.push_str(&format!(": {}", detail));
+        }
+        return (verdict, 0.99, reason);
+    }
+
+    let weight_of =
+        |predicate: fn(&Evidence) -> Option<f64>| combine(evidence.iter().filter_map(predicate));
+
+    let registered = weight_of(|e| match e {
+        Evidence::RdapRecordFound { .. } => Some(0.95),
+        Evidence::NameserversPresent { .. } => Some(0.9),
+        Evidence::WhoisRegistrarPresent { .. } => Some(0.85),
+        Evidence::WhoisStatusPresent { .. } => Some(0.6),
+        Evidence::AddressRecordsPresent { .. } => Some(0.5),
+        _ => None,
+    });
+    let available = weight_of(|e| match e {
+        Evidence::RdapNotFound { .. } => Some(0.97),
+        Evidence::WhoisNotFoundPattern { .. } => Some(0.75),
+        _ => None,
+    });
+    let reserved = weight_of(|e| match e {
+        Evidence::WhoisReservedPattern { .. } => Some(0.8),
+        _ => None,
+    });
+    let premium = weight_of(|e| match e {
+        Evidence::WhoisPremiumPattern { .. } => Some(0.7),
+        _ => None,
+    });
+
+    let round = |value: f64| (value * 100.0).round() / 100.0;
+
+    if registered > 0.0 && available > 0.0 {
+        // Conflicting sources: never report available, and say how unsure we are.
+        let confidence = round(registered.max(available) * (1.0 - registered.min(available)));
+        return if registered >= available {
+            (
+                AvailabilityVerdict::Registered,
+                confidence,
+                "Registration signals outweigh a conflicting \"not found\" response".to_string(),
+            )
+        } else {
+            (
+                AvailabilityVerdict::Unknown,
+                confidence,
+                "Sources disagree on whether the domain is registered".to_string(),
+            )
+        };
+    }
+
+    if registered > 0.0 {
+        return (
+            AvailabilityVerdict::Registered,
+            round(registered),
+            "Domain is registered".to_string(),
+        );
+    }
+
+    if reserved > 0.0 {
+        return (
+            AvailabilityVerdict::Reserved,
+            round(reserved),
+            "Registry reports the name as reserved".to_string(),
+        );
+    }
+
+    if premium > 0.0 {
+        return (
+            AvailabilityVerdict::Premium,
+            round(premium),
+            "Name appears to be available at a premium price".to_string(),
+        );
+    }
+
+    if available > 0.0 {
+        return (
+            AvailabilityVerdict::Available,
+            round(available),
+            "No registration record found".to_string(),
+        );
+    }
+
+    (
+        AvailabilityVerdict::Unknown,
+        0.0,
+        "No conclusive registration data; a missing DNS delegation alone does not mean available"
+            .to_string(),
+    )
 }
 
-fn normalize_domain(domain: &str) -> String {
+pub(crate) fn normalize_domain(domain: &str) -> String {
     domain
         .trim()
         .to_lowercase()
@@ -161,38 +667,50 @@ mod tests {
     fn domain_availability_serialization_test() {
         let availability = DomainAvailability {
             domain: "example.com".to_string(),
+            verdict: AvailabilityVerdict::Available,
             available: true,
-            reason: "Domain appears available".to_string(),
-            whois_available: Some(true),
-            dns_available: Some(true),
+            confidence: 0.75,
+            reason: "No registration record found".to_string(),
+            evidence: vec![
+                Evidence::WhoisNotFoundPattern {
+                    pattern: "no match for".to_string(),
+                },
+                Evidence::NoDnsRecords,
+            ],
         };
 
         let serialized = serde_json::to_string(&availability).unwrap();
         let deserialized: DomainAvailability = serde_json::from_str(&serialized).unwrap();
 
         assert_eq!(availability.domain, deserialized.domain);
+        assert_eq!(availability.verdict, deserialized.verdict);
         assert_eq!(availability.available, deserialized.available);
+        assert_eq!(availability.confidence, deserialized.confidence);
         assert_eq!(availability.reason, deserialized.reason);
-        assert_eq!(availability.whois_available, deserialized.whois_available);
-        assert_eq!(availability.dns_available, deserialized.dns_available);
+        assert_eq!(availability.evidence, deserialized.evidence);
+        assert!(serialized.contains(r#""signal":"whois_not_found_pattern""#));
     }
 
     #[test]
     fn bulk_check_result_serialization_test() {
         let availability1 = DomainAvailability {
             domain: "example1.com".to_string(),
+            verdict: AvailabilityVerdict::Available,
             available: true,
+            confidence: 0.75,
             reason: "Available".to_string(),
-            whois_available: Some(true),
-            dns_available: Some(true),
+            evidence: Vec::new(),
         };
 
         let availability2 = DomainAvailability {
             domain: "example2.com".to_string(),
+            verdict: AvailabilityVerdict::Registered,
             available: false,
+            confidence: 0.95,
             reason: "Taken".to_string(),
-            whois_available: Some(false),
-            dns_available: Some(false),
+            evidence: vec![Evidence::RdapRecordFound {
+                status: vec!["active".to_string()],
+            }],
         };
 
         let bulk_result = BulkCheckResult {
@@ -201,8 +719,12 @@ mod tests {
                 total: 2,
                 available: 1,
                 taken: 1,
+                reserved: 0,
+                premium: 0,
+                unknown: 0,
                 errors: 0,
             },
+            page: None,
         };
 
         let serialized = serde_json::to_string(&bulk_result).unwrap();
@@ -219,17 +741,291 @@ mod tests {
     }
 
     #[test]
-    fn bulk_check_summary_calculation_test() {
-        let summary = BulkCheckSummary {
-            total: 5,
-            available: 2,
-            taken: 2,
-            errors: 1,
+    fn bulk_check_summary_counts_each_verdict_test() {
+        let verdicts = [
+            AvailabilityVerdict::Available,
+            AvailabilityVerdict::Available,
+            AvailabilityVerdict::Registered,
+            AvailabilityVerdict::Registered,
+            AvailabilityVerdict::Registered,
+            AvailabilityVerdict::Reserved,
+            AvailabilityVerdict::Premium,
+            AvailabilityVerdict::Unknown,
+        ];
+        let mut domains: Vec<String> = (0..verdicts.len()).map(|i| format!("d{}.com", i)).collect();
+        let mut results: Vec<Result<DomainAvailability>> = domains
+            .iter()
+            .zip(verdicts)
+            .map(|(domain, verdict)| {
+                Ok(DomainAvailability {
+                    domain: domain.clone(),
+                    available: verdict == AvailabilityVerdict::Available,
+                    verdict,
+                    confidence: 0.9,
+                    reason: String::new(),
+                    evidence: Vec::new(),
+                })
+            })
+            .collect();
+        // Failed checks show up as unknown domains but count only as errors.
+        for i in 0..2 {
+            domains.push(format!("failed{}.com", i));
+            results.push(Err(anyhow::anyhow!("connection reset")));
+        }
+
+        let result = collect_results(&domains, results);
+        let summary = &result.summary;
+        assert_eq!(summary.total, 10);
+        assert_eq!(summary.available, 2);
+        assert_eq!(summary.taken, 3);
+        assert_eq!(summary.reserved, 1);
+        assert_eq!(summary.premium, 1);
+        assert_eq!(summary.unknown, 1);
+        assert_eq!(summary.errors, 2);
+        assert_eq!(result.domains.len(), 10);
+        assert_eq!(result.domains[9].verdict, AvailabilityVerdict::Unknown);
+    }
+
+    #[test]
+    fn assess_registered_domain_without_dns_test() {
+        // A registered domain parked without A/NS records must not be available.
+        let evidence = vec![
+            Evidence::WhoisRegistrarPresent {
+                registrar: "Example Registrar, Inc.".to_string(),
+            },
+            Evidence::NoDnsRecords,
+        ];
+
+        let (verdict, confidence, _) = assess(&evidence);
+        assert_eq!(verdict, AvailabilityVerdict::Registered);
+        assert!(confidence >= 0.8);
+    }
+
+    #[test]
+    fn assess_failed_lookups_are_unknown_test() {
+        let evidence = vec![
+            Evidence::WhoisInconclusive,
+            Evidence::DnsLookupFailed {
+                error: "timed out".to_string(),
+            },
+        ];
+
+        let (verdict, confidence, _) = assess(&evidence);
+        assert_eq!(verdict, AvailabilityVerdict::Unknown);
+        assert_eq!(confidence, 0.0);
+
+        let (verdict, _, _) = assess(&[Evidence::WhoisInconclusive, Evidence::NoDnsRecords]);
+        assert_eq!(verdict, AvailabilityVerdict::Unknown);
+    }
+
+    #[test]
+    fn assess_available_requires_not_found_signal_test() {
+        let evidence = vec![
+            Evidence::WhoisNotFoundPattern {
+                pattern: "no match for".to_string(),
+            },
+            Evidence::NoDnsRecords,
+        ];
+
+        let (verdict, confidence, _) = assess(&evidence);
+        assert_eq!(verdict, AvailabilityVerdict::Available);
+        assert!(confidence > 0.5);
+    }
+
+    #[test]
+    fn assess_conflicting_signals_test() {
+        let evidence = vec![
+            Evidence::WhoisNotFoundPattern {
+                pattern: "not found".to_string(),
+            },
+            Evidence::NameserversPresent {
+                nameservers: vec!["ns1.example.net".to_string()],
+            },
+        ];
+
+        let (verdict, confidence, _) = assess(&evidence);
+        assert_eq!(verdict, AvailabilityVerdict::Registered);
+        assert!(confidence < 0.5);
+    }
+
+    #[test]
+    fn assess_reserved_and_premium_test() {
+        let (verdict, _, _) = assess(&[Evidence::WhoisReservedPattern {
+            pattern: "reserved by the registry".to_string(),
+        }]);
+        assert_eq!(verdict, AvailabilityVerdict::Reserved);
+
+        let (verdict, _, _) = assess(&[
+            Evidence::WhoisPremiumPattern {
+                pattern: "premium domain".to_string(),
+            },
+            Evidence::WhoisNotFoundPattern {
+                pattern: "not found".to_string(),
+            },
+        ]);
+        assert_eq!(verdict, AvailabilityVerdict::Premium);
+    }
+
+    #[test]
+    fn whois_free_status_is_not_registration_test() {
+        let info = whois::WhoisInfo {
+            domain: "unregistered-example.de".to_string(),
+            registrar: None,
+            registrant: None,
+            creation_date: None,
+            expiry_date: None,
+            updated_date: None,
+            name_servers: Vec::new(),
+            status: vec!["free".to_string()],
+            raw_data: "Domain: unregistered-example.de\nStatus: free\n".to_string(),
+            rdap_available: false,
         };
 
+        let evidence = whois_evidence(&info);
         assert_eq!(
-            summary.total,
-            summary.available + summary.taken + summary.errors
+            evidence,
+            vec![Evidence::WhoisNotFoundPattern {
+                pattern: "status: free".to_string()
+            }]
         );
     }
+
+    #[test]
+    fn assess_rdap_not_found_test() {
+        let evidence = vec![
+            Evidence::RdapNotFound {
+                server: "https://rdap.verisign.com/com/v1".to_string(),
+            },
+            Evidence::NoDnsRecords,
+        ];
+
+        let (verdict, confidence, _) = assess(&evidence);
+        assert_eq!(verdict, AvailabilityVerdict::Available);
+        assert!(confidence > 0.95);
+
+        // A failed RDAP query is not a "not found".
+        let evidence = vec![
+            Evidence::RdapLookupFailed {
+                error: "RDAP server returned status: 503".to_string(),
+            },
+            Evidence::WhoisInconclusive,
+            Evidence::NoDnsRecords,
+        ];
+        let (verdict, _, _) = assess(&evidence);
+        assert_eq!(verdict, AvailabilityVerdict::Unknown);
+    }
+
+    #[tokio::test]
+    async fn assess_registry_check_from_mock_provider_test() {
+        use crate::tools::registry_check::MockProvider;
+
+        let provider = MockProvider::new()
+            .with_premium("shop.example", "2500.00", "USD")
+            .with_status("nic.example", RegistryStatus::Reserved);
+
+        for (domain, expected) in [
+            ("shop.example", AvailabilityVerdict::Premium),
+            ("nic.example", AvailabilityVerdict::Reserved),
+        ] {
+            let check = provider.check(domain).await.unwrap();
+            let evidence = vec![
+                Evidence::RegistryCheck {
+                    provider: provider.name().to_string(),
+                    check,
+                },
+                Evidence::RdapNotFound {
+                    server: "https://rdap.example".to_string(),
+                },
+            ];
+
+            let (verdict, confidence, reason) = assess(&evidence);
+            assert_eq!(verdict, expected);
+            assert_eq!(confidence, 0.99);
+            assert!(reason.contains("mock"));
+        }
+    }
+
+    #[test]
+    fn collect_results_test() {
+        let domains = vec!["a.com".to_string(), "b.com".to_string()];
+        let results = vec![
+            Ok(DomainAvailability {
+                domain: "a.com".to_string(),
+                verdict: AvailabilityVerdict::Available,
+                available: true,
+                confidence: 0.97,
+                reason: "No registration record found".to_string(),
+                evidence: Vec::new(),
+            }),
+            Err(anyhow::anyhow!("timed out")),
+        ];
+
+        let result = collect_results(&domains, results);
+        assert_eq!(result.summary.total, 2);
+        assert_eq!(result.summary.available, 1);
+        assert_eq!(result.summary.errors, 1);
+        assert_eq!(result.summary.unknown, 0);
+        assert_eq!(result.domains[1].domain, "b.com");
+        assert!(result.domains[1].reason.contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn check_name_across_tlds_rejects_fqdn_test() {
+        let result = check_name_across_tlds("acme.com", &TldSet::Popular).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn timed_out_is_unknown_test() {
+        let result = timed_out("Example.com", Duration::from_secs(30));
+        assert_eq!(result.domain, "example.com");
+        assert_eq!(result.verdict, AvailabilityVerdict::Unknown);
+        assert!(!result.available);
+        assert_eq!(result.evidence, vec![Evidence::TimedOut { after_secs: 30 }]);
+    }
+
+    #[test]
+    fn paginate_test() {
+        let domains: Vec<String> = (0..5).map(|i| format!("d{}.com", i)).collect();
+
+        let (page, info) = paginate(domains.clone(), 0, Some(2)).unwrap();
+        assert_eq!(page, vec!["d0.com", "d1.com"]);
+        assert_eq!(info.next_offset, Some(2));
+        assert_eq!(info.total, 5);
+
+        let (page, info) = paginate(domains.clone(), 4, Some(2)).unwrap();
+        assert_eq!(page, vec!["d4.com"]);
+        assert_eq!(info.next_offset, None);
+
+        let (page, info) = paginate(domains.clone(), 3, None).unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(info.next_offset, None);
+
+        // An offset at the end gives an empty last page, with or without a limit.
+        for limit in [None, Some(2)] {
+            let (page, info) = paginate(domains.clone(), 5, limit).unwrap();
+            assert!(page.is_empty());
+            assert_eq!(info.total, 5);
+            assert_eq!(info.next_offset, None);
+        }
+        let (page, info) = paginate(Vec::<String>::new(), 0, None).unwrap();
+        assert!(page.is_empty());
+        assert_eq!(info.next_offset, None);
+
+        assert!(paginate(domains.clone(), 6, None).is_err());
+        assert!(paginate(domains, 0, Some(0)).is_err());
+    }
+
+    #[tokio::test]
+    async fn cancelled_bulk_check_test() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        let result =
+            bulk_check_with_progress(vec!["example.com".to_string()], Some(sender), &cancel).await;
+
+        assert!(result.unwrap_err().to_string().contains("cancelled"));
+        assert!(receiver.recv().await.is_none());
+    }
 }
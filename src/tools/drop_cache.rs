@@ -0,0 +1,795 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, USER_AGENT};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use super::expired::{ExpiredDomain, ExpiredQuery};
+use super::scheduler::Scheduler;
+
+/// Turns a downloaded or imported list into domains, failing when the list
+/// is not in the expected format.
+pub type ListParser = fn(&str) -> Result<Vec<ExpiredDomain>>;
+
+#[derive(Debug, Clone)]
+pub struct DropCacheConfig {
+    /// Downloaded lists live here; imported lists under `imported/`.
+    pub dir: PathBuf,
+    /// How long a downloaded list is used before asking the provider whether
+    /// it changed.
+    pub refresh_interval: Duration,
+    /// Refresh the drop lists in the background every `refresh_interval`,
+    /// rather than only when a search finds them stale.
+    pub prefetch: bool,
+    /// The only directory lists may be imported from; importing is refused
+    /// when unset.
+    pub import_dir: Option<PathBuf>,
+}
+
+impl Default for DropCacheConfig {
+    fn default() -> Self {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir);
+        Self {
+            dir: base.join("domain-mcp").join("drop-lists"),
+            refresh_interval: Duration::from_secs(6 * 60 * 60),
+            prefetch: false,
+            import_dir: None,
+        }
+    }
+}
+
+impl DropCacheConfig {
+    /// Defaults, overridden by `DOMAIN_MCP_DROP_CACHE_DIR`,
+    /// `DOMAIN_MCP_DROP_CACHE_REFRESH_SECS`, `DOMAIN_MCP_DROP_CACHE_PREFETCH`
+    /// and `DOMAIN_MCP_DROP_IMPORT_DIR`.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(dir) = std::env::var_os("DOMAIN_MCP_DROP_CACHE_DIR") {
+            config.dir = PathBuf::from(dir);
+        }
+        if let Some(value) = std::env::var("DOMAIN_MCP_DROP_CACHE_REFRESH_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+        {
+            config.refresh_interval = Duration::from_secs(value);
+        }
+        if let Ok(value) = std::env::var("DOMAIN_MCP_DROP_CACHE_PREFETCH") {
+            config.prefetch = matches!(value.trim(), "1" | "true" | "yes");
+        }
+        if let Some(dir) = std::env::var_os("DOMAIN_MCP_DROP_IMPORT_DIR") {
+            config.import_dir = Some(PathBuf::from(dir));
+        }
+        config
+    }
+}
+
+/// Stored next to each downloaded list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheMeta {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    /// When the stored body was downloaded.
+    downloaded_at: DateTime<Utc>,
+    /// When the provider last confirmed it, by sending it or a 304.
+    checked_at: DateTime<Utc>,
+}
+
+/// A parsed list, indexed so that keyword and TLD queries only look at the
+/// domains that can match.
+#[derive(Debug)]
+pub struct DropListIndex {
+    domains: Vec<ExpiredDomain>,
+    /// Lowercase domain names, parallel to `domains`.
+    names: Vec<String>,
+    by_tld: HashMap<String, Vec<u32>>,
+    /// Every domain containing each three-byte sequence.
+    trigrams: HashMap<[u8; 3], Vec<u32>>,
+    /// When the list was downloaded, or the imported file last changed.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DropListIndex {
+    pub fn build(domains: Vec<ExpiredDomain>, updated_at: DateTime<Utc>) -> Self {
+        let names: Vec<String> = domains.iter().map(|d| d.domain.to_lowercase()).collect();
+        let mut by_tld: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut trigrams: HashMap<[u8; 3], Vec<u32>> = HashMap::new();
+
+        for (position, name) in names.iter().enumerate() {
+            let position = position as u32;
+            if let Some((_, tld)) = name.rsplit_once('.') {
+                by_tld.entry(tld.to_string()).or_default().push(position);
+            }
+            for window in name.as_bytes().windows(3) {
+                let postings = trigrams
+                    .entry([window[0], window[1], window[2]])
+                    .or_default();
+                if postings.last() != Some(&position) {
+                    postings.push(position);
+                }
+            }
+        }
+
+        Self {
+            domains,
+            names,
+            by_tld,
+            trigrams,
+            updated_at,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// The entry for exactly `domain`, if the list has it.
+    pub fn get(&self, domain: &str) -> Option<&ExpiredDomain> {
+        let domain = domain.to_lowercase();
+        let query = ExpiredQuery {
+            keyword: domain.clone(),
+            tld: String::new(),
+        };
+        self.candidates(&query)
+            .find(|&position| self.names[position] == domain)
+            .map(|position| &self.domains[position])
+    }
+
+    /// Every domain matching `query`, in list order.
+    pub fn search(&self, query: &ExpiredQuery) -> Vec<ExpiredDomain> {
+        self.candidates(query)
+            .filter(|&position| query.matches(&self.names[position]))
+            .map(|position| self.domains[position].clone())
+            .collect()
+    }
+
+    /// Positions worth checking against `query`: the shortest posting list
+    /// that every match must appear in, or the whole list.
+    fn candidates(&self, query: &ExpiredQuery) -> Box<dyn Iterator<Item = usize> + '_> {
+        let tld = (!query.tld.is_empty()).then(|| {
+            self.by_tld
+                .get(query.tld.rsplit('.').next().unwrap_or(&query.tld))
+        });
+        let keyword = query
+            .keyword
+            .as_bytes()
+            .windows(3)
+            .map(|window| self.trigrams.get(&[window[0], window[1], window[2]]));
+        let postings: Option<&[u32]> = tld
+            .into_iter()
+            .chain(keyword)
+            .map(|postings| postings.map(Vec::as_slice).unwrap_or(&[]))
+            .min_by_key(|postings| postings.len());
+
+        match postings {
+            Some(postings) => Box::new(postings.iter().map(|&position| position as usize)),
+            None => Box::new(0..self.domains.len()),
+        }
+    }
+}
+
+/// A list the user imported.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportedDropList {
+    pub name: String,
+    pub domains: usize,
+    pub path: String,
+}
+
+enum Download {
+    NotModified,
+    Body {
+        url: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        text: String,
+    },
+}
+
+/// Drop lists kept on disk between runs and indexed in memory, so a search
+/// only downloads a list when its copy is older than the refresh interval,
+/// and then only if the provider says it changed.
+pub struct DropCache {
+    config: DropCacheConfig,
+    indexes: Mutex<HashMap<String, Arc<DropListIndex>>>,
+    /// One refresh of each list at a time.
+    refreshing: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl DropCache {
+    pub fn new(config: DropCacheConfig) -> Self {
+        Self {
+            config,
+            indexes: Mutex::new(HashMap::new()),
+            refreshing: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The process-wide cache, configured from the environment.
+    pub fn global() -> &'static DropCache {
+        static CACHE: OnceLock<DropCache> = OnceLock::new();
+        CACHE.get_or_init(|| DropCache::new(DropCacheConfig::from_env()))
+    }
+
+    pub fn config(&self) -> &DropCacheConfig {
+        &self.config
+    }
+
+    /// The list `name`, from the cache while it is fresh; otherwise
+    /// revalidated against the first of `urls` that answers. When every URL
+    /// fails, a stale copy is used if there is one.
+    pub async fn list(
+        &self,
+        client: &Client,
+        name: &str,
+        urls: &[&str],
+        parse: ListParser,
+    ) -> Result<Arc<DropListIndex>> {
+        let lock = {
+            let mut refreshing = self.refreshing.lock().unwrap_or_else(|e| e.into_inner());
+            refreshing.entry(name.to_string()).or_default().clone()
+        };
+        let _guard = lock.lock().await;
+
+        let body_path = self.config.dir.join(format!("{}.txt", name));
+        let meta_path = self.config.dir.join(format!("{}.json", name));
+        let meta = match tokio::fs::read(&meta_path).await {
+            Ok(bytes) if tokio::fs::try_exists(&body_path).await.unwrap_or(false) => {
+                serde_json::from_slice::<CacheMeta>(&bytes).ok()
+            }
+            _ => None,
+        };
+
+        let now = Utc::now();
+        if let Some(meta) = &meta {
+            let age = (now - meta.checked_at).to_std().unwrap_or_default();
+            if age < self.config.refresh_interval {
+                return self.load(name, &body_path, meta.downloaded_at, parse).await;
+            }
+        }
+
+        match download(client, urls, meta.as_ref()).await {
+            Ok(Download::NotModified) => {
+                let mut meta = meta.expect("304 is only possible with a cached copy");
+                meta.checked_at = now;
+                write_atomic(&meta_path, &serde_json::to_vec_pretty(&meta)?).await?;
+                self.load(name, &body_path, meta.downloaded_at, parse).await
+            }
+            Ok(Download::Body {
+                url,
+                etag,
+                last_modified,
+                text,
+            }) => {
+                // A list that no longer parses must not replace a good copy.
+                let index = match self.index(name, text.clone(), now, parse).await {
+                    Ok(index) => index,
+                    Err(e) => match meta {
+                        Some(meta) => {
+                            tracing::warn!(
+                                "Using the {} list from {}, the new one does not parse: {:#}",
+                                name,
+                                meta.checked_at,
+                                e
+                            );
+                            return self.load(name, &body_path, meta.downloaded_at, parse).await;
+                        }
+                        None => return Err(e),
+                    },
+                };
+                tokio::fs::create_dir_all(&self.config.dir).await?;
+                write_atomic(&body_path, text.as_bytes()).await?;
+                let meta = CacheMeta {
+                    url,
+                    etag,
+                    last_modified,
+                    downloaded_at: now,
+                    checked_at: now,
+                };
+                write_atomic(&meta_path, &serde_json::to_vec_pretty(&meta)?).await?;
+                Ok(index)
+            }
+            Err(e) => match meta {
+                Some(meta) => {
+                    tracing::warn!("Using the {} list from {}: {:#}", name, meta.checked_at, e);
+                    self.load(name, &body_path, meta.downloaded_at, parse).await
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Copy the list at `path` into the cache as `name`, after checking that
+    /// `parse` finds domains in it. `path` must lie in the import directory;
+    /// a relative path is taken from there.
+    pub async fn import(
+        &self,
+        name: &str,
+        path: &Path,
+        parse: ListParser,
+    ) -> Result<ImportedDropList> {
+        let path = &self.import_path(path)?;
+        let text = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let domains = parse(&text).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        if domains.is_empty() {
+            return Err(anyhow::anyhow!("No domains found in {}", path.display()));
+        }
+
+        let dir = self.imported_dir();
+        tokio::fs::create_dir_all(&dir).await?;
+        let target = dir.join(format!("{}.csv", name));
+        write_atomic(&target, text.as_bytes()).await?;
+        self.indexes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&imported_key(name));
+
+        Ok(ImportedDropList {
+            name: name.to_string(),
+            domains: domains.len(),
+            path: target.display().to_string(),
+        })
+    }
+
+    /// `path` resolved, links included, and checked to be a file inside the
+    /// import directory. Paths outside it all get the same error, so the
+    /// caller cannot probe which files exist.
+    fn import_path(&self, path: &Path) -> Result<PathBuf> {
+        let Some(dir) = &self.config.import_dir else {
+            return Err(anyhow::anyhow!(
+                "Importing is disabled; set DOMAIN_MCP_DROP_IMPORT_DIR to the directory to import from"
+            ));
+        };
+        let dir = dir
+            .canonicalize()
+            .map_err(|e| anyhow::anyhow!("Import directory {}: {}", dir.display(), e))?;
+        match dir.join(path).canonicalize() {
+            Ok(resolved) if resolved.starts_with(&dir) && resolved.is_file() => Ok(resolved),
+            _ => Err(anyhow::anyhow!(
+                "{} is not a file in the import directory {}",
+                path.display(),
+                dir.display()
+            )),
+        }
+    }
+
+    /// Names of the imported lists, sorted.
+    pub fn imported_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(self.imported_dir())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "csv" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// The imported list `name`, re-read whenever the file changes.
+    pub async fn imported(&self, name: &str, parse: ListParser) -> Result<Arc<DropListIndex>> {
+        let path = self.imported_dir().join(format!("{}.csv", name));
+        let modified = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        self.load(&imported_key(name), &path, modified.into(), parse)
+            .await
+    }
+
+    fn imported_dir(&self) -> PathBuf {
+        self.config.dir.join("imported")
+    }
+
+    /// The in-memory index for `key` if it was built from this copy,
+    /// otherwise parse `path` again.
+    async fn load(
+        &self,
+        key: &str,
+        path: &Path,
+        updated_at: DateTime<Utc>,
+        parse: ListParser,
+    ) -> Result<Arc<DropListIndex>> {
+        if let Some(index) = self
+            .indexes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .filter(|index| index.updated_at == updated_at)
+        {
+            return Ok(index.clone());
+        }
+        let text = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        self.index(key, text, updated_at, parse).await
+    }
+
+    async fn index(
+        &self,
+        key: &str,
+        text: String,
+        updated_at: DateTime<Utc>,
+        parse: ListParser,
+    ) -> Result<Arc<DropListIndex>> {
+        let index = tokio::task::spawn_blocking(move || {
+            parse(&text).map(|domains| Arc::new(DropListIndex::build(domains, updated_at)))
+        })
+        .await??;
+        self.indexes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.to_string(), index.clone());
+        Ok(index)
+    }
+}
+
+fn imported_key(name: &str) -> String {
+    format!("imported/{}", name)
+}
+
+/// Try the URL that worked last time first, asking it only for changes.
+async fn download(client: &Client, urls: &[&str], meta: Option<&CacheMeta>) -> Result<Download> {
+    let mut ordered: Vec<&str> = urls.to_vec();
+    if let Some(position) = meta.and_then(|meta| ordered.iter().position(|url| *url == meta.url)) {
+        let url = ordered.remove(position);
+        ordered.insert(0, url);
+    }
+
+    let mut last_error = anyhow::anyhow!("No URLs to download");
+    for url in ordered {
+        // Some providers turn away clients that do not look like a browser.
+        let mut request = client
+            .get(url)
+            .header(USER_AGENT, "Mozilla/5.0 (compatible; Domain-MCP/1.0)");
+        if let Some(meta) = meta.filter(|meta| meta.url == url) {
+            if let Some(etag) = &meta.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = match request.build() {
+            Ok(request) => Scheduler::global().execute(client, request).await,
+            Err(e) => Err(e.into()),
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                last_error = e;
+                continue;
+            }
+        };
+
+        if response.status() == StatusCode::NOT_MODIFIED && meta.is_some() {
+            return Ok(Download::NotModified);
+        }
+        if !response.status().is_success() {
+            last_error = anyhow::anyhow!("{} returned status: {}", url, response.status());
+            continue;
+        }
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        match response.text().await {
+            Ok(text) => {
+                return Ok(Download::Body {
+                    url: url.to_string(),
+                    etag,
+                    last_modified,
+                    text,
+                })
+            }
+            Err(e) => last_error = e.into(),
+        }
+    }
+    Err(last_error)
+}
+
+/// Write via a temporary file so a crash never leaves half a list behind.
+pub(crate) async fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    tokio::fs::write(&temporary, contents).await?;
+    tokio::fs::rename(&temporary, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn parse_lines(text: &str) -> Result<Vec<ExpiredDomain>> {
+        Ok(text
+            .lines()
+            .map(|line| ExpiredDomain {
+                domain: line.trim().to_string(),
+                status: "pending delete".to_string(),
+                source: "Test".to_string(),
+                created: None,
+                updated: None,
+                end_time: None,
+                appraisal: None,
+                starting_price: None,
+                bids: None,
+                has_dns: None,
+                has_website: None,
+                score: None,
+                matched_keywords: Vec::new(),
+            })
+            .collect())
+    }
+
+    /// Like `parse_lines`, but fails on a line that is not a domain.
+    fn parse_domains(text: &str) -> Result<Vec<ExpiredDomain>> {
+        if let Some(line) = text.lines().find(|line| !line.contains('.')) {
+            return Err(anyhow::anyhow!("'{}' is not a domain", line));
+        }
+        parse_lines(text)
+    }
+
+    fn query(keyword: &str, tld: &str) -> ExpiredQuery {
+        ExpiredQuery {
+            keyword: keyword.to_string(),
+            tld: tld.to_string(),
+        }
+    }
+
+    fn cache(test: &str, refresh_interval: Duration) -> DropCache {
+        let dir = std::env::temp_dir().join(format!("drop-cache-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        DropCache::new(DropCacheConfig {
+            dir,
+            refresh_interval,
+            prefetch: false,
+            import_dir: None,
+        })
+    }
+
+    /// Serves `body` with an ETag, answering 304 to requests that send it
+    /// back. Counts full downloads and 304s.
+    async fn serve(body: &'static str) -> (String, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let downloads = Arc::new(AtomicUsize::new(0));
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let (full, cached) = (downloads.clone(), not_modified.clone());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let response = if request.contains("if-none-match: \"v1\"") {
+                    cached.fetch_add(1, Ordering::SeqCst);
+                    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                } else if request.starts_with("get /broken") {
+                    "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nConnection: close\r\n\r\nnot a list"
+                        .to_string()
+                } else if request.starts_with("get /missing") {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                } else {
+                    full.fetch_add(1, Ordering::SeqCst);
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (format!("http://{}", addr), downloads, not_modified)
+    }
+
+    #[test]
+    fn index_search_test() {
+        let index = DropListIndex::build(
+            parse_lines("cloudshop.com\nshopfast.net\nmycloud.io\nCLOUDY.COM\nab.com").unwrap(),
+            Utc::now(),
+        );
+        let names = |query: ExpiredQuery| {
+            index
+                .search(&query)
+                .into_iter()
+                .map(|domain| domain.domain)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(query("cloud", "")),
+            vec!["cloudshop.com", "mycloud.io", "CLOUDY.COM"]
+        );
+        assert_eq!(
+            names(query("cloud", "com")),
+            vec!["cloudshop.com", "CLOUDY.COM"]
+        );
+        assert_eq!(names(query("shop", "net")), vec!["shopfast.net"]);
+        // Too short for the trigram index, so narrowed by TLD only.
+        assert_eq!(names(query("ab", "com")), vec!["ab.com"]);
+        assert_eq!(names(query("", "")).len(), 5);
+        assert!(names(query("zzz", "")).is_empty());
+        assert!(names(query("cloud", "org")).is_empty());
+
+        assert_eq!(index.get("cloudy.com").unwrap().domain, "CLOUDY.COM");
+        assert_eq!(index.get("AB.com").unwrap().domain, "ab.com");
+        assert!(index.get("cloud.com").is_none());
+    }
+
+    #[tokio::test]
+    async fn revalidates_with_etag_and_falls_back_to_stale_copy() {
+        let (base, downloads, not_modified) = serve("cloudshop.com\nmycloud.io").await;
+        let missing = format!("{}/missing", base);
+        let list = format!("{}/list.txt", base);
+        let client = Client::new();
+
+        // Fresh for an hour: the second search never touches the network.
+        let fresh = cache("fresh", Duration::from_secs(3600));
+        let index = fresh
+            .list(&client, "test", &[&missing, &list], parse_lines)
+            .await
+            .unwrap();
+        assert_eq!(index.len(), 2);
+        let again = fresh
+            .list(&client, "test", &[&missing, &list], parse_lines)
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&index, &again));
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+
+        // Always stale: revalidated with the stored ETag instead of downloaded.
+        let stale = DropCache::new(DropCacheConfig {
+            refresh_interval: Duration::ZERO,
+            ..fresh.config().clone()
+        });
+        let index = stale
+            .list(&client, "test", &[&missing, &list], parse_lines)
+            .await
+            .unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+        assert_eq!(not_modified.load(Ordering::SeqCst), 1);
+
+        // Provider gone: the cached copy is still served.
+        let index = stale
+            .list(&client, "test", &[&missing], parse_lines)
+            .await
+            .unwrap();
+        assert_eq!(index.len(), 2);
+
+        let empty = cache("empty", Duration::ZERO);
+        let error = empty
+            .list(&client, "test", &[&missing], parse_lines)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("404"));
+        let _ = std::fs::remove_dir_all(&fresh.config().dir);
+    }
+
+    #[tokio::test]
+    async fn keeps_cached_copy_when_new_download_does_not_parse() {
+        let (base, downloads, _) = serve("cloudshop.com\nmycloud.io").await;
+        let list = format!("{}/list.txt", base);
+        let broken = format!("{}/broken", base);
+        let client = Client::new();
+
+        let stale = cache("unparseable", Duration::ZERO);
+        let index = stale
+            .list(&client, "test", &[&list], parse_domains)
+            .await
+            .unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+
+        let index = stale
+            .list(&client, "test", &[&broken], parse_domains)
+            .await
+            .unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search(&query("cloud", "io")).len(), 1);
+
+        let empty = cache("unparseable-empty", Duration::ZERO);
+        let error = empty
+            .list(&client, "test", &[&broken], parse_domains)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not a domain"));
+        let _ = std::fs::remove_dir_all(&stale.config().dir);
+        let _ = std::fs::remove_dir_all(&empty.config().dir);
+    }
+
+    #[tokio::test]
+    async fn imports_and_reloads_lists() {
+        let disabled = cache("import", Duration::from_secs(3600));
+        let import_dir = disabled.config().dir.with_extension("imports");
+        std::fs::create_dir_all(&import_dir).unwrap();
+        let source = import_dir.join("list.csv");
+        std::fs::write(&source, "cloudshop.com\nmycloud.io\n").unwrap();
+        assert!(disabled.import("mine", &source, parse_lines).await.is_err());
+
+        let cache = DropCache::new(DropCacheConfig {
+            import_dir: Some(import_dir.clone()),
+            ..disabled.config().clone()
+        });
+        let imported = cache
+            .import("mine", Path::new("list.csv"), parse_lines)
+            .await
+            .unwrap();
+        assert_eq!(imported.domains, 2);
+        assert_eq!(cache.imported_names(), vec!["mine"]);
+        let index = cache.imported("mine", parse_lines).await.unwrap();
+        assert_eq!(index.search(&query("cloud", "io")).len(), 1);
+
+        std::fs::write(&source, "").unwrap();
+        assert!(cache.import("mine", &source, parse_lines).await.is_err());
+        assert!(cache.imported("other", parse_lines).await.is_err());
+
+        let _ = std::fs::remove_dir_all(&import_dir);
+        let _ = std::fs::remove_dir_all(&cache.config().dir);
+    }
+
+    #[tokio::test]
+    async fn refuses_imports_outside_the_import_dir() {
+        let cache = cache("import-outside", Duration::from_secs(3600));
+        let import_dir = cache.config().dir.with_extension("imports");
+        std::fs::create_dir_all(&import_dir).unwrap();
+        let outside = cache.config().dir.with_extension("csv");
+        std::fs::write(&outside, "cloudshop.com\n").unwrap();
+        let cache = DropCache::new(DropCacheConfig {
+            import_dir: Some(import_dir.clone()),
+            ..cache.config().clone()
+        });
+
+        let escaping = import_dir.join("..").join(outside.file_name().unwrap());
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&outside, import_dir.join("link.csv")).unwrap();
+        for path in [outside.as_path(), &escaping, Path::new("link.csv")] {
+            let error = cache.import("mine", path, parse_lines).await.unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("is not a file in the import directory"));
+        }
+        assert!(cache.imported_names().is_empty());
+
+        let _ = std::fs::remove_file(&outside);
+        let _ = std::fs::remove_dir_all(&import_dir);
+    }
+}
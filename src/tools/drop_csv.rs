@@ -0,0 +1,326 @@
+use anyhow::Result;
+use csv::{ReaderBuilder, StringRecord, Trim};
+
+use super::expired::ExpiredDomain;
+
+/// A column a drop list may carry, found by its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Domain,
+    EndTime,
+    Appraisal,
+    Price,
+    Bids,
+}
+
+impl Column {
+    const ALL: [Column; 5] = [
+        Column::Domain,
+        Column::EndTime,
+        Column::Appraisal,
+        Column::Price,
+        Column::Bids,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Column::Domain => "domain",
+            Column::EndTime => "end time",
+            Column::Appraisal => "appraisal",
+            Column::Price => "price",
+            Column::Bids => "bids",
+        }
+    }
+
+    /// Header names seen for this column, lowercase with everything but
+    /// letters and digits removed.
+    fn headers(self) -> &'static [&'static str] {
+        match self {
+            Column::Domain => &["domain", "domainname", "name"],
+            Column::EndTime => &[
+                "endtime",
+                "enddate",
+                "auctionendtime",
+                "auctionend",
+                "closingtime",
+                "deletedate",
+                "dropdate",
+                "end",
+            ],
+            Column::Appraisal => &[
+                "appraisal",
+                "appraisalvalue",
+                "estibotappraisal",
+                "estibotvalue",
+                "estimatedvalue",
+                "value",
+            ],
+            Column::Price => &[
+                "price",
+                "startingprice",
+                "startprice",
+                "currentprice",
+                "currentbid",
+                "minimumbid",
+                "minbid",
+            ],
+            Column::Bids => &["bids", "bidcount", "numberofbids", "numbids"],
+        }
+    }
+}
+
+/// Where each known column sits in a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schema {
+    pub domain: usize,
+    pub end_time: Option<usize>,
+    pub appraisal: Option<usize>,
+    pub price: Option<usize>,
+    pub bids: Option<usize>,
+}
+
+impl Schema {
+    /// A list with no header and the domain in the first column.
+    pub const DOMAINS_ONLY: Schema = Schema {
+        domain: 0,
+        end_time: None,
+        appraisal: None,
+        price: None,
+        bids: None,
+    };
+
+    /// Find the columns in `header`, failing if any of `required` (and
+    /// always the domain) is missing. The error names the missing columns
+    /// but not the header, which the caller may not want to repeat.
+    pub fn detect(header: &StringRecord, required: &[Column]) -> Result<Self> {
+        let normalized: Vec<String> = header
+            .iter()
+            .map(|name| {
+                name.chars()
+                    .filter(char::is_ascii_alphanumeric)
+                    .collect::<String>()
+                    .to_lowercase()
+            })
+            .collect();
+        let find = |column: Column| {
+            normalized
+                .iter()
+                .position(|name| column.headers().contains(&name.as_str()))
+        };
+
+        let missing: Vec<&str> = Column::ALL
+            .iter()
+            .filter(|column| {
+                (**column == Column::Domain || required.contains(column))
+                    && find(**column).is_none()
+            })
+            .map(|column| column.label())
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!("No {} column", missing.join(", ")));
+        }
+
+        Ok(Schema {
+            domain: find(Column::Domain).unwrap_or_default(),
+            end_time: find(Column::EndTime),
+            appraisal: find(Column::Appraisal),
+            price: find(Column::Price),
+            bids: find(Column::Bids),
+        })
+    }
+}
+
+/// How to read one provider's list.
+#[derive(Debug, Clone, Copy)]
+pub struct ListFormat {
+    /// Provider name for results and errors, e.g. "Dynadot".
+    pub source: &'static str,
+    /// Lifecycle status given to every domain on the list.
+    pub status: &'static str,
+    /// Columns the header must have besides the domain.
+    pub required: &'static [Column],
+    /// Whether a bare list of domains, without a header, is accepted.
+    pub headerless: bool,
+    /// Quote the header row in format errors. Off for user files, whose
+    /// first line may be anything.
+    pub show_header: bool,
+}
+
+/// Parse `text` as a CSV drop list, finding the columns from its header row.
+/// Lines starting with '#' are comments. Rows without a domain are skipped.
+pub fn parse(text: &str, format: &ListFormat) -> Result<Vec<ExpiredDomain>> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(text.as_bytes());
+    let mut records = reader.records();
+
+    let Some(first) = records.next().transpose()? else {
+        return Err(anyhow::anyhow!("{} list is empty", format.source));
+    };
+    // A first row that already holds a domain is data, not a header.
+    let (schema, first) = if format.headerless && first.get(0).is_some_and(is_domain) {
+        (Schema::DOMAINS_ONLY, Some(first))
+    } else {
+        let schema = Schema::detect(&first, format.required).map_err(|e| {
+            if format.show_header {
+                let header = first.iter().collect::<Vec<_>>().join(", ");
+                anyhow::anyhow!(
+                    "{} list format changed: {} in header [{}]",
+                    format.source,
+                    e,
+                    header
+                )
+            } else {
+                anyhow::anyhow!("{} list format not recognised: {}", format.source, e)
+            }
+        })?;
+        (schema, None)
+    };
+
+    let mut domains = Vec::new();
+    for record in first.into_iter().map(Ok).chain(records) {
+        let record = record.map_err(|e| anyhow::anyhow!("{} list: {}", format.source, e))?;
+        let field = |position: Option<usize>| {
+            position
+                .and_then(|position| record.get(position))
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let Some(domain) = record.get(schema.domain).filter(|value| is_domain(value)) else {
+            continue;
+        };
+
+        domains.push(ExpiredDomain {
+            domain: domain.to_lowercase(),
+            status: format.status.to_string(),
+            source: format.source.to_string(),
+            created: None,
+            updated: None,
+            end_time: field(schema.end_time),
+            appraisal: field(schema.appraisal),
+            starting_price: field(schema.price),
+            bids: field(schema.bids).and_then(|bids| bids.replace(',', "").parse().ok()),
+            has_dns: None,
+            has_website: None,
+            score: None,
+            matched_keywords: Vec::new(),
+        });
+    }
+    Ok(domains)
+}
+
+fn is_domain(value: &str) -> bool {
+    let value = value.trim_end_matches('.');
+    value.contains('.')
+        && !value.starts_with('.')
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DYNADOT: &str = include_str!("../../tests/fixtures/drop-lists/dynadot.csv");
+    const DYNADOT_REORDERED: &str =
+        include_str!("../../tests/fixtures/drop-lists/dynadot-reordered.csv");
+    const NAMEJET_TXT: &str = include_str!("../../tests/fixtures/drop-lists/namejet.txt");
+    const SNAPNAMES: &str = include_str!("../../tests/fixtures/drop-lists/snapnames.csv");
+
+    const AUCTION: ListFormat = ListFormat {
+        source: "Dynadot",
+        status: "pending delete",
+        required: &[Column::EndTime],
+        headerless: false,
+        show_header: true,
+    };
+    const PLAIN: ListFormat = ListFormat {
+        source: "NameJet",
+        status: "auction/pending",
+        required: &[],
+        headerless: true,
+        show_header: true,
+    };
+
+    #[test]
+    fn detects_columns_from_header() {
+        let header = StringRecord::from(vec!["Domain Name", "Bids", "Current Bid", "End Date"]);
+        assert_eq!(
+            Schema::detect(&header, &[Column::EndTime]).unwrap(),
+            Schema {
+                domain: 0,
+                end_time: Some(3),
+                appraisal: None,
+                price: Some(2),
+                bids: Some(1),
+            }
+        );
+
+        let error = Schema::detect(&header, &[Column::Appraisal])
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "No appraisal column");
+    }
+
+    #[test]
+    fn parses_quoted_fields_with_commas() {
+        let domains = parse(DYNADOT, &AUCTION).unwrap();
+        assert_eq!(domains.len(), 3);
+
+        let premium = &domains[1];
+        assert_eq!(premium.domain, "cloudshop.com");
+        assert_eq!(premium.end_time.as_deref(), Some("2024/03/01 10:00 PST"));
+        assert_eq!(premium.appraisal.as_deref(), Some("$1,200"));
+        assert_eq!(premium.starting_price.as_deref(), Some("$1,050.00"));
+        assert_eq!(premium.bids, Some(12));
+        assert_eq!(premium.source, "Dynadot");
+    }
+
+    #[test]
+    fn reordered_columns_keep_their_values() {
+        let original = parse(DYNADOT, &AUCTION).unwrap();
+        let reordered = parse(DYNADOT_REORDERED, &AUCTION).unwrap();
+        let values = |domains: &[ExpiredDomain]| {
+            domains
+                .iter()
+                .map(|d| {
+                    (
+                        d.domain.clone(),
+                        d.end_time.clone(),
+                        d.appraisal.clone(),
+                        d.starting_price.clone(),
+                        d.bids,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(&original), values(&reordered));
+    }
+
+    #[test]
+    fn plain_lists_and_comments() {
+        let domains = parse(NAMEJET_TXT, &PLAIN).unwrap();
+        let names: Vec<&str> = domains.iter().map(|d| d.domain.as_str()).collect();
+        assert_eq!(names, vec!["shopfast.net", "mycloud.io", "cloudy.com"]);
+
+        let domains = parse(SNAPNAMES, &PLAIN).unwrap();
+        assert_eq!(domains.len(), 2);
+        assert_eq!(domains[0].end_time.as_deref(), Some("2024-03-05"));
+    }
+
+    #[test]
+    fn changed_schema_is_an_error() {
+        let error = parse("Name Server,Expiry\nns1.example.com,2024-01-01\n", &AUCTION)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Dynadot list format changed: No domain, end time column in header [Name Server, Expiry]"
+        );
+        assert!(parse("", &AUCTION).is_err());
+    }
+}
@@ -1,7 +1,28 @@
 use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, NaiveDateTime, Utc};
+use futures::future::try_join_all;
+use futures::{stream, StreamExt};
+use regex::Regex;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::dns;
+use super::domain::{self, BulkPage};
+use super::drop_cache::{DropCache, DropListIndex, ImportedDropList, ListParser};
+use super::drop_csv::{self, Column, ListFormat};
+use super::scheduler::Scheduler;
+use super::scoring::{self, DomainScore};
+use super::words;
+
+/// Results per page when the caller does not pass a limit.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+/// The most domains DomainsDB returns per request.
+const DOMAINSDB_PAGE_SIZE: usize = 50;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExpiredDomain {
@@ -19,7 +40,17 @@ pub struct ExpiredDomain {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub starting_price: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub bids: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub has_dns: Option<bool>,
+    /// An A record was published, so the name likely served a website.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_website: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<DomainScore>,
+    /// The search keywords this domain matched.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_keywords: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -40,341 +71,1793 @@ struct DomainsDBDomain {
     ns_records: Option<Vec<String>>,
 }
 
-pub async fn search_expired_domains(keyword: &str, tld: &str) -> Result<Vec<ExpiredDomain>> {
-    let client = Client::builder()
-        .user_agent("Domain-MCP-Rust/1.0")
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
+/// Which sources to use, how long each may take, and how many results each
+/// keyword/TLD query keeps (no cap by default).
+#[derive(Debug, Clone)]
+pub struct ExpiredSourcesConfig {
+    /// Only these sources, when set; otherwise every registered source.
+    pub enabled: Option<Vec<String>>,
+    pub disabled: Vec<String>,
+    pub source_timeout: Duration,
+    pub max_results_per_query: Option<usize>,
+    /// DNS lookups one search may make for a `has_dns` filter on matches
+    /// whose source did not say.
+    pub max_dns_lookups: usize,
+}
 
-    let mut domains = Vec::new();
-    let mut seen_domains = HashSet::new();
-
-    // Method 1: DomainsDB API - Primary source for expired domains
-    if let Ok(domainsdb_results) = search_domainsdb(&client, keyword, tld).await {
-        for domain in domainsdb_results {
-            if !seen_domains.contains(&domain.domain) {
-                seen_domains.insert(domain.domain.clone());
-                domains.push(domain);
-                if domains.len() >= 10 {
-                    return Ok(domains);
-                }
-            }
+impl Default for ExpiredSourcesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: None,
+            disabled: Vec::new(),
+            source_timeout: Duration::from_secs(30),
+            max_results_per_query: None,
+            max_dns_lookups: 200,
         }
     }
+}
 
-    // Method 2: Dynadot CSV - Pending delete domains with appraisal values
-    if domains.len() < 10 {
-        if let Ok(dynadot_results) = search_dynadot(&client, keyword, tld).await {
-            for domain in dynadot_results {
-                if !seen_domains.contains(&domain.domain) {
-                    seen_domains.insert(domain.domain.clone());
-                    domains.push(domain);
-                    if domains.len() >= 10 {
-                        return Ok(domains);
-                    }
-                }
+impl ExpiredSourcesConfig {
+    /// Defaults, overridden by `DOMAIN_MCP_EXPIRED_SOURCES` and
+    /// `DOMAIN_MCP_EXPIRED_DISABLED_SOURCES` (comma separated source names),
+    /// `DOMAIN_MCP_EXPIRED_SOURCE_TIMEOUT_SECS`,
+    /// `DOMAIN_MCP_EXPIRED_MAX_PER_QUERY` and
+    /// `DOMAIN_MCP_EXPIRED_MAX_DNS_LOOKUPS`.
+    pub fn from_env() -> Self {
+        fn names(name: &str) -> Option<Vec<String>> {
+            let value = std::env::var(name).ok()?;
+            Some(
+                value
+                    .split(',')
+                    .map(|name| name.trim().to_lowercase())
+                    .filter(|name| !name.is_empty())
+                    .collect(),
+            )
+        }
+
+        let mut config = Self {
+            enabled: names("DOMAIN_MCP_EXPIRED_SOURCES"),
+            ..Self::default()
+        };
+        if let Some(disabled) = names("DOMAIN_MCP_EXPIRED_DISABLED_SOURCES") {
+            config.disabled = disabled;
+        }
+        if let Some(value) = std::env::var("DOMAIN_MCP_EXPIRED_SOURCE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+        {
+            config.source_timeout = Duration::from_secs(value);
+        }
+        if let Some(value) = std::env::var("DOMAIN_MCP_EXPIRED_MAX_PER_QUERY")
+            .ok()
+            .and_then(|value| value.parse().ok())
+        {
+            config.max_results_per_query = Some(value);
+        }
+        if let Some(value) = std::env::var("DOMAIN_MCP_EXPIRED_MAX_DNS_LOOKUPS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+        {
+            config.max_dns_lookups = value;
+        }
+        config
+    }
+
+    fn is_enabled(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.enabled
+            .as_ref()
+            .is_none_or(|enabled| enabled.contains(&name))
+            && !self.disabled.contains(&name)
+    }
+}
+
+/// One keyword/TLD combination; an empty field matches anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiredQuery {
+    pub keyword: String,
+    pub tld: String,
+}
+
+impl ExpiredQuery {
+    pub fn matches(&self, domain: &str) -> bool {
+        let domain = domain.to_lowercase();
+        (self.keyword.is_empty() || domain.contains(&self.keyword))
+            && (self.tld.is_empty() || domain.ends_with(&format!(".{}", self.tld)))
+    }
+}
+
+/// Somewhere to find expiring or deleted domains.
+#[async_trait]
+pub trait ExpiredDomainSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Matches for each query, in query order. Sources that download a whole
+    /// list should fetch it once and filter it for every query.
+    async fn search(&self, queries: &[ExpiredQuery]) -> Result<Vec<Vec<ExpiredDomain>>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceReport {
+    pub name: String,
+    pub status: SourceStatus,
+    /// New domains this source added to the results.
+    pub found: usize,
+    /// Why the source failed or was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpiredSearchResult {
+    pub domains: Vec<ExpiredDomain>,
+    /// Every registered source in the order tried, so an empty result can be
+    /// told apart from every source being down.
+    pub sources: Vec<SourceReport>,
+    /// Where `domains` sits within all the merged, sorted matches.
+    pub page: BulkPage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    EndTime,
+    Appraisal,
+    Length,
+    Age,
+    Score,
+}
+
+impl SortKey {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "end_time" => Ok(SortKey::EndTime),
+            "appraisal" => Ok(SortKey::Appraisal),
+            "length" => Ok(SortKey::Length),
+            "age" => Ok(SortKey::Age),
+            "score" => Ok(SortKey::Score),
+            _ => Err(anyhow::anyhow!(
+                "Unknown sort key '{}'. Use end_time, appraisal, length, age or score",
+                name
+            )),
+        }
+    }
+
+    /// Soonest auction end and shortest name first; highest appraisal, oldest
+    /// registration and best score first.
+    pub fn default_order(self) -> SortOrder {
+        match self {
+            SortKey::EndTime | SortKey::Length => SortOrder::Asc,
+            SortKey::Appraisal | SortKey::Age | SortKey::Score => SortOrder::Desc,
+        }
+    }
+
+    /// A comparable value for `domain`, or `None` when the source did not
+    /// supply one. Larger ages mean older registrations.
+    fn value(self, domain: &ExpiredDomain) -> Option<f64> {
+        match self {
+            SortKey::EndTime => domain
+                .end_time
+                .as_deref()
+                .and_then(parse_date)
+                .map(|date| date.timestamp() as f64),
+            SortKey::Appraisal => domain.appraisal.as_deref().and_then(parse_money),
+            SortKey::Length => Some(label(&domain.domain).chars().count() as f64),
+            SortKey::Age => domain
+                .created
+                .as_deref()
+                .and_then(parse_date)
+                .map(|date| -(date.timestamp() as f64)),
+            SortKey::Score => domain.score.as_ref().map(|score| score.total),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "asc" | "ascending" => Ok(SortOrder::Asc),
+            "desc" | "descending" => Ok(SortOrder::Desc),
+            _ => Err(anyhow::anyhow!("Unknown order '{}'. Use asc or desc", name)),
+        }
+    }
+}
+
+/// Conditions every returned domain must meet; unset fields let everything
+/// through. A filter on a value the source did not supply (age, price)
+/// excludes the domain; DNS presence is looked up when a source omits it.
+#[derive(Debug, Clone, Default)]
+pub struct ExpiredFilters {
+    /// Bounds on the length of the label before the TLD.
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub no_hyphens: bool,
+    pub no_digits: bool,
+    /// The label is one or more words from the bundled dictionary.
+    pub dictionary_words_only: bool,
+    /// Matched against the lowercase label.
+    pub pattern: Option<Regex>,
+    /// Days since `created`.
+    pub min_age_days: Option<i64>,
+    /// Upper bound on `starting_price`.
+    pub max_price: Option<f64>,
+    pub has_dns: Option<bool>,
+}
+
+impl ExpiredFilters {
+    pub fn matches(&self, domain: &ExpiredDomain, now: DateTime<Utc>) -> bool {
+        let label = label(&domain.domain).to_lowercase();
+        let length = label.chars().count();
+
+        self.min_length.is_none_or(|min| length >= min)
+            && self.max_length.is_none_or(|max| length <= max)
+            && !(self.no_hyphens && label.contains('-'))
+            && !(self.no_digits && label.chars().any(|c| c.is_ascii_digit()))
+            && !(self.dictionary_words_only && words::segment(&label).is_none())
+            && self
+                .pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(&label))
+            && self.min_age_days.is_none_or(|min| {
+                domain
+                    .created
+                    .as_deref()
+                    .and_then(parse_date)
+                    .is_some_and(|created| (now - created).num_days() >= min)
+            })
+            && self.max_price.is_none_or(|max| {
+                domain
+                    .starting_price
+                    .as_deref()
+                    .and_then(parse_money)
+                    .is_some_and(|price| price <= max)
+            })
+            && self
+                .has_dns
+                .is_none_or(|wanted| domain.has_dns == Some(wanted))
+    }
+}
+
+/// How to filter, sort and page the merged results.
+#[derive(Debug, Clone)]
+pub struct ExpiredSearchOptions {
+    pub filters: ExpiredFilters,
+    /// Keep the sources' order when unset.
+    pub sort_by: Option<SortKey>,
+    /// The sort key's natural order when unset.
+    pub order: Option<SortOrder>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl ExpiredSearchOptions {
+    /// Options from the tool's string parameters.
+    pub fn new(
+        filters: ExpiredFilters,
+        sort_by: Option<&str>,
+        order: Option<&str>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<Self> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if limit == 0 {
+            return Err(anyhow::anyhow!("Limit must be at least 1"));
+        }
+        if let (Some(min), Some(max)) = (filters.min_length, filters.max_length) {
+            if min > max {
+                return Err(anyhow::anyhow!(
+                    "min_length {} is greater than max_length {}",
+                    min,
+                    max
+                ));
             }
         }
+        Ok(Self {
+            filters,
+            sort_by: sort_by.map(SortKey::parse).transpose()?,
+            order: order.map(SortOrder::parse).transpose()?,
+            offset,
+            limit,
+        })
+    }
+}
+
+impl Default for ExpiredSearchOptions {
+    fn default() -> Self {
+        Self {
+            filters: ExpiredFilters::default(),
+            sort_by: None,
+            order: None,
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// The sources to search, tried in order; later ones only run while some
+/// query still has room for results.
+pub struct SourceRegistry {
+    sources: Vec<Box<dyn ExpiredDomainSource>>,
+    config: ExpiredSourcesConfig,
+}
+
+impl SourceRegistry {
+    pub fn new(config: ExpiredSourcesConfig) -> Self {
+        Self {
+            sources: Vec::new(),
+            config,
+        }
+    }
+
+    /// DomainsDB, then the Dynadot, NameJet and SnapNames drop lists, then
+    /// any imported lists.
+    pub fn with_defaults(config: ExpiredSourcesConfig) -> Result<Self> {
+        let client = Client::builder()
+            .user_agent("Domain-MCP-Rust/1.0")
+            .timeout(config.source_timeout)
+            .build()?;
+        let domainsdb =
+            DomainsDbSource::new(client.clone()).with_max_results(config.max_results_per_query);
+        let mut registry = Self::new(config)
+            .with_source(Box::new(domainsdb))
+            .with_source(Box::new(DropListSource::dynadot(client.clone())))
+            .with_source(Box::new(DropListSource::namejet(client.clone())))
+            .with_source(Box::new(DropListSource::snapnames(client)));
+        for name in DropCache::global().imported_names() {
+            registry = registry.with_source(Box::new(ImportedListSource::new(&name)));
+        }
+        Ok(registry)
     }
 
-    // Method 3: NameJet inventory files
-    if domains.len() < 10 {
-        if let Ok(namejet_results) = search_namejet(&client, keyword, tld).await {
-            for domain in namejet_results {
-                if !seen_domains.contains(&domain.domain) {
-                    seen_domains.insert(domain.domain.clone());
-                    domains.push(domain);
-                    if domains.len() >= 10 {
-                        return Ok(domains);
+    pub fn with_source(mut self, source: Box<dyn ExpiredDomainSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.sources.iter().map(|source| source.name()).collect()
+    }
+
+    /// Search every keyword with every TLD (an empty list means "any"),
+    /// keeping the domains that pass the filters, merging the results and
+    /// recording which keywords matched each domain, then sort them and return
+    /// the requested page.
+    pub async fn search(
+        &self,
+        keywords: &[String],
+        tlds: &[String],
+        options: &ExpiredSearchOptions,
+    ) -> Result<ExpiredSearchResult> {
+        let queries = queries(keywords, tlds);
+        let limit = self.config.max_results_per_query;
+        let now = Utc::now();
+        let mut found: Vec<QueryResults> = vec![QueryResults::default(); queries.len()];
+        let mut dns = DnsLookups::new(self.config.max_dns_lookups);
+        let mut reports = Vec::new();
+
+        for source in &self.sources {
+            let mut report = SourceReport {
+                name: source.name().to_string(),
+                status: SourceStatus::Skipped,
+                found: 0,
+                reason: None,
+                elapsed_ms: None,
+            };
+            if !self.config.is_enabled(source.name()) {
+                report.reason = Some("Disabled in config".to_string());
+                reports.push(report);
+                continue;
+            }
+            if let Some(limit) = limit {
+                if found.iter().all(|results| results.len() >= limit) {
+                    report.reason = Some(format!("Every query already has {} results", limit));
+                    reports.push(report);
+                    continue;
+                }
+            }
+
+            let started = Instant::now();
+            // DNS lookups for `has_dns` count against the source's time limit.
+            let outcome = tokio::time::timeout(self.config.source_timeout, async {
+                let mut matches = source.search(&queries).await?;
+                let unresolved = dns.resolve(&mut matches, &options.filters, now).await;
+                Ok::<_, anyhow::Error>((matches, unresolved))
+            })
+            .await;
+            report.elapsed_ms = Some(started.elapsed().as_millis() as u64);
+            match outcome {
+                Ok(Ok((matches, unresolved))) => {
+                    report.status = SourceStatus::Succeeded;
+                    if unresolved > 0 {
+                        report.reason = Some(format!(
+                            "{} matches left out: the limit of {} DNS lookups was reached",
+                            unresolved, self.config.max_dns_lookups
+                        ));
+                    }
+                    for (results, matches) in found.iter_mut().zip(matches) {
+                        let matches = matches
+                            .into_iter()
+                            .filter(|domain| options.filters.matches(domain, now));
+                        report.found += results.add(matches, limit);
                     }
                 }
+                Ok(Err(e)) => {
+                    report.status = SourceStatus::Failed;
+                    report.reason = Some(format!("{:#}", e));
+                }
+                Err(_) => {
+                    report.status = SourceStatus::Failed;
+                    report.reason = Some(format!(
+                        "Timed out after {}s",
+                        self.config.source_timeout.as_secs_f64()
+                    ));
+                }
             }
+            reports.push(report);
+        }
+
+        let found = found.into_iter().map(|results| results.domains).collect();
+        let mut domains = merge(&queries, found);
+        for domain in &mut domains {
+            domain.score = Some(scoring::score(domain, now));
         }
+        if let Some(key) = options.sort_by {
+            sort(
+                &mut domains,
+                key,
+                options.order.unwrap_or(key.default_order()),
+            );
+        }
+        let (domains, page) = domain::paginate(domains, options.offset, Some(options.limit))?;
+
+        Ok(ExpiredSearchResult {
+            domains,
+            sources: reports,
+            page,
+        })
     }
+}
 
-    // Method 4: SnapNames CSV as fallback
-    if domains.len() < 10 {
-        if let Ok(snapnames_results) = search_snapnames(&client, keyword, tld).await {
-            for domain in snapnames_results {
-                if !seen_domains.contains(&domain.domain) {
-                    seen_domains.insert(domain.domain.clone());
-                    domains.push(domain);
-                    if domains.len() >= 10 {
-                        return Ok(domains);
-                    }
-                }
+/// Search with the default sources, configured from the environment.
+pub async fn search_expired_domains(
+    keywords: &[String],
+    tlds: &[String],
+    options: &ExpiredSearchOptions,
+) -> Result<ExpiredSearchResult> {
+    SourceRegistry::with_defaults(ExpiredSourcesConfig::from_env())?
+        .search(keywords, tlds, options)
+        .await
+}
+
+/// NS and A presence looked up for a `has_dns` filter, shared by every
+/// source in one search so a domain is looked up at most once.
+struct DnsLookups {
+    /// Lowercase domain to (has NS, has A); `None` when the lookup failed.
+    found: HashMap<String, Option<(bool, bool)>>,
+    remaining: usize,
+}
+
+impl DnsLookups {
+    fn new(limit: usize) -> Self {
+        Self {
+            found: HashMap::new(),
+            remaining: limit,
+        }
+    }
+
+    /// Fill in `has_dns` where the source did not say, so a `has_dns` filter
+    /// can judge those domains rather than drop them. Only domains passing
+    /// every other filter are looked up, each once across all queries, and
+    /// at most `remaining` of them. Returns how many were left unresolved
+    /// because the limit was reached; a failed lookup leaves the value unset.
+    async fn resolve(
+        &mut self,
+        matches: &mut [Vec<ExpiredDomain>],
+        filters: &ExpiredFilters,
+        now: DateTime<Utc>,
+    ) -> usize {
+        if filters.has_dns.is_none() {
+            return 0;
+        }
+        let offline = ExpiredFilters {
+            has_dns: None,
+            ..filters.clone()
+        };
+
+        let mut wanted = Vec::new();
+        let mut seen = HashSet::new();
+        for domain in matches.iter().flatten() {
+            let name = domain.domain.to_lowercase();
+            if domain.has_dns.is_none()
+                && !self.found.contains_key(&name)
+                && offline.matches(domain, now)
+                && seen.insert(name.clone())
+            {
+                wanted.push(name);
             }
         }
+        let unresolved = wanted.len().saturating_sub(self.remaining);
+        wanted.truncate(self.remaining);
+        self.remaining -= wanted.len();
+
+        let concurrency = Scheduler::global().config().domain_concurrency.max(1);
+        let looked_up: Vec<(String, Option<(bool, bool)>)> = stream::iter(wanted)
+            .map(|name| async move {
+                let present = dns::ns_and_a_present(&name).await;
+                (name, present)
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        self.found.extend(looked_up);
+
+        for domain in matches.iter_mut().flatten() {
+            if domain.has_dns.is_some() {
+                continue;
+            }
+            if let Some(Some((has_ns, has_a))) = self.found.get(&domain.domain.to_lowercase()) {
+                domain.has_dns = Some(*has_ns || *has_a);
+                domain.has_website.get_or_insert(*has_a);
+            }
+        }
+        unresolved
     }
+}
 
-    Ok(domains)
+/// The part of `domain` before its first dot.
+fn label(domain: &str) -> &str {
+    domain.split('.').next().unwrap_or(domain)
 }
 
-async fn search_domainsdb(client: &Client, keyword: &str, tld: &str) -> Result<Vec<ExpiredDomain>> {
-    let mut params = vec![("isDead", "true"), ("limit", "50")];
+/// Stable sort by `key`; domains without a value for it always come last.
+fn sort(domains: &mut Vec<ExpiredDomain>, key: SortKey, order: SortOrder) {
+    let mut keyed: Vec<(Option<f64>, ExpiredDomain)> = domains
+        .drain(..)
+        .map(|domain| (key.value(&domain), domain))
+        .collect();
+    keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => match order {
+            SortOrder::Asc => a.total_cmp(b),
+            SortOrder::Desc => b.total_cmp(a),
+        },
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    domains.extend(keyed.into_iter().map(|(_, domain)| domain));
+}
 
-    let keyword_owned;
-    let tld_owned;
+/// Amounts such as "$1,250.00" or "1250 USD".
+fn parse_money(value: &str) -> Option<f64> {
+    let digits: String = value
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    digits.parse().ok()
+}
 
-    if !keyword.is_empty() {
-        keyword_owned = keyword.to_string();
-        params.push(("domain", &keyword_owned));
+/// The date formats the sources use, with any trailing time zone name
+/// (e.g. "PST") ignored.
+pub(crate) fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
     }
+    // Lists stamp local times with a zone name; unknown names are read as UTC.
+    let (value, offset_hours) = match value.rsplit_once(' ') {
+        Some((rest, zone)) if zone.chars().all(|c| c.is_ascii_alphabetic()) => {
+            (rest, zone_offset_hours(zone).unwrap_or(0))
+        }
+        _ => (value, 0),
+    };
+    let offset = ChronoDuration::hours(offset_hours);
 
-    if !tld.is_empty() {
-        tld_owned = tld.to_string();
-        params.push(("zone", &tld_owned));
+    let datetime_formats = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y/%m/%d %H:%M",
+    ];
+    for format in &datetime_formats {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(date.and_utc() - offset);
+        }
     }
 
-    let response = client
-        .get("https://api.domainsdb.info/v1/domains/search")
-        .query(&params)
-        .send()
-        .await?;
+    let date_formats = ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d-%b-%Y"];
+    for format in &date_formats {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Some(date.and_hms_opt(0, 0, 0)?.and_utc() - offset);
+        }
+    }
 
-    if !response.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "DomainsDB API returned status: {}",
-            response.status()
-        ));
+    None
+}
+
+fn zone_offset_hours(zone: &str) -> Option<i64> {
+    match zone.to_ascii_uppercase().as_str() {
+        "UTC" | "GMT" | "Z" => Some(0),
+        "EST" => Some(-5),
+        "EDT" => Some(-4),
+        "CST" => Some(-6),
+        "CDT" => Some(-5),
+        "MST" => Some(-7),
+        "MDT" => Some(-6),
+        "PST" => Some(-8),
+        "PDT" => Some(-7),
+        _ => None,
     }
+}
 
-    let data: DomainsDBResponse = response.json().await?;
-    let mut results = Vec::new();
+fn queries(keywords: &[String], tlds: &[String]) -> Vec<ExpiredQuery> {
+    let clean = |values: &[String], trim: &[char]| {
+        let mut seen = HashSet::new();
+        let mut values: Vec<String> = values
+            .iter()
+            .map(|value| value.trim().trim_matches(trim).to_lowercase())
+            .filter(|value| !value.is_empty() && seen.insert(value.clone()))
+            .collect();
+        if values.is_empty() {
+            values.push(String::new());
+        }
+        values
+    };
+    let keywords = clean(keywords, &[]);
+    let tlds = clean(tlds, &['.']);
 
-    if let Some(domains) = data.domains {
-        for domain_info in domains {
-            if let Some(domain_name) = domain_info.domain {
-                if domain_name.contains('.') {
-                    // Additional filtering for keyword and TLD
-                    let matches_keyword = keyword.is_empty()
-                        || domain_name.to_lowercase().contains(&keyword.to_lowercase());
-                    let matches_tld = tld.is_empty() || domain_name.ends_with(&format!(".{}", tld));
-
-                    if matches_keyword && matches_tld {
-                        let has_dns =
-                            domain_info.a_records.is_some() || domain_info.ns_records.is_some();
-
-                        results.push(ExpiredDomain {
-                            domain: domain_name,
-                            status: if domain_info.is_dead == Some("True".to_string()) {
-                                "expired".to_string()
-                            } else {
-                                "unknown".to_string()
-                            },
-                            source: "DomainsDB".to_string(),
-                            created: domain_info.create_date,
-                            updated: domain_info.update_date,
-                            end_time: None,
-                            appraisal: None,
-                            starting_price: None,
-                            has_dns: Some(has_dns),
-                        });
-
-                        if results.len() >= 10 {
-                            break;
-                        }
-                    }
+    keywords
+        .iter()
+        .flat_map(|keyword| {
+            tlds.iter().map(move |tld| ExpiredQuery {
+                keyword: keyword.clone(),
+                tld: tld.clone(),
+            })
+        })
+        .collect()
+}
+
+/// One query's results so far, with their lowercase names so duplicates are
+/// found without scanning the list.
+#[derive(Debug, Clone, Default)]
+struct QueryResults {
+    domains: Vec<ExpiredDomain>,
+    seen: HashSet<String>,
+}
+
+impl QueryResults {
+    fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    /// Append new domains until the query has `limit`, if set; returns how
+    /// many were added.
+    fn add(
+        &mut self,
+        candidates: impl IntoIterator<Item = ExpiredDomain>,
+        limit: Option<usize>,
+    ) -> usize {
+        let before = self.len();
+        for candidate in candidates {
+            if limit.is_some_and(|limit| self.len() >= limit) {
+                break;
+            }
+            if self.seen.insert(candidate.domain.to_lowercase()) {
+                self.domains.push(candidate);
+            }
+        }
+        self.len() - before
+    }
+}
+
+/// Merge per-query results in order, keeping the first record of each
+/// domain and every keyword that found it.
+fn merge(queries: &[ExpiredQuery], found: Vec<Vec<ExpiredDomain>>) -> Vec<ExpiredDomain> {
+    let mut merged: Vec<ExpiredDomain> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    for (query, results) in queries.iter().zip(found) {
+        for mut domain in results {
+            let key = domain.domain.to_lowercase();
+            let position = match index.get(&key) {
+                Some(&position) => position,
+                None => {
+                    domain.matched_keywords.clear();
+                    index.insert(key, merged.len());
+                    merged.push(domain);
+                    merged.len() - 1
                 }
+            };
+            let matched = &mut merged[position].matched_keywords;
+            if !query.keyword.is_empty() && !matched.contains(&query.keyword) {
+                matched.push(query.keyword.clone());
             }
         }
     }
 
-    Ok(results)
+    merged
 }
 
-async fn search_dynadot(client: &Client, keyword: &str, tld: &str) -> Result<Vec<ExpiredDomain>> {
-    let response = client
-        .get("https://www.dynadot.com/market/backorder/backorders.csv")
-        .header("User-Agent", "Mozilla/5.0 (compatible; Domain-MCP/1.0)")
-        .send()
-        .await?;
+/// DomainsDB search API, queried once per keyword/TLD combination.
+pub struct DomainsDbSource {
+    client: Client,
+    max_results: Option<usize>,
+}
 
-    if !response.status().is_success() {
+impl DomainsDbSource {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            max_results: None,
+        }
+    }
+
+    /// Stop paging through a query once this many matches are found.
+    pub fn with_max_results(mut self, max_results: Option<usize>) -> Self {
+        self.max_results = max_results;
+        self
+    }
+}
+
+#[async_trait]
+impl ExpiredDomainSource for DomainsDbSource {
+    fn name(&self) -> &str {
+        "domainsdb"
+    }
+
+    async fn search(&self, queries: &[ExpiredQuery]) -> Result<Vec<Vec<ExpiredDomain>>> {
+        try_join_all(
+            queries
+                .iter()
+                .map(|query| search_domainsdb(&self.client, query, self.max_results)),
+        )
+        .await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DropList {
+    Dynadot,
+    NameJet,
+    SnapNames,
+}
+
+impl DropList {
+    const ALL: [DropList; 3] = [DropList::Dynadot, DropList::NameJet, DropList::SnapNames];
+
+    fn name(self) -> &'static str {
+        match self {
+            DropList::Dynadot => "dynadot",
+            DropList::NameJet => "namejet",
+            DropList::SnapNames => "snapnames",
+        }
+    }
+
+    /// Where the list is published, tried in order.
+    fn urls(self) -> &'static [&'static str] {
+        match self {
+            DropList::Dynadot => &["https://www.dynadot.com/market/backorder/backorders.csv"],
+            DropList::NameJet => &[
+                "https://www.namejet.com/download/namejet_inventory.txt",
+                "https://www.namejet.com/download/namejet-inventory.csv",
+            ],
+            DropList::SnapNames => &["https://www.snapnames.com/file_dl.sn?file=deletinglist.csv"],
+        }
+    }
+
+    fn parser(self) -> ListParser {
+        match self {
+            DropList::Dynadot => parse_dynadot,
+            DropList::NameJet => parse_namejet,
+            DropList::SnapNames => parse_snapnames,
+        }
+    }
+
+    async fn index(self, client: &Client) -> Result<Arc<DropListIndex>> {
+        DropCache::global()
+            .list(client, self.name(), self.urls(), self.parser())
+            .await
+    }
+}
+
+/// A drop list kept in the local cache and searched through its index.
+pub struct DropListSource {
+    list: DropList,
+    client: Client,
+}
+
+impl DropListSource {
+    pub fn dynadot(client: Client) -> Self {
+        Self {
+            list: DropList::Dynadot,
+            client,
+        }
+    }
+
+    pub fn namejet(client: Client) -> Self {
+        Self {
+            list: DropList::NameJet,
+            client,
+        }
+    }
+
+    pub fn snapnames(client: Client) -> Self {
+        Self {
+            list: DropList::SnapNames,
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl ExpiredDomainSource for DropListSource {
+    fn name(&self) -> &str {
+        self.list.name()
+    }
+
+    async fn search(&self, queries: &[ExpiredQuery]) -> Result<Vec<Vec<ExpiredDomain>>> {
+        let index = self.list.index(&self.client).await?;
+        Ok(queries.iter().map(|query| index.search(query)).collect())
+    }
+}
+
+/// A drop list the user imported with `import_drop_list`.
+pub struct ImportedListSource {
+    name: String,
+}
+
+impl ImportedListSource {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ExpiredDomainSource for ImportedListSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn search(&self, queries: &[ExpiredQuery]) -> Result<Vec<Vec<ExpiredDomain>>> {
+        let index = DropCache::global()
+            .imported(&self.name, parse_imported)
+            .await?;
+        Ok(queries
+            .iter()
+            .map(|query| {
+                let mut matches = index.search(query);
+                for domain in &mut matches {
+                    domain.source = self.name.clone();
+                }
+                matches
+            })
+            .collect())
+    }
+}
+
+/// Store the CSV at `path` as the drop list `name`, searched alongside the
+/// built-in sources from then on.
+pub async fn import_drop_list(name: &str, path: &str) -> Result<ImportedDropList> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
         return Err(anyhow::anyhow!(
-            "Dynadot CSV returned status: {}",
-            response.status()
+            "List names may only contain letters, digits, '-' and '_'"
         ));
     }
+    if name == "domainsdb" || DropList::ALL.iter().any(|list| list.name() == name) {
+        return Err(anyhow::anyhow!("'{}' is a built-in source", name));
+    }
+    DropCache::global()
+        .import(&name, Path::new(path), parse_imported)
+        .await
+}
 
-    let text = response.text().await?;
-    let mut results = Vec::new();
+/// Which enabled drop lists, built-in and imported, carry each domain.
+#[derive(Debug, Default)]
+pub struct DropListPresence {
+    /// Lists that were loaded and searched.
+    pub checked: Vec<String>,
+    /// Lists that could not be loaded, with the error.
+    pub unavailable: Vec<(String, String)>,
+    /// The checked lists each domain is on; domains on none are absent.
+    pub listed: HashMap<String, Vec<String>>,
+}
 
-    // Skip header line
-    for line in text.lines().skip(1) {
-        if line.trim().is_empty() {
+pub async fn drop_list_presence(domains: &[String]) -> Result<DropListPresence> {
+    let config = ExpiredSourcesConfig::from_env();
+    let client = Client::builder()
+        .user_agent("Domain-MCP-Rust/1.0")
+        .timeout(config.source_timeout)
+        .build()?;
+    let cache = DropCache::global();
+
+    let mut presence = DropListPresence::default();
+    let names = DropList::ALL
+        .iter()
+        .map(|list| list.name().to_string())
+        .chain(cache.imported_names());
+    for name in names {
+        if !config.is_enabled(&name) {
             continue;
         }
+        let load = async {
+            match DropList::ALL.into_iter().find(|list| list.name() == name) {
+                Some(list) => list.index(&client).await,
+                None => cache.imported(&name, parse_imported).await,
+            }
+        };
+        let index = match tokio::time::timeout(config.source_timeout, load).await {
+            Ok(Ok(index)) => index,
+            Ok(Err(e)) => {
+                presence.unavailable.push((name, format!("{:#}", e)));
+                continue;
+            }
+            Err(_) => {
+                presence.unavailable.push((
+                    name,
+                    format!("Timed out after {}s", config.source_timeout.as_secs_f64()),
+                ));
+                continue;
+            }
+        };
 
-        let parts: Vec<&str> = line.split(',').map(|p| p.trim()).collect();
-        if parts.len() >= 2 {
-            let domain_name = parts[0].trim();
-            if domain_name.contains('.') {
-                // Filter by keyword and TLD
-                let matches_keyword = keyword.is_empty()
-                    || domain_name.to_lowercase().contains(&keyword.to_lowercase());
-                let matches_tld = tld.is_empty() || domain_name.ends_with(&format!(".{}", tld));
-
-                if matches_keyword && matches_tld {
-                    results.push(ExpiredDomain {
-                        domain: domain_name.to_string(),
-                        status: "pending delete".to_string(),
-                        source: "Dynadot".to_string(),
-                        created: None,
-                        updated: None,
-                        end_time: if parts.len() > 1 {
-                            Some(parts[1].to_string())
-                        } else {
-                            None
-                        },
-                        appraisal: if parts.len() > 3 {
-                            Some(parts[3].to_string())
-                        } else {
-                            None
-                        },
-                        starting_price: if parts.len() > 4 {
-                            Some(parts[4].to_string())
-                        } else {
-                            None
-                        },
-                        has_dns: None,
-                    });
-
-                    if results.len() >= 10 {
-                        break;
-                    }
-                }
+        for domain in domains {
+            if index.get(domain).is_some() {
+                presence
+                    .listed
+                    .entry(domain.clone())
+                    .or_default()
+                    .push(name.clone());
             }
         }
+        presence.checked.push(name);
     }
-
-    Ok(results)
+    Ok(presence)
 }
 
-async fn search_namejet(client: &Client, keyword: &str, tld: &str) -> Result<Vec<ExpiredDomain>> {
-    let urls = vec![
-        "https://www.namejet.com/download/namejet_inventory.txt",
-        "https://www.namejet.com/download/namejet-inventory.csv",
-    ];
-
-    let mut results = Vec::new();
+/// Refresh the enabled drop lists every refresh interval, so searches find
+/// them current. Returns `None` unless prefetching is configured.
+pub fn spawn_drop_list_refresh() -> Option<tokio::task::JoinHandle<()>> {
+    let cache = DropCache::global();
+    if !cache.config().prefetch {
+        return None;
+    }
+    let interval = cache.config().refresh_interval.max(Duration::from_secs(60));
+    let sources = ExpiredSourcesConfig::from_env();
 
-    for url in urls {
-        match client
-            .get(url)
-            .header("User-Agent", "Mozilla/5.0 (compatible; Domain-MCP/1.0)")
-            .send()
-            .await
+    Some(tokio::spawn(async move {
+        let client = match Client::builder()
+            .user_agent("Domain-MCP-Rust/1.0")
+            .timeout(sources.source_timeout)
+            .build()
         {
-            Ok(response) if response.status().is_success() => {
-                let text = response.text().await?;
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("Drop list refresh disabled: {:#}", e);
+                return;
+            }
+        };
+        loop {
+            for list in DropList::ALL {
+                if !sources.is_enabled(list.name()) {
+                    continue;
+                }
+                match list.index(&client).await {
+                    Ok(index) => tracing::info!("{} list: {} domains", list.name(), index.len()),
+                    Err(e) => tracing::warn!("Failed to refresh the {} list: {:#}", list.name(), e),
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }))
+}
 
-                for line in text.lines() {
-                    if line.trim().is_empty() || line.starts_with('#') {
-                        continue;
-                    }
+/// Page through DomainsDB until it runs out of matches, or until
+/// `max_results` matches have been found.
+async fn search_domainsdb(
+    client: &Client,
+    query: &ExpiredQuery,
+    max_results: Option<usize>,
+) -> Result<Vec<ExpiredDomain>> {
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
 
-                    // Extract domain name (could be first column in CSV or whole line in TXT)
-                    let domain_name = line
-                        .split(',')
-                        .next()
-                        .unwrap_or(line)
-                        .trim()
-                        .trim_matches('"');
-
-                    if domain_name.contains('.') && !domain_name.starts_with('#') {
-                        // Filter by keyword and TLD
-                        let matches_keyword = keyword.is_empty()
-                            || domain_name.to_lowercase().contains(&keyword.to_lowercase());
-                        let matches_tld =
-                            tld.is_empty() || domain_name.ends_with(&format!(".{}", tld));
-
-                        if matches_keyword && matches_tld {
-                            results.push(ExpiredDomain {
-                                domain: domain_name.to_string(),
-                                status: "auction/pending".to_string(),
-                                source: "NameJet".to_string(),
-                                created: None,
-                                updated: None,
-                                end_time: None,
-                                appraisal: None,
-                                starting_price: None,
-                                has_dns: None,
-                            });
-
-                            if results.len() >= 10 {
-                                return Ok(results);
-                            }
-                        }
-                    }
-                }
+    for page in 1.. {
+        let domains = match domainsdb_page(client, query, page).await {
+            Ok(domains) => domains,
+            Err(e) if page > 1 => {
+                tracing::warn!(
+                    "DomainsDB page {} failed, keeping earlier pages: {}",
+                    page,
+                    e
+                );
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        let received = domains.len();
+        let mut new_domains = 0;
 
-                if !results.is_empty() {
-                    break; // Stop trying other NameJet URLs if one worked
-                }
+        for domain_info in domains {
+            let Some(domain_name) = domain_info.domain.clone() else {
+                continue;
+            };
+            if !seen.insert(domain_name.clone()) {
+                continue;
+            }
+            new_domains += 1;
+            // Additional filtering for keyword and TLD
+            if domain_name.contains('.') && query.matches(&domain_name) {
+                results.push(domainsdb_domain(domain_name, domain_info));
             }
-            _ => continue,
+        }
+
+        // A page repeating earlier domains means the API ignored the page.
+        let full = max_results.is_some_and(|max| results.len() >= max);
+        if received < DOMAINSDB_PAGE_SIZE || new_domains == 0 || full {
+            break;
         }
     }
 
     Ok(results)
 }
 
-async fn search_snapnames(client: &Client, keyword: &str, tld: &str) -> Result<Vec<ExpiredDomain>> {
-    let response = client
-        .get("https://www.snapnames.com/file_dl.sn?file=deletinglist.csv")
-        .header("User-Agent", "Mozilla/5.0 (compatible; Domain-MCP/1.0)")
-        .send()
-        .await?;
+async fn domainsdb_page(
+    client: &Client,
+    query: &ExpiredQuery,
+    page: usize,
+) -> Result<Vec<DomainsDBDomain>> {
+    let page = page.to_string();
+    let page_size = DOMAINSDB_PAGE_SIZE.to_string();
+    let mut params = vec![
+        ("isDead", "true"),
+        ("limit", page_size.as_str()),
+        ("page", page.as_str()),
+    ];
+
+    if !query.keyword.is_empty() {
+        params.push(("domain", &query.keyword));
+    }
+
+    if !query.tld.is_empty() {
+        params.push(("zone", &query.tld));
+    }
+
+    let request = client
+        .get("https://api.domainsdb.info/v1/domains/search")
+        .query(&params)
+        .build()?;
+    let response = Scheduler::global().execute(client, request).await?;
 
     if !response.status().is_success() {
         return Err(anyhow::anyhow!(
-            "SnapNames CSV returned status: {}",
+            "DomainsDB API returned status: {}",
             response.status()
         ));
     }
 
-    let text = response.text().await?;
-    let mut results = Vec::new();
+    let data: DomainsDBResponse = response.json().await?;
+    Ok(data.domains.unwrap_or_default())
+}
 
-    // Skip header line
-    for line in text.lines().skip(1) {
-        if line.trim().is_empty() {
-            continue;
+fn domainsdb_domain(domain: String, info: DomainsDBDomain) -> ExpiredDomain {
+    let has_website = info
+        .a_records
+        .as_ref()
+        .is_some_and(|records| !records.is_empty());
+    let has_dns = has_website || info.ns_records.as_ref().is_some_and(|r| !r.is_empty());
+
+    ExpiredDomain {
+        domain,
+        status: if info.is_dead == Some("True".to_string()) {
+            "expired".to_string()
+        } else {
+            "unknown".to_string()
+        },
+        source: "DomainsDB".to_string(),
+        created: info.create_date,
+        updated: info.update_date,
+        end_time: None,
+        appraisal: None,
+        starting_price: None,
+        bids: None,
+        has_dns: Some(has_dns),
+        has_website: Some(has_website),
+        score: None,
+        matched_keywords: Vec::new(),
+    }
+}
+
+const DYNADOT: ListFormat = ListFormat {
+    source: "Dynadot",
+    status: "pending delete",
+    required: &[Column::EndTime, Column::Appraisal, Column::Price],
+    headerless: false,
+    show_header: true,
+};
+
+/// NameJet publishes either a plain list of domains or a CSV.
+const NAMEJET: ListFormat = ListFormat {
+    source: "NameJet",
+    status: "auction/pending",
+    required: &[],
+    headerless: true,
+    show_header: true,
+};
+
+const SNAPNAMES: ListFormat = ListFormat {
+    source: "SnapNames",
+    status: "pending delete",
+    required: &[],
+    headerless: false,
+    show_header: true,
+};
+
+/// A user's list: one domain per line, or a CSV with a header row.
+const IMPORTED: ListFormat = ListFormat {
+    source: "Imported",
+    status: "listed",
+    required: &[],
+    headerless: true,
+    show_header: false,
+};
+
+fn parse_dynadot(text: &str) -> Result<Vec<ExpiredDomain>> {
+    drop_csv::parse(text, &DYNADOT)
+}
+
+fn parse_namejet(text: &str) -> Result<Vec<ExpiredDomain>> {
+    drop_csv::parse(text, &NAMEJET)
+}
+
+fn parse_snapnames(text: &str) -> Result<Vec<ExpiredDomain>> {
+    drop_csv::parse(text, &SNAPNAMES)
+}
+
+fn parse_imported(text: &str) -> Result<Vec<ExpiredDomain>> {
+    drop_csv::parse(text, &IMPORTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed(domain: &str, source: &str) -> ExpiredDomain {
+        ExpiredDomain {
+            domain: domain.to_string(),
+            status: "pending delete".to_string(),
+            source: source.to_string(),
+            created: None,
+            updated: None,
+            end_time: None,
+            appraisal: None,
+            starting_price: None,
+            bids: None,
+            has_dns: None,
+            has_website: None,
+            score: None,
+            matched_keywords: Vec::new(),
         }
+    }
 
-        let parts: Vec<&str> = line
-            .split(',')
-            .map(|p| p.trim().trim_matches('"'))
-            .collect();
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn query(keyword: &str, tld: &str) -> ExpiredQuery {
+        ExpiredQuery {
+            keyword: keyword.to_string(),
+            tld: tld.to_string(),
+        }
+    }
+
+    enum Behavior {
+        List(Vec<&'static str>),
+        Fail,
+        Hang,
+    }
 
-        if !parts.is_empty() && parts[0].contains('.') {
-            let domain_name = parts[0];
-
-            // Filter by keyword and TLD
-            let matches_keyword =
-                keyword.is_empty() || domain_name.to_lowercase().contains(&keyword.to_lowercase());
-            let matches_tld = tld.is_empty() || domain_name.ends_with(&format!(".{}", tld));
-
-            if matches_keyword && matches_tld {
-                results.push(ExpiredDomain {
-                    domain: domain_name.to_string(),
-                    status: "pending delete".to_string(),
-                    source: "SnapNames".to_string(),
-                    created: None,
-                    updated: None,
-                    end_time: None,
-                    appraisal: None,
-                    starting_price: None,
-                    has_dns: None,
-                });
-
-                if results.len() >= 10 {
-                    break;
+    struct StubSource {
+        name: &'static str,
+        behavior: Behavior,
+    }
+
+    #[async_trait]
+    impl ExpiredDomainSource for StubSource {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn search(&self, queries: &[ExpiredQuery]) -> Result<Vec<Vec<ExpiredDomain>>> {
+            let listing = match &self.behavior {
+                Behavior::List(listing) => listing,
+                Behavior::Fail => return Err(anyhow::anyhow!("HTTP 503")),
+                Behavior::Hang => {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    unreachable!()
                 }
-            }
+            };
+            Ok(queries
+                .iter()
+                .map(|query| {
+                    listing
+                        .iter()
+                        .filter(|domain| query.matches(domain))
+                        .map(|domain| listed(domain, self.name))
+                        .collect()
+                })
+                .collect())
         }
     }
 
-    Ok(results)
+    fn stub(name: &'static str, behavior: Behavior) -> Box<dyn ExpiredDomainSource> {
+        Box::new(StubSource { name, behavior })
+    }
+
+    #[test]
+    fn queries_test() {
+        assert_eq!(
+            queries(
+                &strings(&["Shop", "cloud", "shop"]),
+                &strings(&[".com", "net"])
+            ),
+            vec![
+                query("shop", "com"),
+                query("shop", "net"),
+                query("cloud", "com"),
+                query("cloud", "net"),
+            ]
+        );
+        assert_eq!(queries(&[], &[]), vec![query("", "")]);
+    }
+
+    #[test]
+    fn matches_test() {
+        assert!(query("shop", "com").matches("CloudShop.com"));
+        assert!(query("", "").matches("cloudshop.com"));
+        assert!(!query("shop", "net").matches("cloudshop.com"));
+        assert!(!query("shop", "com").matches("shop.comx"));
+    }
+
+    #[test]
+    fn query_results_cap_and_dedupe() {
+        let mut results = QueryResults::default();
+        results.add([listed("A.com", "DomainsDB")], None);
+        let added = results.add(
+            (0..20).map(|i| listed(&format!("{}.com", (b'a' + i) as char), "Dynadot")),
+            Some(10),
+        );
+        assert_eq!(added, 9);
+        assert_eq!(results.len(), 10);
+        assert_eq!(results.domains[0].source, "DomainsDB");
+        assert_eq!(results.domains[1].domain, "b.com");
+
+        let added = results.add(
+            (0..20).map(|i| listed(&format!("{}.net", (b'a' + i) as char), "Dynadot")),
+            None,
+        );
+        assert_eq!(added, 20);
+        assert_eq!(results.len(), 30);
+    }
+
+    #[test]
+    fn merge_records_every_matching_keyword() {
+        let queries = queries(&strings(&["shop", "cloud"]), &[]);
+        let merged = merge(
+            &queries,
+            vec![
+                vec![
+                    listed("cloudshop.com", "DomainsDB"),
+                    listed("shopfast.net", "Dynadot"),
+                ],
+                vec![
+                    listed("CloudShop.com", "SnapNames"),
+                    listed("mycloud.io", "NameJet"),
+                ],
+            ],
+        );
+
+        let summary: Vec<(&str, &str, Vec<String>)> = merged
+            .iter()
+            .map(|d| {
+                (
+                    d.domain.as_str(),
+                    d.source.as_str(),
+                    d.matched_keywords.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("cloudshop.com", "DomainsDB", strings(&["shop", "cloud"])),
+                ("shopfast.net", "Dynadot", strings(&["shop"])),
+                ("mycloud.io", "NameJet", strings(&["cloud"])),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_date_and_money_test() {
+        let date = |value| parse_date(value).map(|date| date.to_rfc3339());
+        assert_eq!(
+            date("2024-03-01T12:00:00Z").as_deref(),
+            Some("2024-03-01T12:00:00+00:00")
+        );
+        assert_eq!(
+            date("2024/03/01 12:00 PST").as_deref(),
+            Some("2024-03-01T20:00:00+00:00")
+        );
+        assert_eq!(
+            date("2024-07-01 23:30:00 EDT").as_deref(),
+            Some("2024-07-02T03:30:00+00:00")
+        );
+        assert_eq!(
+            date("2024-03-01 12:00:00 GMT").as_deref(),
+            Some("2024-03-01T12:00:00+00:00")
+        );
+        assert_eq!(
+            date("2020-05-14T11:53:59.361898").as_deref(),
+            Some("2020-05-14T11:53:59.361898+00:00")
+        );
+        assert_eq!(
+            date("03/01/2024").as_deref(),
+            Some("2024-03-01T00:00:00+00:00")
+        );
+        assert_eq!(date("soon"), None);
+
+        assert_eq!(parse_money("$1,250.50"), Some(1250.5));
+        assert_eq!(parse_money("75 USD"), Some(75.0));
+        assert_eq!(parse_money("n/a"), None);
+    }
+
+    #[test]
+    fn sorts_with_missing_values_last() {
+        let priced =
+            |domain: &str, appraisal: Option<&str>, end_time: Option<&str>| ExpiredDomain {
+                appraisal: appraisal.map(str::to_string),
+                end_time: end_time.map(str::to_string),
+                ..listed(domain, "Dynadot")
+            };
+        let domains = vec![
+            priced("unpriced.com", None, None),
+            priced("cheap.com", Some("$50"), Some("2024-03-02 10:00:00")),
+            priced("premium.com", Some("$1,200"), Some("2024-03-01 10:00:00")),
+            priced("mid.io", Some("$300"), None),
+        ];
+        let order = |key, order| {
+            let mut domains = domains.clone();
+            sort(&mut domains, key, order);
+            domains
+                .into_iter()
+                .map(|domain| domain.domain)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            order(SortKey::Appraisal, SortKey::Appraisal.default_order()),
+            vec!["premium.com", "mid.io", "cheap.com", "unpriced.com"]
+        );
+        assert_eq!(
+            order(SortKey::Appraisal, SortOrder::Asc),
+            vec!["cheap.com", "mid.io", "premium.com", "unpriced.com"]
+        );
+        assert_eq!(
+            order(SortKey::EndTime, SortKey::EndTime.default_order()),
+            vec!["premium.com", "cheap.com", "unpriced.com", "mid.io"]
+        );
+        assert_eq!(
+            order(SortKey::Length, SortOrder::Desc),
+            vec!["unpriced.com", "premium.com", "cheap.com", "mid.io"]
+        );
+    }
+
+    #[test]
+    fn search_options_test() {
+        let options =
+            ExpiredSearchOptions::new(ExpiredFilters::default(), Some("Age"), None, 0, None)
+                .unwrap();
+        assert_eq!(options.sort_by, Some(SortKey::Age));
+        assert_eq!(options.limit, DEFAULT_PAGE_SIZE);
+        assert!(
+            ExpiredSearchOptions::new(ExpiredFilters::default(), Some("price"), None, 0, None)
+                .is_err()
+        );
+        assert!(
+            ExpiredSearchOptions::new(ExpiredFilters::default(), None, Some("up"), 0, None)
+                .is_err()
+        );
+        assert!(
+            ExpiredSearchOptions::new(ExpiredFilters::default(), None, None, 0, Some(0)).is_err()
+        );
+        let backwards = ExpiredFilters {
+            min_length: Some(8),
+            max_length: Some(4),
+            ..ExpiredFilters::default()
+        };
+        assert!(ExpiredSearchOptions::new(backwards, None, None, 0, None).is_err());
+    }
+
+    #[test]
+    fn parse_imported_test() {
+        let names = |text| {
+            parse_imported(text)
+                .unwrap()
+                .into_iter()
+                .map(|domain| domain.domain)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names("Price,Domain Name\n$10,CloudShop.com\n$20,\"mycloud.io\"\n"),
+            vec!["cloudshop.com", "mycloud.io"]
+        );
+        assert_eq!(
+            names("cloudshop.com\n\nmycloud.io"),
+            vec!["cloudshop.com", "mycloud.io"]
+        );
+        assert!(names("domain\nnot a domain").is_empty());
+        assert!(parse_imported("Price,Name Server\n$10,ns1.example.com\n").is_err());
+        // Whatever file was imported, its first line is not repeated.
+        let error = parse_imported("root:$6$secret:19000:0:99999:7:::\n")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Imported list format not recognised: No domain column"
+        );
+    }
+
+    #[test]
+    fn parses_drop_list_fixtures() {
+        let dynadot = parse_dynadot(include_str!(
+            "../../tests/fixtures/drop-lists/dynadot-reordered.csv"
+        ))
+        .unwrap();
+        assert_eq!(dynadot.len(), 3);
+        assert_eq!(dynadot[1].appraisal.as_deref(), Some("$1,200"));
+        assert_eq!(dynadot[1].starting_price.as_deref(), Some("$1,050.00"));
+        // Without an appraisal column the format has changed.
+        assert!(parse_dynadot(include_str!(
+            "../../tests/fixtures/drop-lists/snapnames.csv"
+        ))
+        .is_err());
+
+        let namejet =
+            parse_namejet(include_str!("../../tests/fixtures/drop-lists/namejet.txt")).unwrap();
+        assert_eq!(namejet.len(), 3);
+        assert_eq!(namejet[0].source, "NameJet");
+
+        let snapnames = parse_snapnames(include_str!(
+            "../../tests/fixtures/drop-lists/snapnames.csv"
+        ))
+        .unwrap();
+        assert_eq!(snapnames[0].bids, Some(2));
+        assert_eq!(snapnames[1].bids, None);
+    }
+
+    #[tokio::test]
+    async fn import_rejects_bad_names() {
+        for name in ["", "../etc", "my list", "dynadot", "DomainsDB"] {
+            assert!(
+                import_drop_list(name, "/nonexistent.csv").await.is_err(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn filters_test() {
+        let now = DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let passes = |filters: ExpiredFilters, domain: &ExpiredDomain| filters.matches(domain, now);
+        let plain = listed("cloudshop.com", "Dynadot");
+        let hyphenated = listed("cloud-shop4u.net", "Dynadot");
+
+        let length = ExpiredFilters {
+            min_length: Some(5),
+            max_length: Some(9),
+            ..ExpiredFilters::default()
+        };
+        assert!(passes(length.clone(), &plain));
+        assert!(!passes(length, &hyphenated));
+
+        let clean = ExpiredFilters {
+            no_hyphens: true,
+            ..ExpiredFilters::default()
+        };
+        assert!(!passes(clean, &hyphenated));
+        let no_digits = ExpiredFilters {
+            no_digits: true,
+            ..ExpiredFilters::default()
+        };
+        assert!(passes(no_digits.clone(), &plain));
+        assert!(!passes(no_digits, &hyphenated));
+
+        let dictionary = ExpiredFilters {
+            dictionary_words_only: true,
+            ..ExpiredFilters::default()
+        };
+        assert!(passes(dictionary.clone(), &plain));
+        assert!(!passes(dictionary, &hyphenated));
+
+        let pattern = ExpiredFilters {
+            pattern: Some(Regex::new("^cloud").unwrap()),
+            ..ExpiredFilters::default()
+        };
+        assert!(passes(pattern.clone(), &plain));
+        assert!(!passes(pattern, &listed("mycloud.io", "NameJet")));
+
+        // Domains without the value are excluded.
+        let aged = ExpiredDomain {
+            created: Some("2014-05-01".to_string()),
+            has_dns: Some(true),
+            ..listed("old.com", "DomainsDB")
+        };
+        let old = ExpiredFilters {
+            min_age_days: Some(3650),
+            ..ExpiredFilters::default()
+        };
+        assert!(passes(old.clone(), &aged));
+        assert!(!passes(old, &plain));
+
+        let priced = ExpiredDomain {
+            starting_price: Some("$69".to_string()),
+            ..listed("cheap.com", "Dynadot")
+        };
+        let budget = ExpiredFilters {
+            max_price: Some(100.0),
+            ..ExpiredFilters::default()
+        };
+        assert!(passes(budget.clone(), &priced));
+        assert!(!passes(budget, &plain));
+
+        let live = ExpiredFilters {
+            has_dns: Some(true),
+            ..ExpiredFilters::default()
+        };
+        assert!(passes(live.clone(), &aged));
+        assert!(!passes(live, &plain));
+    }
+
+    #[tokio::test]
+    async fn ranks_every_source_by_score() {
+        let registry = SourceRegistry::new(ExpiredSourcesConfig::default())
+            .with_source(stub("first", Behavior::List(vec!["x-shop9q.biz"])))
+            .with_source(stub(
+                "second",
+                Behavior::List(vec!["shop.com", "shopcloud.net"]),
+            ));
+        let options = ExpiredSearchOptions {
+            sort_by: Some(SortKey::Score),
+            ..ExpiredSearchOptions::default()
+        };
+
+        let result = registry
+            .search(&strings(&["shop"]), &[], &options)
+            .await
+            .unwrap();
+        let ranked: Vec<&str> = result.domains.iter().map(|d| d.domain.as_str()).collect();
+        assert_eq!(ranked, vec!["shop.com", "shopcloud.net", "x-shop9q.biz"]);
+        assert!(result.domains.iter().all(|d| d.score.is_some()));
+    }
+
+    #[tokio::test]
+    async fn resolves_dns_once_per_domain_within_the_limit_test() {
+        let now = Utc::now();
+        let known = ExpiredDomain {
+            has_dns: Some(false),
+            ..listed("cloudshop.com", "DomainsDB")
+        };
+        // Filtered out by length, so never looked up.
+        let long = listed("cloud-shop-online.com", "Dynadot");
+        let matches = vec![
+            vec![
+                known,
+                long,
+                listed("cached.com", "Dynadot"),
+                listed("cloudy.io", "Dynadot"),
+            ],
+            vec![
+                listed("Cloudy.io", "NameJet"),
+                listed("shop.net", "NameJet"),
+            ],
+        ];
+        let filters = ExpiredFilters {
+            max_length: Some(9),
+            has_dns: Some(true),
+            ..ExpiredFilters::default()
+        };
+
+        // No lookups allowed: the two distinct unknown names are counted
+        // once each, and a name already looked up is filled in for free.
+        let mut dns = DnsLookups::new(0);
+        dns.found
+            .insert("cached.com".to_string(), Some((true, false)));
+        let mut resolved = matches.clone();
+        assert_eq!(dns.resolve(&mut resolved, &filters, now).await, 2);
+        let has_dns: Vec<(&str, Option<bool>)> = resolved
+            .iter()
+            .flatten()
+            .map(|domain| (domain.domain.as_str(), domain.has_dns))
+            .collect();
+        assert_eq!(
+            has_dns,
+            vec![
+                ("cloudshop.com", Some(false)),
+                ("cloud-shop-online.com", None),
+                ("cached.com", Some(true)),
+                ("cloudy.io", None),
+                ("Cloudy.io", None),
+                ("shop.net", None),
+            ]
+        );
+        assert_eq!(resolved[0][2].has_website, Some(false));
+
+        let mut unfiltered = matches;
+        let mut dns = DnsLookups::new(0);
+        let unresolved = dns
+            .resolve(&mut unfiltered, &ExpiredFilters::default(), now)
+            .await;
+        assert_eq!(unresolved, 0);
+    }
+
+    #[tokio::test]
+    async fn filters_before_capping() {
+        let config = ExpiredSourcesConfig {
+            max_results_per_query: Some(2),
+            ..ExpiredSourcesConfig::default()
+        };
+        let registry = SourceRegistry::new(config).with_source(stub(
+            "list",
+            Behavior::List(vec!["shop-1.com", "shop-2.com", "shop.com", "shopnow.com"]),
+        ));
+        let options = ExpiredSearchOptions {
+            filters: ExpiredFilters {
+                no_hyphens: true,
+                ..ExpiredFilters::default()
+            },
+            ..ExpiredSearchOptions::default()
+        };
+
+        let result = registry
+            .search(&strings(&["shop"]), &[], &options)
+            .await
+            .unwrap();
+        let domains: Vec<&str> = result.domains.iter().map(|d| d.domain.as_str()).collect();
+        assert_eq!(domains, vec!["shop.com", "shopnow.com"]);
+    }
+
+    #[tokio::test]
+    async fn pages_through_every_result() {
+        let names: Vec<&'static str> = (0..250)
+            .map(|i| &*format!("shop{}.com", i).leak())
+            .collect();
+        let registry = SourceRegistry::new(ExpiredSourcesConfig::default())
+            .with_source(stub("list", Behavior::List(names)));
+        let options = ExpiredSearchOptions {
+            offset: 200,
+            ..ExpiredSearchOptions::default()
+        };
+
+        let result = registry
+            .search(&strings(&["shop"]), &[], &options)
+            .await
+            .unwrap();
+        assert_eq!(result.domains.len(), 50);
+        assert_eq!(result.domains[0].domain, "shop200.com");
+        assert_eq!(result.sources[0].found, 250);
+        assert_eq!(
+            result.page,
+            BulkPage {
+                offset: 200,
+                limit: DEFAULT_PAGE_SIZE,
+                total: 250,
+                next_offset: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_each_source_status() {
+        let config = ExpiredSourcesConfig {
+            disabled: strings(&["off"]),
+            source_timeout: Duration::from_millis(50),
+            ..ExpiredSourcesConfig::default()
+        };
+        let registry = SourceRegistry::new(config)
+            .with_source(stub("down", Behavior::Fail))
+            .with_source(stub("slow", Behavior::Hang))
+            .with_source(stub("off", Behavior::List(vec!["shopoff.com"])))
+            .with_source(stub("list", Behavior::List(vec!["shop1.com", "shop2.net"])));
+        assert_eq!(registry.names(), vec!["down", "slow", "off", "list"]);
+
+        let result = registry
+            .search(
+                &strings(&["shop"]),
+                &strings(&["com"]),
+                &ExpiredSearchOptions::default(),
+            )
+            .await
+            .unwrap();
+        let statuses: Vec<(&str, SourceStatus, usize)> = result
+            .sources
+            .iter()
+            .map(|s| (s.name.as_str(), s.status, s.found))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("down", SourceStatus::Failed, 0),
+                ("slow", SourceStatus::Failed, 0),
+                ("off", SourceStatus::Skipped, 0),
+                ("list", SourceStatus::Succeeded, 1),
+            ]
+        );
+        assert_eq!(result.sources[0].reason.as_deref(), Some("HTTP 503"));
+        assert!(result.sources[1]
+            .reason
+            .as_ref()
+            .unwrap()
+            .starts_with("Timed out"));
+        assert_eq!(result.domains.len(), 1);
+        assert_eq!(result.domains[0].domain, "shop1.com");
+    }
+
+    #[tokio::test]
+    async fn later_sources_skipped_once_full() {
+        let config = ExpiredSourcesConfig {
+            enabled: Some(strings(&["first", "second"])),
+            max_results_per_query: Some(2),
+            ..ExpiredSourcesConfig::default()
+        };
+        let registry = SourceRegistry::new(config)
+            .with_source(stub(
+                "first",
+                Behavior::List(vec!["a.com", "b.com", "c.com"]),
+            ))
+            .with_source(stub("second", Behavior::Fail))
+            .with_source(stub("third", Behavior::Fail));
+
+        let result = registry
+            .search(&[], &[], &ExpiredSearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(result.domains.len(), 2);
+        assert_eq!(result.sources[1].status, SourceStatus::Skipped);
+        assert!(result.sources[1]
+            .reason
+            .as_ref()
+            .unwrap()
+            .contains("already has 2"));
+        // Not in the enabled list.
+        assert_eq!(
+            result.sources[2].reason.as_deref(),
+            Some("Disabled in config")
+        );
+    }
 }
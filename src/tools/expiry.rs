@@ -0,0 +1,372 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use super::cert_chain::{self, Endpoint};
+use super::scheduler::Scheduler;
+use super::ssl::{self, SslCertificateInfo};
+use super::starttls::Protocol;
+
+pub const DEFAULT_WARN_DAYS: i64 = 30;
+pub const DEFAULT_CRITICAL_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiryThresholds {
+    pub warn_days: i64,
+    pub critical_days: i64,
+}
+
+impl Default for ExpiryThresholds {
+    fn default() -> Self {
+        ExpiryThresholds {
+            warn_days: DEFAULT_WARN_DAYS,
+            critical_days: DEFAULT_CRITICAL_DAYS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryStatus {
+    Expired,
+    Critical,
+    Warning,
+    Ok,
+    /// The certificate could not be fetched.
+    Error,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpiryEntry {
+    /// The entry as given, e.g. "example.com:8443".
+    pub target: String,
+    pub host: String,
+    pub port: u16,
+    pub status: ExpiryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days_until_expiry: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// DNS names the certificate covers.
+    pub names: Vec<String>,
+    /// The certificate does not cover the host it was fetched for.
+    pub hostname_mismatch: bool,
+    /// Why the key is considered weak, if it is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weak_key: Option<String>,
+    /// Chain validation or revocation problem, other than expiry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpiryReport {
+    pub thresholds: ExpiryThresholds,
+    pub checked_at: DateTime<Utc>,
+    pub total: usize,
+    pub expired: usize,
+    pub critical: usize,
+    pub warning: usize,
+    pub errors: usize,
+    pub hostname_mismatches: usize,
+    pub weak_keys: usize,
+    /// Soonest expiry first; targets that failed come last.
+    pub certificates: Vec<ExpiryEntry>,
+}
+
+/// Split "host", "host:port" or "[v6]:port" into a host and port (default 443).
+pub fn parse_target(target: &str) -> Result<(String, u16)> {
+    let target = target.trim();
+    let (host, port) = if let Some(rest) = target.strip_prefix('[') {
+        let (host, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| anyhow::anyhow!("Unclosed '[' in '{}'", target))?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match target.rsplit_once(':') {
+            // More than one colon without brackets is a bare IPv6 address.
+            Some((host, port)) if !host.contains(':') => (host, Some(port)),
+            _ => (target, None),
+        }
+    };
+
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.is_empty() {
+        return Err(anyhow::anyhow!("Missing host in '{}'", target));
+    }
+    let port = match port {
+        Some(port) => port
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid port in '{}'", target))?,
+        None => 443,
+    };
+    Ok((host, port))
+}
+
+/// Fetch every target's certificate concurrently and sort them by expiry.
+pub async fn report(targets: &[String], thresholds: ExpiryThresholds) -> ExpiryReport {
+    let concurrency = Scheduler::global().config().domain_concurrency.max(1);
+    let now = Utc::now();
+
+    let entries = stream::iter(targets.iter().cloned())
+        .map(|target| async move {
+            match parse_target(&target) {
+                Ok((host, port)) => {
+                    let endpoint = Endpoint::new(&host, port, Protocol::Tls);
+                    let certificate = ssl::get_certificate_info(&endpoint).await;
+                    entry(&target, &host, port, certificate, thresholds, now)
+                }
+                Err(e) => failed(&target, String::new(), 0, e.to_string()),
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect::<Vec<_>>()
+        .await;
+
+    summarize(entries, thresholds, now)
+}
+
+fn entry(
+    target: &str,
+    host: &str,
+    port: u16,
+    certificate: Result<SslCertificateInfo>,
+    thresholds: ExpiryThresholds,
+    now: DateTime<Utc>,
+) -> ExpiryEntry {
+    let certificate = match certificate {
+        Ok(certificate) => certificate,
+        Err(e) => return failed(target, host.to_string(), port, e.to_string()),
+    };
+
+    let days = ssl::calculate_days_until_expiry(certificate.not_after, now);
+    let status = if certificate.not_after <= now {
+        ExpiryStatus::Expired
+    } else if days <= thresholds.critical_days {
+        ExpiryStatus::Critical
+    } else if days <= thresholds.warn_days {
+        ExpiryStatus::Warning
+    } else {
+        ExpiryStatus::Ok
+    };
+
+    ExpiryEntry {
+        target: target.to_string(),
+        host: host.to_string(),
+        port,
+        status,
+        days_until_expiry: Some(days),
+        not_after: Some(certificate.not_after),
+        issuer: Some(certificate.issuer.text.clone()),
+        hostname_mismatch: !cert_chain::matches_hostname(host, &certificate),
+        weak_key: weak_key(&certificate),
+        validation_error: certificate.validation_error.clone(),
+        names: certificate.san_domains,
+        error: None,
+    }
+}
+
+fn failed(target: &str, host: String, port: u16, error: String) -> ExpiryEntry {
+    ExpiryEntry {
+        target: target.to_string(),
+        host,
+        port,
+        status: ExpiryStatus::Error,
+        days_until_expiry: None,
+        not_after: None,
+        issuer: None,
+        names: Vec::new(),
+        hostname_mismatch: false,
+        weak_key: None,
+        validation_error: None,
+        error: Some(error),
+    }
+}
+
+/// RSA under 2048 bits, EC under 256 bits, and DSA are all below what the
+/// CA/B Forum baseline requirements accept.
+fn weak_key(certificate: &SslCertificateInfo) -> Option<String> {
+    let key = &certificate.public_key;
+    match (key.algorithm.as_str(), key.key_size_bits) {
+        ("RSA", Some(bits)) if bits < 2048 => Some(format!("RSA key of {} bits", bits)),
+        ("EC", Some(bits)) if bits < 256 => Some(format!("EC key of {} bits", bits)),
+        ("DSA", _) => Some("DSA key".to_string()),
+        _ => None,
+    }
+}
+
+fn summarize(
+    mut certificates: Vec<ExpiryEntry>,
+    thresholds: ExpiryThresholds,
+    now: DateTime<Utc>,
+) -> ExpiryReport {
+    certificates.sort_by_key(|entry| {
+        (
+            entry.status == ExpiryStatus::Error,
+            entry.days_until_expiry,
+            entry.target.clone(),
+        )
+    });
+    let count = |status| {
+        certificates
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    };
+
+    ExpiryReport {
+        thresholds,
+        checked_at: now,
+        total: certificates.len(),
+        expired: count(ExpiryStatus::Expired),
+        critical: count(ExpiryStatus::Critical),
+        warning: count(ExpiryStatus::Warning),
+        errors: count(ExpiryStatus::Error),
+        hostname_mismatches: certificates.iter().filter(|e| e.hostname_mismatch).count(),
+        weak_keys: certificates.iter().filter(|e| e.weak_key.is_some()).count(),
+        certificates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const LEAF: &[u8] = include_bytes!("../../tests/fixtures/example-com-leaf.der");
+
+    fn leaf_expiring_in(days: i64, now: DateTime<Utc>) -> SslCertificateInfo {
+        let mut certificate = ssl::parse_x509_certificate(LEAF).unwrap();
+        certificate.not_after = now + Duration::days(days) + Duration::hours(1);
+        certificate
+    }
+
+    #[test]
+    fn parse_target_test() {
+        assert_eq!(
+            parse_target("Example.com").unwrap(),
+            ("example.com".to_string(), 443)
+        );
+        assert_eq!(
+            parse_target("example.com:8443").unwrap(),
+            ("example.com".to_string(), 8443)
+        );
+        assert_eq!(
+            parse_target("[2001:db8::1]:993").unwrap(),
+            ("2001:db8::1".to_string(), 993)
+        );
+        assert_eq!(
+            parse_target("2001:db8::1").unwrap(),
+            ("2001:db8::1".to_string(), 443)
+        );
+        assert!(parse_target("example.com:https").is_err());
+        assert!(parse_target(":443").is_err());
+    }
+
+    #[test]
+    fn classifies_by_threshold() {
+        let now = Utc::now();
+        let thresholds = ExpiryThresholds::default();
+        let status = |days| {
+            entry(
+                "t",
+                "example.com",
+                443,
+                Ok(leaf_expiring_in(days, now)),
+                thresholds,
+                now,
+            )
+            .status
+        };
+
+        assert_eq!(status(90), ExpiryStatus::Ok);
+        assert_eq!(status(30), ExpiryStatus::Warning);
+        assert_eq!(status(7), ExpiryStatus::Critical);
+        assert_eq!(status(0), ExpiryStatus::Critical);
+        assert_eq!(status(-2), ExpiryStatus::Expired);
+    }
+
+    #[test]
+    fn flags_mismatch_and_weak_keys() {
+        let now = Utc::now();
+        let thresholds = ExpiryThresholds::default();
+
+        let matching = entry(
+            "a",
+            "www.example.com",
+            443,
+            Ok(leaf_expiring_in(90, now)),
+            thresholds,
+            now,
+        );
+        assert!(!matching.hostname_mismatch);
+        assert_eq!(matching.weak_key, None);
+
+        let mut weak = leaf_expiring_in(90, now);
+        weak.public_key.key_size_bits = Some(1024);
+        let weak = entry("b", "other.example.net", 443, Ok(weak), thresholds, now);
+        assert!(weak.hostname_mismatch);
+        assert_eq!(weak.weak_key.as_deref(), Some("RSA key of 1024 bits"));
+    }
+
+    #[test]
+    fn sorts_soonest_first_with_errors_last() {
+        let now = Utc::now();
+        let thresholds = ExpiryThresholds::default();
+        let entries = vec![
+            entry(
+                "late",
+                "example.com",
+                443,
+                Ok(leaf_expiring_in(200, now)),
+                thresholds,
+                now,
+            ),
+            failed(
+                "down",
+                "down.example.com".to_string(),
+                443,
+                "refused".to_string(),
+            ),
+            entry(
+                "soon",
+                "example.com",
+                443,
+                Ok(leaf_expiring_in(3, now)),
+                thresholds,
+                now,
+            ),
+            entry(
+                "gone",
+                "example.com",
+                443,
+                Ok(leaf_expiring_in(-10, now)),
+                thresholds,
+                now,
+            ),
+        ];
+
+        let report = summarize(entries, thresholds, now);
+        let order: Vec<&str> = report
+            .certificates
+            .iter()
+            .map(|e| e.target.as_str())
+            .collect();
+        assert_eq!(order, vec!["gone", "soon", "late", "down"]);
+        assert_eq!(
+            (
+                report.total,
+                report.expired,
+                report.critical,
+                report.warning,
+                report.errors
+            ),
+            (4, 1, 1, 0, 1)
+        );
+    }
+}
@@ -0,0 +1,603 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use super::cert_chain::Endpoint;
+use super::ct::{self, CtSource};
+use super::dns;
+use super::http_probe::{self, HstsPolicy, RedirectChain};
+use super::scheduler::Scheduler;
+use super::ssl::{self, SslCertificateInfo};
+use super::starttls::Protocol;
+
+/// The shortest max-age hstspreload.org accepts.
+const PRELOAD_MIN_MAX_AGE: u64 = 31_536_000;
+
+/// Subdomains checked even when the caller lists none and CT has none.
+const DEFAULT_SUBDOMAINS: &[&str] = &["www"];
+
+/// Most names taken from CT logs; each costs a DNS lookup and, when it
+/// resolves, a TLS handshake. Names past this fail `subdomains_https`.
+const MAX_CT_SUBDOMAINS: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreloadReport {
+    pub domain: String,
+    /// True when every requirement passes.
+    pub eligible: bool,
+    pub requirements: Vec<PreloadRequirement>,
+    /// The HSTS policy on the first HTTPS response from the apex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hsts: Option<HstsPolicy>,
+    pub subdomains: Vec<SubdomainHttps>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreloadRequirement {
+    pub requirement: String,
+    pub passed: bool,
+    /// What was seen, and for a failure what to change.
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubdomainHttps {
+    pub name: String,
+    /// Has A or AAAA records; names that don't resolve are not checked.
+    pub resolves: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_certificate: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check `domain` against every hstspreload.org submission requirement.
+/// Subdomains seen in crt.sh certificates and `subdomains` (labels or full
+/// names) are checked along with "www".
+pub async fn check(domain: &str, subdomains: &[String]) -> Result<PreloadReport> {
+    let source = ct::source_from(None, None, None, None)?;
+    check_with(domain, subdomains, source.as_ref()).await
+}
+
+async fn check_with(
+    domain: &str,
+    subdomains: &[String],
+    source: &dyn CtSource,
+) -> Result<PreloadReport> {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+    let endpoint = Endpoint::new(&domain, 443, Protocol::Tls);
+    let (probe, certificate, soa, seen) = futures::join!(
+        http_probe::probe(&domain),
+        ssl::get_certificate_info(&endpoint),
+        dns::query(&domain, "SOA", 6),
+        ct_subdomains(&domain, source)
+    );
+    let probe = probe?;
+    let is_apex = soa.map(|records| !records.is_empty());
+
+    let mut names: BTreeSet<String> = DEFAULT_SUBDOMAINS
+        .iter()
+        .map(|label| label.to_string())
+        .chain(subdomains.iter().cloned())
+        .map(|name| {
+            let name = name.trim().trim_end_matches('.').to_lowercase();
+            if name.ends_with(&format!(".{}", domain)) {
+                name
+            } else {
+                format!("{}.{}", name, domain)
+            }
+        })
+        .collect();
+    let ct_unchecked = seen.map(|seen| {
+        let extra: Vec<String> = seen
+            .into_iter()
+            .filter(|name| !names.contains(name))
+            .collect();
+        let unchecked = extra.len().saturating_sub(MAX_CT_SUBDOMAINS);
+        names.extend(extra.into_iter().take(MAX_CT_SUBDOMAINS));
+        unchecked
+    });
+
+    let concurrency = Scheduler::global().config().domain_concurrency.max(1);
+    let mut subdomains: Vec<SubdomainHttps> = stream::iter(names)
+        .map(|name| async move { check_subdomain(&name).await })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+    subdomains.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let requirements = evaluate(
+        &domain,
+        is_apex,
+        &certificate,
+        &probe.http,
+        &probe.https,
+        &subdomains,
+        &ct_unchecked,
+    );
+    Ok(PreloadReport {
+        eligible: requirements.iter().all(|requirement| requirement.passed),
+        hsts: first_hsts(&probe.https).map(|raw| http_probe::parse_hsts(&raw)),
+        domain,
+        requirements,
+        subdomains,
+    })
+}
+
+/// Names under `domain` in CT logs, which includeSubDomains would cover.
+async fn ct_subdomains(domain: &str, source: &dyn CtSource) -> Result<Vec<String>> {
+    let result = ct::search(domain, source, 0).await?;
+    Ok(result
+        .subdomains
+        .into_iter()
+        .filter(|name| name != domain)
+        .collect())
+}
+
+async fn check_subdomain(name: &str) -> SubdomainHttps {
+    let (a, aaaa) = futures::join!(dns::query(name, "A", 1), dns::query(name, "AAAA", 28));
+    let resolves = match (a, aaaa) {
+        (Ok(a), Ok(aaaa)) => !a.is_empty() || !aaaa.is_empty(),
+        (Err(e), _) | (_, Err(e)) => {
+            return SubdomainHttps {
+                name: name.to_string(),
+                resolves: false,
+                valid_certificate: None,
+                error: Some(e.to_string()),
+            }
+        }
+    };
+    if !resolves {
+        return SubdomainHttps {
+            name: name.to_string(),
+            resolves,
+            valid_certificate: None,
+            error: None,
+        };
+    }
+
+    let endpoint = Endpoint::new(name, 443, Protocol::Tls);
+    let (valid_certificate, error) = match ssl::get_certificate_info(&endpoint).await {
+        Ok(certificate) => (Some(certificate.is_valid), certificate.validation_error),
+        Err(e) => (Some(false), Some(e.to_string())),
+    };
+    SubdomainHttps {
+        name: name.to_string(),
+        resolves,
+        valid_certificate,
+        error,
+    }
+}
+
+/// HSTS on the first HTTPS response; a redirect there must carry it too.
+fn first_hsts(https: &RedirectChain) -> Option<String> {
+    https.hops.first().and_then(|hop| hop.hsts.clone())
+}
+
+fn requirement(name: &str, passed: bool, detail: String) -> PreloadRequirement {
+    PreloadRequirement {
+        requirement: name.to_string(),
+        passed,
+        detail,
+    }
+}
+
+fn evaluate(
+    domain: &str,
+    is_apex: Result<bool>,
+    certificate: &Result<SslCertificateInfo>,
+    http: &RedirectChain,
+    https: &RedirectChain,
+    subdomains: &[SubdomainHttps],
+    ct_unchecked: &Result<usize>,
+) -> Vec<PreloadRequirement> {
+    let mut requirements = Vec::new();
+
+    requirements.push(match is_apex {
+        Ok(true) => requirement("apex_domain", true, format!("{} is a zone apex", domain)),
+        Ok(false) => requirement(
+            "apex_domain",
+            false,
+            format!(
+                "{} has no SOA record of its own; only registered domains can be preloaded, and subdomains are covered by the parent's entry",
+                domain
+            ),
+        ),
+        Err(e) => requirement(
+            "apex_domain",
+            false,
+            format!("Could not look up the SOA record: {}", e),
+        ),
+    });
+
+    requirements.push(match certificate {
+        Ok(certificate) if certificate.is_valid => requirement(
+            "valid_certificate",
+            true,
+            format!("Valid until {}", certificate.not_after),
+        ),
+        Ok(certificate) => requirement(
+            "valid_certificate",
+            false,
+            format!(
+                "The certificate on port 443 does not validate: {}",
+                certificate
+                    .validation_error
+                    .as_deref()
+                    .unwrap_or("expired, not yet valid or revoked")
+            ),
+        ),
+        Err(e) => requirement(
+            "valid_certificate",
+            false,
+            format!("Could not fetch a certificate on port 443: {}", e),
+        ),
+    });
+
+    requirements.push(http_redirect(domain, http));
+
+    let hsts = first_hsts(https);
+    let policy = hsts.as_deref().map(http_probe::parse_hsts);
+    requirements.push(match (&hsts, https.hops.first()) {
+        (Some(raw), _) => requirement("hsts_header", true, raw.clone()),
+        (None, Some(hop)) => requirement(
+            "hsts_header",
+            false,
+            format!(
+                "{} answered {} without Strict-Transport-Security; the header must be on this response, even when it is a redirect",
+                hop.url, hop.status
+            ),
+        ),
+        (None, None) => requirement(
+            "hsts_header",
+            false,
+            format!(
+                "No HTTPS response from {}: {}",
+                https.start_url,
+                https.error.as_deref().unwrap_or("unknown error")
+            ),
+        ),
+    });
+
+    if let Some(policy) = policy {
+        requirements.push(match policy.max_age {
+            Some(age) if age >= PRELOAD_MIN_MAX_AGE => {
+                requirement("max_age", true, format!("max-age={}", age))
+            }
+            Some(age) => requirement(
+                "max_age",
+                false,
+                format!(
+                    "max-age={} is too short; use at least {} (one year)",
+                    age, PRELOAD_MIN_MAX_AGE
+                ),
+            ),
+            None => requirement(
+                "max_age",
+                false,
+                "The header has no valid max-age directive".to_string(),
+            ),
+        });
+        requirements.push(requirement(
+            "include_subdomains",
+            policy.include_subdomains,
+            if policy.include_subdomains {
+                "includeSubDomains is set".to_string()
+            } else {
+                "Add includeSubDomains to the header".to_string()
+            },
+        ));
+        requirements.push(requirement(
+            "preload",
+            policy.preload,
+            if policy.preload {
+                "preload is set".to_string()
+            } else {
+                "Add the preload directive to the header".to_string()
+            },
+        ));
+    }
+
+    let mut failing: Vec<String> = subdomains
+        .iter()
+        .filter(|subdomain| subdomain.resolves || subdomain.error.is_some())
+        .filter(|subdomain| subdomain.valid_certificate != Some(true))
+        .map(|subdomain| match &subdomain.error {
+            Some(error) if !subdomain.resolves => {
+                format!("{} (DNS lookup failed: {})", subdomain.name, error)
+            }
+            Some(error) => format!("{} ({})", subdomain.name, error),
+            None => subdomain.name.clone(),
+        })
+        .collect();
+    match ct_unchecked {
+        Ok(0) => {}
+        Ok(unchecked) => failing.push(format!(
+            "{} more name(s) from CT logs that were not checked",
+            unchecked
+        )),
+        Err(e) => failing.push(format!(
+            "any other names in use (the CT log search failed: {})",
+            e
+        )),
+    }
+    let checked = subdomains
+        .iter()
+        .filter(|subdomain| subdomain.resolves)
+        .count();
+    requirements.push(if failing.is_empty() {
+        requirement(
+            "subdomains_https",
+            true,
+            format!("{} resolving subdomain(s) serve valid HTTPS", checked),
+        )
+    } else {
+        requirement(
+            "subdomains_https",
+            false,
+            format!(
+                "includeSubDomains will force HTTPS on these, but they can't be shown to serve a valid certificate: {}",
+                failing.join("; ")
+            ),
+        )
+    });
+
+    requirements
+}
+
+/// Port 80, if it answers at all, must first redirect to HTTPS on the same
+/// host, so the browser sees HSTS before going anywhere else.
+fn http_redirect(domain: &str, http: &RedirectChain) -> PreloadRequirement {
+    let Some(hop) = http.hops.first() else {
+        return requirement(
+            "http_redirects_to_https",
+            true,
+            "Nothing answers on port 80".to_string(),
+        );
+    };
+
+    let target = hop.location.as_deref().and_then(|location| {
+        Url::parse(&hop.url)
+            .ok()
+            .and_then(|url| url.join(location).ok())
+    });
+    match target {
+        Some(target)
+            if (300..400).contains(&hop.status)
+                && target.scheme() == "https"
+                && target.host_str() == Some(domain) =>
+        {
+            requirement(
+                "http_redirects_to_https",
+                true,
+                format!("{} redirects to {}", hop.url, target),
+            )
+        }
+        Some(target) if (300..400).contains(&hop.status) => requirement(
+            "http_redirects_to_https",
+            false,
+            format!(
+                "{} redirects to {}; the first redirect must go to https://{}/ on the same host",
+                hop.url, target, domain
+            ),
+        ),
+        _ => requirement(
+            "http_redirects_to_https",
+            false,
+            format!(
+                "{} answered {} instead of redirecting to https://{}/",
+                hop.url, hop.status, domain
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_probe::Hop;
+
+    const LEAF: &[u8] = include_bytes!("../../tests/fixtures/example-com-leaf.der");
+
+    fn chain(start: &str, hops: &[(u16, Option<&str>, Option<&str>)]) -> RedirectChain {
+        let mut url = Url::parse(start).unwrap();
+        let mut chain = RedirectChain {
+            start_url: start.to_string(),
+            hops: Vec::new(),
+            final_url: None,
+            final_status: None,
+            error: None,
+        };
+        for (status, location, hsts) in hops {
+            chain.hops.push(Hop {
+                url: url.to_string(),
+                status: *status,
+                location: location.map(str::to_string),
+                hsts: hsts.map(str::to_string),
+                elapsed_ms: 1,
+            });
+            if let Some(location) = location {
+                url = url.join(location).unwrap();
+            }
+        }
+        chain
+    }
+
+    fn failed(requirements: &[PreloadRequirement]) -> Vec<&str> {
+        requirements
+            .iter()
+            .filter(|requirement| !requirement.passed)
+            .map(|requirement| requirement.requirement.as_str())
+            .collect()
+    }
+
+    fn www(valid: Option<bool>) -> SubdomainHttps {
+        SubdomainHttps {
+            name: "www.example.com".to_string(),
+            resolves: valid.is_some(),
+            valid_certificate: valid,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn eligible_domain_passes_everything() {
+        let hsts = "max-age=63072000; includeSubDomains; preload";
+        let requirements = evaluate(
+            "example.com",
+            Ok(true),
+            &ssl::parse_x509_certificate(LEAF),
+            &chain(
+                "http://example.com/",
+                &[
+                    (301, Some("https://example.com/"), None),
+                    (200, None, Some(hsts)),
+                ],
+            ),
+            // HSTS on the redirect counts even though the final page lacks it.
+            &chain(
+                "https://example.com/",
+                &[
+                    (301, Some("https://www.example.com/"), Some(hsts)),
+                    (200, None, None),
+                ],
+            ),
+            &[www(Some(true))],
+            &Ok(0),
+        );
+        assert_eq!(failed(&requirements), Vec::<&str>::new());
+        assert_eq!(requirements.len(), 8);
+    }
+
+    #[test]
+    fn explains_each_failure() {
+        let requirements = evaluate(
+            "example.com",
+            Ok(false),
+            &ssl::parse_x509_certificate(LEAF),
+            &chain(
+                "http://example.com/",
+                &[(301, Some("https://www.example.com/"), None)],
+            ),
+            &chain(
+                "https://example.com/",
+                &[(200, None, Some("max-age=86400"))],
+            ),
+            &[www(Some(false))],
+            &Ok(0),
+        );
+        assert_eq!(
+            failed(&requirements),
+            vec![
+                "apex_domain",
+                "http_redirects_to_https",
+                "max_age",
+                "include_subdomains",
+                "preload",
+                "subdomains_https",
+            ]
+        );
+        let redirect = &requirements[2];
+        assert!(redirect.detail.contains("same host"), "{}", redirect.detail);
+    }
+
+    #[test]
+    fn missing_hsts_and_closed_port_80() {
+        let requirements = evaluate(
+            "example.com",
+            Ok(true),
+            &Err(anyhow::anyhow!("Connection refused")),
+            &chain("http://example.com/", &[]),
+            &chain(
+                "https://example.com/",
+                &[
+                    (301, Some("/home"), None),
+                    (200, None, Some("max-age=63072000")),
+                ],
+            ),
+            &[www(None)],
+            &Ok(0),
+        );
+        // Port 80 being closed is fine; www doesn't resolve so isn't checked.
+        assert_eq!(
+            failed(&requirements),
+            vec!["valid_certificate", "hsts_header"]
+        );
+        assert!(requirements[3]
+            .detail
+            .contains("even when it is a redirect"));
+    }
+
+    struct FixedSource(Vec<&'static str>);
+
+    #[async_trait::async_trait]
+    impl CtSource for FixedSource {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn certificates(&self, _domain: &str) -> Result<Vec<ct::CtCertificate>> {
+            let now = chrono::Utc::now();
+            Ok(vec![ct::CtCertificate {
+                id: Some(1),
+                issuer: "CN=Test CA".to_string(),
+                common_name: None,
+                names: self.0.iter().map(|name| name.to_string()).collect(),
+                serial_number: "01".to_string(),
+                not_before: now,
+                not_after: now,
+                logged_at: None,
+            }])
+        }
+    }
+
+    #[tokio::test]
+    async fn ct_subdomains_skips_the_apex_test() {
+        let source = FixedSource(vec![
+            "example.com",
+            "*.example.com",
+            "api.example.com",
+            "*.dev.example.com",
+        ]);
+        let names = ct_subdomains("example.com", &source).await.unwrap();
+        assert_eq!(names, vec!["api.example.com", "dev.example.com"]);
+    }
+
+    #[test]
+    fn unchecked_ct_names_fail_subdomains_https() {
+        let hsts = "max-age=63072000; includeSubDomains; preload";
+        let http = chain(
+            "http://example.com/",
+            &[(301, Some("https://example.com/"), None)],
+        );
+        let https = chain("https://example.com/", &[(200, None, Some(hsts))]);
+        let certificate = ssl::parse_x509_certificate(LEAF);
+
+        for (ct_unchecked, expected) in [
+            (Ok(3), "3 more name(s) from CT logs"),
+            (
+                Err(anyhow::anyhow!("crt.sh returned 502")),
+                "crt.sh returned 502",
+            ),
+        ] {
+            let requirements = evaluate(
+                "example.com",
+                Ok(true),
+                &certificate,
+                &http,
+                &https,
+                &[www(Some(true))],
+                &ct_unchecked,
+            );
+            assert_eq!(failed(&requirements), vec!["subdomains_https"]);
+            let subdomains = requirements.last().unwrap();
+            assert!(
+                subdomains.detail.contains(expected),
+                "{}",
+                subdomains.detail
+            );
+        }
+    }
+}
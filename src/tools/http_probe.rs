@@ -0,0 +1,507 @@
+use anyhow::Result;
+use reqwest::header::HeaderMap;
+use reqwest::redirect::Policy;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::scheduler::Scheduler;
+
+/// Redirects followed before a chain is abandoned.
+const MAX_REDIRECTS: usize = 10;
+
+/// One year, the max-age browsers and the preload list expect.
+const HSTS_RECOMMENDED_MAX_AGE: u64 = 31_536_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpProbeResult {
+    pub domain: String,
+    pub http: RedirectChain,
+    pub https: RedirectChain,
+    /// Plain HTTP ends up on an HTTPS URL.
+    pub https_enforced: bool,
+    /// Strict-Transport-Security from the final HTTPS response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hsts: Option<HstsPolicy>,
+    pub headers: Vec<HeaderCheck>,
+    /// Security header score out of 100.
+    pub score: u32,
+    pub grade: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedirectChain {
+    pub start_url: String,
+    pub hops: Vec<Hop>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_status: Option<u16>,
+    /// The chain stopped before a non-redirect response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Hop {
+    pub url: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// Strict-Transport-Security sent with this response, redirects included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hsts: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HstsPolicy {
+    pub raw: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<u64>,
+    pub include_subdomains: bool,
+    pub preload: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderStatus {
+    Good,
+    Weak,
+    Missing,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeaderCheck {
+    pub name: String,
+    pub status: HeaderStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+pub async fn probe(domain: &str) -> Result<HttpProbeResult> {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+    let client = Client::builder()
+        .user_agent("Domain-MCP-Rust/1.0")
+        .timeout(Duration::from_secs(10))
+        .redirect(Policy::none())
+        .build()?;
+
+    let http_url = format!("http://{}/", domain);
+    let https_url = format!("https://{}/", domain);
+    let ((http, _), (https, https_headers)) =
+        futures::join!(follow(&client, &http_url), follow(&client, &https_url));
+
+    let https_enforced = http
+        .final_url
+        .as_deref()
+        .is_some_and(|url| url.starts_with("https://"))
+        && http.error.is_none();
+
+    // Browsers ignore HSTS sent over plain HTTP, so only the HTTPS chain counts.
+    let headers = https_headers.unwrap_or_default();
+    let hsts = header(&headers, "strict-transport-security").map(|raw| parse_hsts(&raw));
+    let checks = grade_headers(&headers, hsts.as_ref());
+    let score = score(&checks);
+
+    Ok(HttpProbeResult {
+        domain,
+        http,
+        https,
+        https_enforced,
+        hsts,
+        headers: checks,
+        score,
+        grade: letter_grade(score).to_string(),
+    })
+}
+
+/// Follow redirects from `start` by hand, recording each hop. Returns the
+/// headers of the final response when the chain ends on an HTTPS URL.
+async fn follow(client: &Client, start: &str) -> (RedirectChain, Option<HeaderMap>) {
+    let mut chain = RedirectChain {
+        start_url: start.to_string(),
+        hops: Vec::new(),
+        final_url: None,
+        final_status: None,
+        error: None,
+    };
+
+    let mut url = match Url::parse(start) {
+        Ok(url) => url,
+        Err(e) => {
+            chain.error = Some(e.to_string());
+            return (chain, None);
+        }
+    };
+
+    loop {
+        let started = Instant::now();
+        let response = match client.get(url.clone()).build() {
+            Ok(request) => Scheduler::global().execute(client, request).await,
+            Err(e) => Err(e.into()),
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                chain.error = Some(format!("{}: {:#}", url, e));
+                return (chain, None);
+            }
+        };
+
+        let status = response.status();
+        let location = header(response.headers(), "location");
+        chain.hops.push(Hop {
+            url: url.to_string(),
+            status: status.as_u16(),
+            location: location.clone(),
+            hsts: header(response.headers(), "strict-transport-security"),
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+
+        let next = match location.filter(|_| status.is_redirection()) {
+            Some(location) => url.join(&location),
+            None => {
+                chain.final_url = Some(url.to_string());
+                chain.final_status = Some(status.as_u16());
+                let headers = (url.scheme() == "https").then(|| response.headers().clone());
+                return (chain, headers);
+            }
+        };
+
+        url = match next {
+            Ok(next) => next,
+            Err(e) => {
+                chain.error = Some(format!("Bad Location header: {}", e));
+                return (chain, None);
+            }
+        };
+        if chain.hops.iter().any(|hop| hop.url == url.as_str()) {
+            chain.error = Some(format!("Redirect loop back to {}", url));
+            return (chain, None);
+        }
+        if chain.hops.len() >= MAX_REDIRECTS {
+            chain.error = Some(format!("More than {} redirects", MAX_REDIRECTS));
+            return (chain, None);
+        }
+    }
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+}
+
+pub fn parse_hsts(raw: &str) -> HstsPolicy {
+    let mut policy = HstsPolicy {
+        raw: raw.to_string(),
+        max_age: None,
+        include_subdomains: false,
+        preload: false,
+    };
+    for directive in raw.split(';') {
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name, Some(value.trim().trim_matches('"'))),
+            None => (directive, None),
+        };
+        match name.trim().to_lowercase().as_str() {
+            "max-age" => policy.max_age = value.and_then(|value| value.parse().ok()),
+            "includesubdomains" => policy.include_subdomains = true,
+            "preload" => policy.preload = true,
+            _ => {}
+        }
+    }
+    policy
+}
+
+fn grade_headers(headers: &HeaderMap, hsts: Option<&HstsPolicy>) -> Vec<HeaderCheck> {
+    let csp = header(headers, "content-security-policy");
+    vec![
+        grade_hsts(hsts),
+        grade_csp(csp.as_deref()),
+        grade_frame_options(header(headers, "x-frame-options"), csp.as_deref()),
+        grade_referrer_policy(header(headers, "referrer-policy")),
+        check(
+            "Permissions-Policy",
+            header(headers, "permissions-policy"),
+            |_| None,
+        ),
+    ]
+}
+
+/// A present header is good unless `weakness` explains why it isn't.
+fn check(
+    name: &str,
+    value: Option<String>,
+    weakness: impl Fn(&str) -> Option<String>,
+) -> HeaderCheck {
+    let (status, note) = match &value {
+        None => (HeaderStatus::Missing, None),
+        Some(value) => match weakness(value) {
+            Some(note) => (HeaderStatus::Weak, Some(note)),
+            None => (HeaderStatus::Good, None),
+        },
+    };
+    HeaderCheck {
+        name: name.to_string(),
+        status,
+        value,
+        note,
+    }
+}
+
+fn grade_hsts(hsts: Option<&HstsPolicy>) -> HeaderCheck {
+    check(
+        "Strict-Transport-Security",
+        hsts.map(|h| h.raw.clone()),
+        |_| {
+            let hsts = hsts?;
+            match hsts.max_age {
+                None => Some("No valid max-age".to_string()),
+                Some(0) => Some("max-age=0 removes the policy".to_string()),
+                Some(age) if age < HSTS_RECOMMENDED_MAX_AGE => Some(format!(
+                    "max-age {} is under one year ({})",
+                    age, HSTS_RECOMMENDED_MAX_AGE
+                )),
+                _ if !hsts.include_subdomains => Some(
+                    "Without includeSubDomains, subdomains can still be reached over HTTP"
+                        .to_string(),
+                ),
+                _ => None,
+            }
+        },
+    )
+}
+
+fn grade_csp(csp: Option<&str>) -> HeaderCheck {
+    check("Content-Security-Policy", csp.map(str::to_string), |csp| {
+        let scripts = csp
+            .split(';')
+            .map(str::trim)
+            .find(|d| d.starts_with("script-src "))
+            .or_else(|| {
+                csp.split(';')
+                    .map(str::trim)
+                    .find(|d| d.starts_with("default-src "))
+            });
+        match scripts {
+            None => Some("No script-src or default-src, so scripts are unrestricted".to_string()),
+            Some(d)
+                if d.contains("'unsafe-inline'")
+                    && !d.contains("'nonce-")
+                    && !d.contains("'sha") =>
+            {
+                Some("Allows 'unsafe-inline' scripts".to_string())
+            }
+            Some(d) if d.contains("'unsafe-eval'") => Some("Allows 'unsafe-eval'".to_string()),
+            Some(d)
+                if d.split_whitespace()
+                    .any(|s| s == "*" || s == "https:" || s == "http:") =>
+            {
+                Some("Allows scripts from any host".to_string())
+            }
+            _ => None,
+        }
+    })
+}
+
+/// CSP frame-ancestors supersedes X-Frame-Options, so either one is enough.
+fn grade_frame_options(value: Option<String>, csp: Option<&str>) -> HeaderCheck {
+    let frame_ancestors = csp.is_some_and(|csp| {
+        csp.split(';')
+            .any(|d| d.trim().starts_with("frame-ancestors"))
+    });
+    let mut result = check("X-Frame-Options", value, |value| {
+        match value.to_uppercase().as_str() {
+            "DENY" | "SAMEORIGIN" => None,
+            _ => Some("Only DENY and SAMEORIGIN are supported by browsers".to_string()),
+        }
+    });
+    if result.status != HeaderStatus::Good && frame_ancestors {
+        result.status = HeaderStatus::Good;
+        result.note = Some("Covered by CSP frame-ancestors".to_string());
+    }
+    result
+}
+
+fn grade_referrer_policy(value: Option<String>) -> HeaderCheck {
+    check("Referrer-Policy", value, |value| {
+        // The last policy the browser understands wins.
+        let policy = value.rsplit(',').next().unwrap_or("").trim().to_lowercase();
+        match policy.as_str() {
+            "unsafe-url" | "no-referrer-when-downgrade" => {
+                Some(format!("'{}' leaks full URLs to other sites", policy))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// HSTS is worth 30 points, CSP 25 and the other three 15 each; a weak header
+/// earns half.
+fn score(checks: &[HeaderCheck]) -> u32 {
+    checks
+        .iter()
+        .map(|check| {
+            let weight = match check.name.as_str() {
+                "Strict-Transport-Security" => 30,
+                "Content-Security-Policy" => 25,
+                _ => 15,
+            };
+            match check.status {
+                HeaderStatus::Good => weight,
+                HeaderStatus::Weak => weight / 2,
+                HeaderStatus::Missing => 0,
+            }
+        })
+        .sum()
+}
+
+fn letter_grade(score: u32) -> &'static str {
+    match score {
+        90.. => "A",
+        75..=89 => "B",
+        60..=74 => "C",
+        40..=59 => "D",
+        _ => "F",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    fn status_of<'a>(checks: &'a [HeaderCheck], name: &str) -> &'a HeaderCheck {
+        checks.iter().find(|check| check.name == name).unwrap()
+    }
+
+    #[test]
+    fn parse_hsts_test() {
+        let hsts = parse_hsts("max-age=\"63072000\"; includeSubDomains; preload");
+        assert_eq!(hsts.max_age, Some(63_072_000));
+        assert!(hsts.include_subdomains);
+        assert!(hsts.preload);
+
+        let hsts = parse_hsts("max-age=abc");
+        assert_eq!(hsts.max_age, None);
+        assert!(!hsts.include_subdomains);
+    }
+
+    #[test]
+    fn grades_headers() {
+        let strong = headers(&[
+            (
+                "strict-transport-security",
+                "max-age=31536000; includeSubDomains; preload",
+            ),
+            (
+                "content-security-policy",
+                "default-src 'self'; frame-ancestors 'none'",
+            ),
+            ("referrer-policy", "strict-origin-when-cross-origin"),
+            ("permissions-policy", "geolocation=()"),
+        ]);
+        let hsts = header(&strong, "strict-transport-security").map(|raw| parse_hsts(&raw));
+        let checks = grade_headers(&strong, hsts.as_ref());
+        assert!(checks
+            .iter()
+            .all(|check| check.status == HeaderStatus::Good));
+        assert_eq!(score(&checks), 100);
+        assert_eq!(letter_grade(score(&checks)), "A");
+
+        let weak = headers(&[
+            ("strict-transport-security", "max-age=300"),
+            (
+                "content-security-policy",
+                "script-src 'self' 'unsafe-inline'",
+            ),
+            ("x-frame-options", "ALLOW-FROM https://example.com"),
+            ("referrer-policy", "no-referrer, unsafe-url"),
+        ]);
+        let hsts = header(&weak, "strict-transport-security").map(|raw| parse_hsts(&raw));
+        let checks = grade_headers(&weak, hsts.as_ref());
+        for name in [
+            "Strict-Transport-Security",
+            "Content-Security-Policy",
+            "X-Frame-Options",
+            "Referrer-Policy",
+        ] {
+            assert_eq!(
+                status_of(&checks, name).status,
+                HeaderStatus::Weak,
+                "{}",
+                name
+            );
+        }
+        assert_eq!(
+            status_of(&checks, "Permissions-Policy").status,
+            HeaderStatus::Missing
+        );
+        assert_eq!(score(&checks), 15 + 12 + 7 + 7);
+        assert_eq!(letter_grade(score(&checks)), "D");
+
+        assert_eq!(score(&grade_headers(&HeaderMap::new(), None)), 0);
+    }
+
+    /// Answers each connection with the next canned response.
+    async fn serve(responses: Vec<String>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = [0u8; 1024];
+                let _ = socket.read(&mut buffer).await.unwrap();
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn follows_redirects_and_detects_loops() {
+        let redirect = |location: &str| {
+            format!(
+                "HTTP/1.1 301 Moved Permanently\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                location
+            )
+        };
+        let client = Client::builder().redirect(Policy::none()).build().unwrap();
+
+        let base = serve(vec![
+            redirect("/a"),
+            redirect("/b"),
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ])
+        .await;
+        let (chain, headers) = follow(&client, &format!("{}/", base)).await;
+        assert_eq!(chain.hops.len(), 3);
+        assert_eq!(chain.hops[0].status, 301);
+        assert_eq!(chain.hops[0].location.as_deref(), Some("/a"));
+        assert_eq!(chain.final_url, Some(format!("{}/b", base)));
+        assert_eq!(chain.final_status, Some(200));
+        assert!(chain.error.is_none());
+        // Plain HTTP headers are not used for grading.
+        assert!(headers.is_none());
+
+        let base = serve(vec![redirect("/loop"), redirect("/")]).await;
+        let (chain, _) = follow(&client, &format!("{}/", base)).await;
+        assert_eq!(chain.hops.len(), 2);
+        assert!(chain.final_url.is_none());
+        assert!(chain.error.unwrap().starts_with("Redirect loop"));
+    }
+}
@@ -1,10 +1,28 @@
+pub mod caa;
+pub mod cert_chain;
+pub mod ct;
 pub mod dns;
 pub mod domain;
 pub mod domain_age_check;
+pub mod drop_cache;
+pub mod drop_csv;
 pub mod expired;
+pub mod expiry;
+pub mod hsts_preload;
+pub mod http_probe;
 pub mod rdap;
+pub mod registry_check;
+pub mod revocation;
+pub mod scheduler;
+pub mod scoring;
 pub mod ssl;
+pub mod starttls;
+pub mod suggest;
+pub mod tlds;
+pub mod tls_scan;
+pub mod watchlist;
 pub mod whois;
+pub mod words;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -1,7 +1,10 @@
 use anyhow::Result;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tokio::sync::OnceCell;
+
+use super::scheduler::Scheduler;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RdapDomain {
@@ -103,9 +106,17 @@ pub struct RdapBootstrapResponse {
     pub description: Option<String>,
 }
 
+/// Outcome of an RDAP domain query that reached an authoritative server.
+#[derive(Debug)]
+pub enum RdapLookup {
+    Found(Box<RdapDomain>),
+    NotFound { server: String },
+}
+
+const IANA_BOOTSTRAP_URL: &str = "https://data.iana.org/rdap/dns.json";
+
 pub struct RdapClient {
     client: Client,
-    rdap_base_urls: HashMap<String, String>,
 }
 
 impl Default for RdapClient {
@@ -116,77 +127,107 @@ impl Default for RdapClient {
 
 impl RdapClient {
     pub fn new() -> Self {
-        let mut rdap_base_urls = HashMap::new();
-
-        // Static mapping of TLD to RDAP servers (same as Python version)
-        rdap_base_urls.insert(
-            "com".to_string(),
-            "https://rdap.verisign.com/com/v1".to_string(),
-        );
-        rdap_base_urls.insert(
-            "net".to_string(),
-            "https://rdap.verisign.com/net/v1".to_string(),
-        );
-        rdap_base_urls.insert(
-            "org".to_string(),
-            "https://rdap.publicinterestregistry.org/rdap".to_string(),
-        );
-        rdap_base_urls.insert(
-            "info".to_string(),
-            "https://rdap.afilias.net/rdap".to_string(),
-        );
-        rdap_base_urls.insert("io".to_string(), "https://rdap.nic.io".to_string());
-        rdap_base_urls.insert("co".to_string(), "https://rdap.nic.co".to_string());
-        rdap_base_urls.insert("me".to_string(), "https://rdap.nic.me".to_string());
-        rdap_base_urls.insert("tv".to_string(), "https://rdap.nic.tv".to_string());
-        rdap_base_urls.insert("app".to_string(), "https://rdap.nic.google".to_string());
-        rdap_base_urls.insert("dev".to_string(), "https://rdap.nic.google".to_string());
-        rdap_base_urls.insert("cloud".to_string(), "https://rdap.nic.google".to_string());
-
         Self {
             client: Client::builder()
                 .user_agent("Domain-MCP-Rust/1.0")
                 .timeout(std::time::Duration::from_secs(10))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
-            rdap_base_urls,
         }
     }
 
     pub async fn lookup_domain(&self, domain: &str) -> Result<RdapDomain> {
+        match self.lookup(domain).await? {
+            RdapLookup::Found(rdap_domain) => Ok(*rdap_domain),
+            RdapLookup::NotFound { server } => Err(anyhow::anyhow!(
+                "RDAP server {} has no record for domain: {}",
+                server,
+                domain
+            )),
+        }
+    }
+
+    /// Look up a domain and keep the distinction between "the registry has no
+    /// such object" (HTTP 404) and "the lookup failed".
+    pub async fn lookup(&self, domain: &str) -> Result<RdapLookup> {
         let tld = domain
             .split('.')
             .next_back()
             .ok_or_else(|| anyhow::anyhow!("Invalid domain format"))?;
 
-        // Try static mapping first
-        if let Some(base_url) = self.rdap_base_urls.get(tld) {
+        // The IANA bootstrap registry names the authoritative server
+        let registry = self.bootstrap_registry().await.ok();
+        if let Some(base_url) = registry.and_then(|registry| registry.get(tld)) {
             if let Ok(result) = self.query_rdap_server(base_url, domain).await {
                 return Ok(result);
             }
         }
 
-        // Fallback to IANA bootstrap
-        if let Ok(result) = self.bootstrap_lookup(domain).await {
-            return Ok(result);
+        // Fallback to the bootstrap redirect service. Its 404 only means "not
+        // registered" when it sent us to a server the IANA registry lists.
+        match self.bootstrap_lookup(domain).await {
+            Ok(RdapLookup::NotFound { server })
+                if registry.is_some_and(|registry| registry.values().any(|url| *url == server)) =>
+            {
+                return Ok(RdapLookup::NotFound { server });
+            }
+            Ok(RdapLookup::Found(rdap_domain)) => return Ok(RdapLookup::Found(rdap_domain)),
+            _ => {}
         }
 
         Err(anyhow::anyhow!("RDAP lookup failed for domain: {}", domain))
     }
 
-    async fn query_rdap_server(&self, base_url: &str, domain: &str) -> Result<RdapDomain> {
+    /// The IANA RDAP bootstrap registry (RFC 9224) as a TLD to base URL map.
+    /// This is also the list of every TLD that publishes RDAP. It is fetched
+    /// once per process.
+    pub async fn bootstrap_registry(&self) -> Result<&'static HashMap<String, String>> {
+        static REGISTRY: OnceCell<HashMap<String, String>> = OnceCell::const_new();
+        REGISTRY
+            .get_or_try_init(|| self.fetch_bootstrap_registry())
+            .await
+    }
+
+    async fn fetch_bootstrap_registry(&self) -> Result<HashMap<String, String>> {
+        let request = self
+            .client
+            .get(IANA_BOOTSTRAP_URL)
+            .header("Accept", "application/json")
+            .build()?;
+        let response = Scheduler::global().execute(&self.client, request).await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "IANA bootstrap registry returned status: {}",
+                response.status()
+            ));
+        }
+
+        let bootstrap: RdapBootstrapResponse = response.json().await?;
+        Ok(parse_bootstrap_registry(&bootstrap))
+    }
+
+    async fn query_rdap_server(&self, base_url: &str, domain: &str) -> Result<RdapLookup> {
         let url = format!("{}/domain/{}", base_url, domain);
 
-        let response = self
+        let request = self
             .client
             .get(&url)
             .header("Accept", "application/rdap+json")
-            .send()
-            .await?;
+            .build()?;
+        let response = Scheduler::global().execute(&self.client, request).await?;
+
+        // RFC 7480 section 5.3: an authoritative server answers 404 when it has
+        // no object for the query, which is the definitive "not registered".
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(RdapLookup::NotFound {
+                server: base_url.to_string(),
+            });
+        }
 
         if response.status().is_success() {
             let rdap_domain: RdapDomain = response.json().await?;
-            Ok(rdap_domain)
+            Ok(RdapLookup::Found(Box::new(rdap_domain)))
         } else {
             Err(anyhow::anyhow!(
                 "RDAP server returned status: {}",
@@ -195,19 +236,19 @@ impl RdapClient {
         }
     }
 
-    async fn bootstrap_lookup(&self, domain: &str) -> Result<RdapDomain> {
+    async fn bootstrap_lookup(&self, domain: &str) -> Result<RdapLookup> {
         // Query IANA bootstrap service
         let bootstrap_url = format!(
             "https://rdap-bootstrap.arin.net/bootstrap/domain/{}",
             domain
         );
 
-        let bootstrap_response = self
+        let request = self
             .client
             .get(&bootstrap_url)
             .header("Accept", "application/json")
-            .send()
-            .await?;
+            .build()?;
+        let bootstrap_response = Scheduler::global().execute(&self.client, request).await?;
 
         if !bootstrap_response.status().is_success() {
             return Err(anyhow::anyhow!("Bootstrap lookup failed"));
@@ -221,19 +262,9 @@ impl RdapClient {
                     if let Some(rdap_url_array) = rdap_urls.as_array() {
                         if let Some(rdap_url) = rdap_url_array.first() {
                             if let Some(rdap_url_str) = rdap_url.as_str() {
-                                let url = format!("{}/domain/{}", rdap_url_str, domain);
-
-                                let response = self
-                                    .client
-                                    .get(&url)
-                                    .header("Accept", "application/rdap+json")
-                                    .send()
-                                    .await?;
-
-                                if response.status().is_success() {
-                                    let rdap_domain: RdapDomain = response.json().await?;
-                                    return Ok(rdap_domain);
-                                }
+                                return self
+                                    .query_rdap_server(rdap_url_str.trim_end_matches('/'), domain)
+                                    .await;
                             }
                         }
                     }
@@ -245,6 +276,39 @@ impl RdapClient {
     }
 }
 
+/// Each bootstrap service is `[[tld, ...], [base_url, ...]]`; HTTPS base URLs
+/// are preferred.
+pub fn parse_bootstrap_registry(bootstrap: &RdapBootstrapResponse) -> HashMap<String, String> {
+    let mut registry = HashMap::new();
+
+    for service in bootstrap.services.iter().flatten() {
+        let (Some(tlds), Some(urls)) = (
+            service.first().and_then(|v| v.as_array()),
+            service.get(1).and_then(|v| v.as_array()),
+        ) else {
+            continue;
+        };
+
+        let urls: Vec<&str> = urls.iter().filter_map(|url| url.as_str()).collect();
+        let Some(base_url) = urls
+            .iter()
+            .find(|url| url.starts_with("https://"))
+            .or_else(|| urls.first())
+        else {
+            continue;
+        };
+
+        for tld in tlds.iter().filter_map(|tld| tld.as_str()) {
+            registry.insert(
+                tld.to_lowercase(),
+                base_url.trim_end_matches('/').to_string(),
+            );
+        }
+    }
+
+    registry
+}
+
 // Utility functions for parsing RDAP data
 pub fn extract_creation_date(rdap_domain: &RdapDomain) -> Option<String> {
     rdap_domain
@@ -333,3 +397,30 @@ pub fn extract_nameservers(rdap_domain: &RdapDomain) -> Vec<String> {
 pub fn extract_status(rdap_domain: &RdapDomain) -> Vec<String> {
     rdap_domain.status.clone().unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bootstrap_registry_test() {
+        let bootstrap: RdapBootstrapResponse = serde_json::from_str(
+            r#"{
+                "description": "RDAP bootstrap file for Domain Name System registrations",
+                "services": [
+                    [["com", "net"], ["https://rdap.verisign.com/com/v1/"]],
+                    [["app", "dev"], ["http://rdap.example/", "https://pubapi.registry.google/rdap/"]],
+                    [["broken"]]
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let registry = parse_bootstrap_registry(&bootstrap);
+        assert_eq!(registry.len(), 4);
+        assert_eq!(registry["com"], "https://rdap.verisign.com/com/v1");
+        assert_eq!(registry["net"], "https://rdap.verisign.com/com/v1");
+        assert_eq!(registry["dev"], "https://pubapi.registry.google/rdap");
+        assert!(!registry.contains_key("broken"));
+    }
+}
@@ -0,0 +1,1052 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use super::scheduler::Scheduler;
+
+/// Registration status as reported by a registry or registrar check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryStatus {
+    Available,
+    Registered,
+    Reserved,
+    Premium,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryCheck {
+    pub domain: String,
+    pub status: RegistryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+/// An authoritative availability check, such as a registrar availability API
+/// or an EPP `<domain:check>` against the registry.
+#[async_trait]
+pub trait AvailabilityProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether the provider can answer for `domain`. Domains it does not
+    /// cover are left to RDAP and DNS.
+    fn covers(&self, _domain: &str) -> bool {
+        true
+    }
+
+    async fn check(&self, domain: &str) -> Result<RegistryCheck>;
+}
+
+/// Returns the provider configured through the environment, if any. It is
+/// created once per process so an EPP session is shared by every check.
+///
+/// EPP is enabled when `DOMAIN_MCP_EPP_HOST`, `DOMAIN_MCP_EPP_USERNAME`,
+/// `DOMAIN_MCP_EPP_PASSWORD` and `DOMAIN_MCP_EPP_TLDS` are set; otherwise the Namecheap API is used when
+/// `DOMAIN_MCP_NAMECHEAP_API_USER`, `DOMAIN_MCP_NAMECHEAP_API_KEY` and
+/// `DOMAIN_MCP_NAMECHEAP_CLIENT_IP` are set.
+pub fn configured_provider() -> Option<Arc<dyn AvailabilityProvider>> {
+    static PROVIDER: OnceLock<Option<Arc<dyn AvailabilityProvider>>> = OnceLock::new();
+    PROVIDER
+        .get_or_init(|| {
+            if let Some(config) = EppConfig::from_env() {
+                return Some(Arc::new(EppProvider::new(config)) as _);
+            }
+            NamecheapConfig::from_env().map(|config| Arc::new(NamecheapProvider::new(config)) as _)
+        })
+        .clone()
+}
+
+/// In-memory provider with canned answers, for tests and local development.
+#[derive(Debug, Clone, Default)]
+pub struct MockProvider {
+    answers: HashMap<String, RegistryCheck>,
+    default_status: Option<RegistryStatus>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_status(mut self, domain: &str, status: RegistryStatus) -> Self {
+        let domain = domain.to_lowercase();
+        self.answers.insert(
+            domain.clone(),
+            RegistryCheck {
+                domain,
+                status,
+                reason: None,
+                price: None,
+                currency: None,
+            },
+        );
+        self
+    }
+
+    pub fn with_premium(mut self, domain: &str, price: &str, currency: &str) -> Self {
+        let domain = domain.to_lowercase();
+        self.answers.insert(
+            domain.clone(),
+            RegistryCheck {
+                domain,
+                status: RegistryStatus::Premium,
+                reason: Some("premium".to_string()),
+                price: Some(price.to_string()),
+                currency: Some(currency.to_string()),
+            },
+        );
+        self
+    }
+
+    /// Status returned for domains without a canned answer. Without a default,
+    /// unknown domains are an error.
+    pub fn with_default(mut self, status: RegistryStatus) -> Self {
+        self.default_status = Some(status);
+        self
+    }
+}
+
+#[async_trait]
+impl AvailabilityProvider for MockProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn check(&self, domain: &str) -> Result<RegistryCheck> {
+        let domain = domain.to_lowercase();
+
+        if let Some(answer) = self.answers.get(&domain) {
+            return Ok(answer.clone());
+        }
+
+        match self.default_status {
+            Some(status) => Ok(RegistryCheck {
+                domain,
+                status,
+                reason: None,
+                price: None,
+                currency: None,
+            }),
+            None => Err(anyhow::anyhow!("No mock answer for domain: {}", domain)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EppConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// PEM client certificate chain and key, required by most registries.
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    pub currency: String,
+    pub timeout: Duration,
+    /// Names sent in one `<domain:check>`.
+    pub batch_size: usize,
+    /// TLDs the registry is authoritative for, e.g. `["com", "net"]`. Any
+    /// other domain would come back unavailable, so it is not sent.
+    pub tlds: Vec<String>,
+}
+
+impl EppConfig {
+    pub fn from_env() -> Option<Self> {
+        let host = std::env::var("DOMAIN_MCP_EPP_HOST").ok()?;
+        let username = std::env::var("DOMAIN_MCP_EPP_USERNAME").ok()?;
+        let password = std::env::var("DOMAIN_MCP_EPP_PASSWORD").ok()?;
+        let tlds: Vec<String> = std::env::var("DOMAIN_MCP_EPP_TLDS")
+            .ok()?
+            .split(',')
+            .map(|tld| tld.trim().trim_matches('.').to_lowercase())
+            .filter(|tld| !tld.is_empty())
+            .collect();
+        if tlds.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            host,
+            port: std::env::var("DOMAIN_MCP_EPP_PORT")
+                .ok()
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(700),
+            username,
+            password,
+            client_cert: std::env::var("DOMAIN_MCP_EPP_CLIENT_CERT").ok(),
+            client_key: std::env::var("DOMAIN_MCP_EPP_CLIENT_KEY").ok(),
+            currency: std::env::var("DOMAIN_MCP_EPP_CURRENCY")
+                .unwrap_or_else(|_| "USD".to_string()),
+            timeout: Duration::from_secs(15),
+            batch_size: std::env::var("DOMAIN_MCP_EPP_CHECK_BATCH")
+                .ok()
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(5)
+                .max(1),
+            tlds,
+        })
+    }
+
+    /// Whether `domain` is under one of the registry's TLDs.
+    pub fn covers(&self, domain: &str) -> bool {
+        let domain = domain.trim_end_matches('.').to_lowercase();
+        self.tlds.iter().any(|tld| {
+            domain
+                .strip_suffix(tld.as_str())
+                .and_then(|rest| rest.strip_suffix('.'))
+                .is_some_and(|label| !label.is_empty())
+        })
+    }
+}
+
+/// EPP (RFC 5730/5731) `<domain:check>` client. Premium names are detected
+/// through the fee extension (RFC 8748) when the registry supports it.
+///
+/// Registries cap concurrent sessions and rate-limit logins, so one session
+/// is logged in and kept open, and it is used by one command at a time.
+/// Checks that arrive while a command is in flight are queued and sent
+/// together, up to `batch_size` names per `<domain:check>`.
+pub struct EppProvider {
+    inner: Arc<EppSession>,
+}
+
+type EppStream = rustls::StreamOwned<rustls::ClientConnection, TcpStream>;
+type PendingCheck = (String, oneshot::Sender<Result<RegistryCheck>>);
+
+struct EppSession {
+    config: EppConfig,
+    stream: tokio::sync::Mutex<Option<EppStream>>,
+    pending: Mutex<Vec<PendingCheck>>,
+}
+
+impl EppProvider {
+    pub fn new(config: EppConfig) -> Self {
+        Self {
+            inner: Arc::new(EppSession {
+                config,
+                stream: tokio::sync::Mutex::new(None),
+                pending: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl AvailabilityProvider for EppProvider {
+    fn name(&self) -> &str {
+        "epp"
+    }
+
+    fn covers(&self, domain: &str) -> bool {
+        self.inner.config.covers(domain)
+    }
+
+    async fn check(&self, domain: &str) -> Result<RegistryCheck> {
+        let (sender, receiver) = oneshot::channel();
+        self.inner
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((domain.to_lowercase(), sender));
+        // Sent from a task of its own so a caller that gives up does not
+        // drop the session in the middle of a command.
+        tokio::spawn(self.inner.clone().send_pending());
+
+        receiver
+            .await
+            .map_err(|_| anyhow::anyhow!("EPP check for {} was not answered", domain))?
+    }
+}
+
+impl EppSession {
+    /// Send queued checks in batches until the queue is empty.
+    async fn send_pending(self: Arc<Self>) {
+        let mut stream = self.stream.lock().await;
+        loop {
+            let batch: Vec<PendingCheck> = {
+                let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+                let size = pending.len().min(self.config.batch_size);
+                pending.drain(..size).collect()
+            };
+            if batch.is_empty() {
+                break;
+            }
+
+            let domains: Vec<String> = batch.iter().map(|(domain, _)| domain.clone()).collect();
+            let config = self.config.clone();
+            let current = stream.take();
+            let response = match tokio::task::spawn_blocking(move || {
+                check_batch(current, &domains, &config.currency, || {
+                    open_session(&config)
+                })
+            })
+            .await
+            {
+                Ok((kept, response)) => {
+                    *stream = kept;
+                    response
+                }
+                Err(e) => Err(anyhow::anyhow!("EPP check task failed: {}", e)),
+            };
+
+            for (domain, sender) in batch {
+                let result = match &response {
+                    Ok(response) => parse_check_response(response, &domain),
+                    Err(e) => Err(anyhow::anyhow!("{:#}", e)),
+                };
+                let _ = sender.send(result);
+            }
+        }
+    }
+}
+
+/// Connect, read the greeting and log in.
+fn open_session(config: &EppConfig) -> Result<EppStream> {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+    use std::net::ToSocketAddrs;
+
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let mut root_store = rustls::RootCertStore::empty();
+    root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+    let builder = rustls::ClientConfig::builder().with_root_certificates(root_store);
+    let tls_config = match (&config.client_cert, &config.client_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let certs = CertificateDer::pem_file_iter(cert_path)?.collect::<Result<Vec<_>, _>>()?;
+            let key = PrivateKeyDer::from_pem_file(key_path)?;
+            builder.with_client_auth_cert(certs, key)?
+        }
+        _ => builder.with_no_client_auth(),
+    };
+
+    let addr = (config.host.as_str(), config.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Could not resolve EPP host: {}", config.host))?;
+    let tcp_stream = TcpStream::connect_timeout(&addr, config.timeout)?;
+    tcp_stream.set_read_timeout(Some(config.timeout))?;
+    tcp_stream.set_write_timeout(Some(config.timeout))?;
+
+    let server_name = ServerName::try_from(config.host.clone())?;
+    let conn = rustls::ClientConnection::new(Arc::new(tls_config), server_name)?;
+    let mut stream = rustls::StreamOwned::new(conn, tcp_stream);
+
+    login(&mut stream, config)?;
+    Ok(stream)
+}
+
+fn login<S: Read + Write>(stream: &mut S, config: &EppConfig) -> Result<()> {
+    // The server sends its <greeting> as soon as the session is established.
+    read_epp_frame(stream)?;
+
+    write_epp_frame(stream, &login_command(&config.username, &config.password))?;
+    ensure_epp_success(&read_epp_frame(stream)?)
+}
+
+/// Send one `<domain:check>` for `domains` on `session`, opening a session
+/// first if there is none. Returns the session to keep, if it is still
+/// usable, and the response.
+fn check_batch<S: Read + Write>(
+    mut session: Option<S>,
+    domains: &[String],
+    currency: &str,
+    mut open: impl FnMut() -> Result<S>,
+) -> (Option<S>, Result<String>) {
+    let command = check_command(domains, currency);
+    loop {
+        let reused = session.is_some();
+        let mut stream = match session.take() {
+            Some(stream) => stream,
+            None => match open() {
+                Ok(stream) => stream,
+                Err(e) => return (None, Err(e)),
+            },
+        };
+
+        let response =
+            write_epp_frame(&mut stream, &command).and_then(|_| read_epp_frame(&mut stream));
+        match response {
+            Ok(response) => {
+                // 2500-2502 mean the server has ended the session.
+                let ended = response.contains(r#"code="250"#);
+                let result = ensure_epp_success(&response).map(|_| response);
+                return ((!ended).then_some(stream), result);
+            }
+            // The registry may have closed an idle session; log in again once.
+            Err(_) if reused => continue,
+            Err(e) => return (None, Err(e)),
+        }
+    }
+}
+
+/// RFC 5734: every EPP data unit is prefixed with its total length, including
+/// the 4-byte header itself, as a big-endian u32.
+fn write_epp_frame<W: Write>(stream: &mut W, xml: &str) -> Result<()> {
+    let length = u32::try_from(xml.len() + 4)?;
+    stream.write_all(&length.to_be_bytes())?;
+    stream.write_all(xml.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_epp_frame<R: Read>(stream: &mut R) -> Result<String> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header)?;
+
+    let length = u32::from_be_bytes(header) as usize;
+    if !(4..=1024 * 1024).contains(&length) {
+        return Err(anyhow::anyhow!("Invalid EPP frame length: {}", length));
+    }
+
+    let mut body = vec![0u8; length - 4];
+    stream.read_exact(&mut body)?;
+    Ok(String::from_utf8_lossy(&body).to_string())
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn login_command(username: &str, password: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<epp xmlns="urn:ietf:params:xml:ns:epp-1.0">
+  <command>
+    <login>
+      <clID>{}</clID>
+      <pw>{}</pw>
+      <options>
+        <version>1.0</version>
+        <lang>en</lang>
+      </options>
+      <svcs>
+        <objURI>urn:ietf:params:xml:ns:domain-1.0</objURI>
+        <svcExtension>
+          <extURI>urn:ietf:params:xml:ns:epp:fee-1.0</extURI>
+        </svcExtension>
+      </svcs>
+    </login>
+    <clTRID>domain-mcp-login</clTRID>
+  </command>
+</epp>"#,
+        xml_escape(username),
+        xml_escape(password)
+    )
+}
+
+fn check_command(domains: &[String], currency: &str) -> String {
+    let names: String = domains
+        .iter()
+        .map(|domain| {
+            format!(
+                "\n        <domain:name>{}</domain:name>",
+                xml_escape(domain)
+            )
+        })
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<epp xmlns="urn:ietf:params:xml:ns:epp-1.0">
+  <command>
+    <check>
+      <domain:check xmlns:domain="urn:ietf:params:xml:ns:domain-1.0">{}
+      </domain:check>
+    </check>
+    <extension>
+      <fee:check xmlns:fee="urn:ietf:params:xml:ns:epp:fee-1.0">
+        <fee:currency>{}</fee:currency>
+        <fee:command name="create">
+          <fee:period unit="y">1</fee:period>
+        </fee:command>
+      </fee:check>
+    </extension>
+    <clTRID>domain-mcp-check</clTRID>
+  </command>
+</epp>"#,
+        names,
+        xml_escape(currency)
+    )
+}
+
+fn ensure_epp_success(response: &str) -> Result<()> {
+    let re = Regex::new(r#"<(?:\w+:)?result\s+code="(\d{4})""#)?;
+    let code = re
+        .captures(response)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str())
+        .ok_or_else(|| anyhow::anyhow!("EPP response has no result code"))?;
+
+    // 1xxx codes are success, 2xxx are errors (RFC 5730 section 3).
+    if code.starts_with('1') {
+        Ok(())
+    } else {
+        let message = Regex::new(r"<(?:\w+:)?msg[^>]*>([^<]*)<")?
+            .captures(response)
+            .and_then(|caps| caps.get(1))
+            .map(|m| m.as_str().trim().to_string())
+            .unwrap_or_default();
+        Err(anyhow::anyhow!(
+            "EPP command failed with code {}: {}",
+            code,
+            message
+        ))
+    }
+}
+
+/// Read the result for `domain` from a check response, which may cover
+/// several names, each in its own `<domain:cd>` and `<fee:cd>` element.
+fn parse_check_response(response: &str, domain: &str) -> Result<RegistryCheck> {
+    let name_re = Regex::new(r#"<(?:\w+:)?name[^>]*\bavail="(\w+)"[^>]*>([^<]+)<"#)?;
+    let object_re = Regex::new(r"<(?:\w+:)?objID[^>]*>([^<]+)<")?;
+    let mut domain_cd = None;
+    let mut fee_cd = None;
+    for cd in Regex::new(r"(?s)<(?:\w+:)?cd\b[^>]*>.*?</(?:\w+:)?cd>")?.find_iter(response) {
+        let cd = cd.as_str();
+        if let Some(caps) = name_re.captures(cd) {
+            if caps[2].trim().eq_ignore_ascii_case(domain) {
+                domain_cd = Some((cd, caps.get(1).map_or("", |m| m.as_str())));
+            }
+        } else if object_re
+            .captures(cd)
+            .is_some_and(|caps| caps[1].trim().eq_ignore_ascii_case(domain))
+        {
+            fee_cd = Some(cd);
+        }
+    }
+    let (domain_cd, avail) = domain_cd
+        .ok_or_else(|| anyhow::anyhow!("EPP check response does not mention {}", domain))?;
+    let name = name_re
+        .captures(domain_cd)
+        .map(|caps| caps[2].trim().to_string())
+        .unwrap_or_else(|| domain.to_string());
+
+    let available = matches!(avail, "1" | "true");
+    let reason = Regex::new(r"<(?:\w+:)?reason[^>]*>([^<]*)<")?
+        .captures(domain_cd)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().trim().to_string());
+
+    let fee_cd = fee_cd.unwrap_or_default();
+    let fee_class = Regex::new(r"<(?:\w+:)?class>([^<]*)<")?
+        .captures(fee_cd)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().trim().to_lowercase());
+    let price = Regex::new(r"<(?:\w+:)?fee(?:\s[^>]*)?>([\d.]+)<")?
+        .captures(fee_cd)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string());
+    let currency = Regex::new(r"<(?:\w+:)?currency>([^<]*)<")?
+        .captures(response)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().trim().to_string());
+
+    let reserved = reason
+        .as_deref()
+        .is_some_and(|reason| reason.to_lowercase().contains("reserved"));
+
+    let status = if available && fee_class.as_deref() == Some("premium") {
+        RegistryStatus::Premium
+    } else if available {
+        RegistryStatus::Available
+    } else if reserved {
+        RegistryStatus::Reserved
+    } else {
+        RegistryStatus::Registered
+    };
+
+    Ok(RegistryCheck {
+        domain: name.to_lowercase(),
+        status,
+        reason: reason.or(fee_class),
+        price: if status == RegistryStatus::Premium {
+            price
+        } else {
+            None
+        },
+        currency: if status == RegistryStatus::Premium {
+            currency
+        } else {
+            None
+        },
+    })
+}
+
+const NAMECHEAP_ENDPOINT: &str = "https://api.namecheap.com/xml.response";
+const NAMECHEAP_SANDBOX_ENDPOINT: &str = "https://api.sandbox.namecheap.com/xml.response";
+
+#[derive(Debug, Clone)]
+pub struct NamecheapConfig {
+    pub api_user: String,
+    pub api_key: String,
+    /// Account the API acts for; usually the API user.
+    pub username: String,
+    /// The server's public IP, which must be whitelisted for the API key.
+    pub client_ip: String,
+    pub endpoint: String,
+}
+
+impl NamecheapConfig {
+    pub fn from_env() -> Option<Self> {
+        let api_user = std::env::var("DOMAIN_MCP_NAMECHEAP_API_USER").ok()?;
+        let api_key = std::env::var("DOMAIN_MCP_NAMECHEAP_API_KEY").ok()?;
+        let client_ip = std::env::var("DOMAIN_MCP_NAMECHEAP_CLIENT_IP").ok()?;
+        let sandbox = std::env::var("DOMAIN_MCP_NAMECHEAP_SANDBOX")
+            .is_ok_and(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"));
+
+        Some(Self {
+            username: std::env::var("DOMAIN_MCP_NAMECHEAP_USERNAME")
+                .unwrap_or_else(|_| api_user.clone()),
+            api_user,
+            api_key,
+            client_ip,
+            endpoint: if sandbox {
+                NAMECHEAP_SANDBOX_ENDPOINT
+            } else {
+                NAMECHEAP_ENDPOINT
+            }
+            .to_string(),
+        })
+    }
+}
+
+/// Registrar availability through the Namecheap API's `namecheap.domains.check`,
+/// which flags premium names with their registration price.
+pub struct NamecheapProvider {
+    config: NamecheapConfig,
+    client: Client,
+}
+
+impl NamecheapProvider {
+    pub fn new(config: NamecheapConfig) -> Self {
+        Self {
+            config,
+            client: Client::builder()
+                .user_agent("Domain-MCP-Rust/1.0")
+                .timeout(Duration::from_secs(15))
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl AvailabilityProvider for NamecheapProvider {
+    fn name(&self) -> &str {
+        "namecheap"
+    }
+
+    async fn check(&self, domain: &str) -> Result<RegistryCheck> {
+        let request = self
+            .client
+            .get(&self.config.endpoint)
+            .query(&[
+                ("ApiUser", self.config.api_user.as_str()),
+                ("ApiKey", self.config.api_key.as_str()),
+                ("UserName", self.config.username.as_str()),
+                ("ClientIp", self.config.client_ip.as_str()),
+                ("Command", "namecheap.domains.check"),
+                ("DomainList", domain),
+            ])
+            .build()?;
+        let response = Scheduler::global()
+            .execute(&self.client, request)
+            .await
+            .map_err(without_url)?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Namecheap API returned status: {}",
+                response.status()
+            ));
+        }
+
+        let text = response.text().await.map_err(|e| without_url(e.into()))?;
+        parse_namecheap_response(&text, domain)
+    }
+}
+
+/// Drop the request URL from a failed request's error: the Namecheap API key
+/// is in the query string, and errors end up in tool results.
+fn without_url(e: anyhow::Error) -> anyhow::Error {
+    match e.downcast::<reqwest::Error>() {
+        Ok(e) => e.without_url().into(),
+        Err(e) => e,
+    }
+}
+
+fn parse_namecheap_response(response: &str, domain: &str) -> Result<RegistryCheck> {
+    let attribute = |element: &str, name: &str| {
+        Regex::new(&format!(r#"\b{}="([^"]*)""#, name))
+            .ok()?
+            .captures(element)
+            .map(|caps| caps[1].to_string())
+    };
+
+    if attribute(response, "Status").as_deref() == Some("ERROR") {
+        let error = Regex::new(r"<Error\b[^>]*>([^<]*)<")?
+            .captures(response)
+            .map(|caps| caps[1].trim().to_string())
+            .unwrap_or_default();
+        return Err(anyhow::anyhow!("Namecheap API error: {}", error));
+    }
+
+    let result = Regex::new(r"<DomainCheckResult\b[^>]*>")?
+        .find_iter(response)
+        .map(|m| m.as_str())
+        .find(|element| {
+            attribute(element, "Domain").is_some_and(|name| name.eq_ignore_ascii_case(domain))
+        })
+        .ok_or_else(|| anyhow::anyhow!("Namecheap response does not mention {}", domain))?;
+
+    let flag = |name: &str| attribute(result, name).is_some_and(|value| value == "true");
+    let description = attribute(result, "Description").filter(|value| !value.is_empty());
+    if attribute(result, "ErrorNo").is_some_and(|code| code != "0") {
+        return Err(anyhow::anyhow!(
+            "Namecheap cannot check {}: {}",
+            domain,
+            description.unwrap_or_default()
+        ));
+    }
+
+    let status = if flag("Available") && flag("IsPremiumName") {
+        RegistryStatus::Premium
+    } else if flag("Available") {
+        RegistryStatus::Available
+    } else if description
+        .as_deref()
+        .is_some_and(|description| description.to_lowercase().contains("reserved"))
+    {
+        RegistryStatus::Reserved
+    } else {
+        RegistryStatus::Registered
+    };
+    let premium = status == RegistryStatus::Premium;
+
+    Ok(RegistryCheck {
+        domain: domain.to_lowercase(),
+        status,
+        reason: if premium {
+            Some("premium".to_string())
+        } else {
+            description
+        },
+        price: attribute(result, "PremiumRegistrationPrice").filter(|_| premium),
+        // Namecheap quotes prices in US dollars.
+        currency: premium.then(|| "USD".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const GREETING: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<epp xmlns="urn:ietf:params:xml:ns:epp-1.0"><greeting><svID>Example EPP server</svID></greeting></epp>"#;
+
+    const LOGIN_OK: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<epp xmlns="urn:ietf:params:xml:ns:epp-1.0"><response><result code="1000"><msg>Command completed successfully</msg></result></response></epp>"#;
+
+    const CHECK_PREMIUM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<epp xmlns="urn:ietf:params:xml:ns:epp-1.0">
+  <response>
+    <result code="1000"><msg>Command completed successfully</msg></result>
+    <resData>
+      <domain:chkData xmlns:domain="urn:ietf:params:xml:ns:domain-1.0">
+        <domain:cd><domain:name avail="1">shop.example</domain:name></domain:cd>
+      </domain:chkData>
+    </resData>
+    <extension>
+      <fee:chkData xmlns:fee="urn:ietf:params:xml:ns:epp:fee-1.0">
+        <fee:currency>USD</fee:currency>
+        <fee:cd avail="1">
+          <fee:objID>shop.example</fee:objID>
+          <fee:class>premium</fee:class>
+          <fee:command name="create">
+            <fee:period unit="y">1</fee:period>
+            <fee:fee description="Registration Fee" refundable="1">2500.00</fee:fee>
+          </fee:command>
+        </fee:cd>
+      </fee:chkData>
+    </extension>
+  </response>
+</epp>"#;
+
+    const CHECK_RESERVED: &str = r#"<epp xmlns="urn:ietf:params:xml:ns:epp-1.0"><response>
+<result code="1000"><msg>Command completed successfully</msg></result>
+<resData><domain:chkData xmlns:domain="urn:ietf:params:xml:ns:domain-1.0">
+<domain:cd><domain:name avail="0">nic.example</domain:name><domain:reason>Reserved by registry policy</domain:reason></domain:cd>
+</domain:chkData></resData></response></epp>"#;
+
+    const CHECK_TAKEN: &str = r#"<epp xmlns="urn:ietf:params:xml:ns:epp-1.0"><response>
+<result code="1000"><msg>Command completed successfully</msg></result>
+<resData><domain:chkData xmlns:domain="urn:ietf:params:xml:ns:domain-1.0">
+<domain:cd><domain:name avail="0">taken.example</domain:name><domain:reason>In use</domain:reason></domain:cd>
+</domain:chkData></resData></response></epp>"#;
+
+    fn frame(xml: &str) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        write_epp_frame(&mut buffer, xml).unwrap();
+        buffer
+    }
+
+    /// Transport that replays canned server frames and records what the
+    /// client writes.
+    struct ScriptedStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for ScriptedStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for ScriptedStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn test_config() -> EppConfig {
+        EppConfig {
+            host: "epp.example".to_string(),
+            port: 700,
+            username: "registrar-1".to_string(),
+            password: "s3cr<t".to_string(),
+            client_cert: None,
+            client_key: None,
+            currency: "USD".to_string(),
+            timeout: Duration::from_secs(5),
+            batch_size: 5,
+            tlds: vec!["com".to_string(), "co.uk".to_string()],
+        }
+    }
+
+    #[test]
+    fn epp_covers_only_its_tlds_test() {
+        let config = test_config();
+        assert!(config.covers("shop.com"));
+        assert!(config.covers("Shop.CO.UK."));
+        assert!(!config.covers("shop.io"));
+        assert!(!config.covers("shop.uk"));
+        assert!(!config.covers("notcom"));
+        assert!(!config.covers("com"));
+    }
+
+    #[test]
+    fn epp_frame_roundtrip_test() {
+        let encoded = frame("<epp/>");
+        assert_eq!(&encoded[..4], &10u32.to_be_bytes());
+
+        let decoded = read_epp_frame(&mut Cursor::new(encoded)).unwrap();
+        assert_eq!(decoded, "<epp/>");
+
+        let bad_length = 2u32.to_be_bytes().to_vec();
+        assert!(read_epp_frame(&mut Cursor::new(bad_length)).is_err());
+    }
+
+    #[test]
+    fn parse_check_response_test() {
+        let premium = parse_check_response(CHECK_PREMIUM, "shop.example").unwrap();
+        assert_eq!(premium.status, RegistryStatus::Premium);
+        assert_eq!(premium.price.as_deref(), Some("2500.00"));
+        assert_eq!(premium.currency.as_deref(), Some("USD"));
+
+        let reserved = parse_check_response(CHECK_RESERVED, "nic.example").unwrap();
+        assert_eq!(reserved.status, RegistryStatus::Reserved);
+
+        let taken = parse_check_response(CHECK_TAKEN, "taken.example").unwrap();
+        assert_eq!(taken.status, RegistryStatus::Registered);
+        assert_eq!(taken.reason.as_deref(), Some("In use"));
+
+        assert!(parse_check_response(CHECK_TAKEN, "other.example").is_err());
+    }
+
+    #[test]
+    fn ensure_epp_success_test() {
+        assert!(ensure_epp_success(LOGIN_OK).is_ok());
+
+        let failure = r#"<epp><response><result code="2200"><msg>Authentication error</msg></result></response></epp>"#;
+        let error = ensure_epp_success(failure).unwrap_err().to_string();
+        assert!(error.contains("2200"));
+        assert!(error.contains("Authentication error"));
+    }
+
+    const CHECK_BATCH: &str = r#"<epp xmlns="urn:ietf:params:xml:ns:epp-1.0"><response>
+<result code="1000"><msg>Command completed successfully</msg></result>
+<resData><domain:chkData xmlns:domain="urn:ietf:params:xml:ns:domain-1.0">
+<domain:cd><domain:name avail="0">taken.example</domain:name><domain:reason>In use</domain:reason></domain:cd>
+<domain:cd><domain:name avail="1">shop.example</domain:name></domain:cd>
+<domain:cd><domain:name avail="1">plain.example</domain:name></domain:cd>
+</domain:chkData></resData>
+<extension><fee:chkData xmlns:fee="urn:ietf:params:xml:ns:epp:fee-1.0">
+<fee:currency>USD</fee:currency>
+<fee:cd avail="1"><fee:objID>plain.example</fee:objID><fee:class>standard</fee:class>
+<fee:command name="create"><fee:fee>9.00</fee:fee></fee:command></fee:cd>
+<fee:cd avail="1"><fee:objID>shop.example</fee:objID><fee:class>premium</fee:class>
+<fee:command name="create"><fee:fee>2500.00</fee:fee></fee:command></fee:cd>
+</fee:chkData></extension></response></epp>"#;
+
+    fn scripted(frames: &[&str]) -> ScriptedStream {
+        ScriptedStream {
+            input: Cursor::new(frames.iter().flat_map(|xml| frame(xml)).collect()),
+            output: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parse_batched_check_response_test() {
+        let taken = parse_check_response(CHECK_BATCH, "taken.example").unwrap();
+        assert_eq!(taken.status, RegistryStatus::Registered);
+        assert_eq!(taken.reason.as_deref(), Some("In use"));
+
+        let premium = parse_check_response(CHECK_BATCH, "shop.example").unwrap();
+        assert_eq!(premium.status, RegistryStatus::Premium);
+        assert_eq!(premium.price.as_deref(), Some("2500.00"));
+
+        let plain = parse_check_response(CHECK_BATCH, "plain.example").unwrap();
+        assert_eq!(plain.status, RegistryStatus::Available);
+        assert_eq!(plain.price, None);
+    }
+
+    #[test]
+    fn login_test() {
+        let mut stream = scripted(&[GREETING, LOGIN_OK]);
+        login(&mut stream, &test_config()).unwrap();
+
+        let sent = String::from_utf8_lossy(&stream.output);
+        assert!(sent.contains("<clID>registrar-1</clID>"));
+        assert!(sent.contains("<pw>s3cr&lt;t</pw>"));
+    }
+
+    #[test]
+    fn check_batch_reuses_one_session_test() {
+        let domains = vec!["shop.example".to_string(), "taken.example".to_string()];
+        let logins = std::cell::Cell::new(0);
+        let mut open = || {
+            logins.set(logins.get() + 1);
+            let mut stream = scripted(&[GREETING, LOGIN_OK, CHECK_BATCH, CHECK_BATCH]);
+            login(&mut stream, &test_config()).map(|_| stream)
+        };
+
+        let (session, response) = check_batch(None, &domains, "USD", &mut open);
+        assert!(response.is_ok());
+        let (session, response) = check_batch(session, &domains, "USD", &mut open);
+        assert!(response.is_ok());
+        assert_eq!(logins.get(), 1);
+
+        // Both names go in one <domain:check>, and there is no logout.
+        let sent = String::from_utf8_lossy(&session.as_ref().unwrap().output).to_string();
+        assert_eq!(sent.matches("<domain:check ").count(), 2);
+        assert!(sent.contains(
+            "<domain:name>shop.example</domain:name>\n        <domain:name>taken.example</domain:name>"
+        ));
+        assert!(!sent.contains("<logout/>"));
+
+        // A session the server has closed is replaced by a new login.
+        let (session, response) = check_batch(session, &domains, "USD", &mut open);
+        assert!(response.is_ok());
+        assert!(session.is_some());
+        assert_eq!(logins.get(), 2);
+    }
+
+    #[test]
+    fn parse_namecheap_response_test() {
+        let response = r#"<?xml version="1.0" encoding="utf-8"?>
+<ApiResponse Status="OK" xmlns="http://api.namecheap.com/xml.response">
+  <Errors />
+  <CommandResponse Type="namecheap.domains.check">
+    <DomainCheckResult Domain="shop.com" Available="true" ErrorNo="0" Description="" IsPremiumName="true" PremiumRegistrationPrice="2500.0000" />
+    <DomainCheckResult Domain="taken.com" Available="false" ErrorNo="0" Description="" IsPremiumName="false" PremiumRegistrationPrice="0" />
+    <DomainCheckResult Domain="free.com" Available="true" ErrorNo="0" Description="" IsPremiumName="false" PremiumRegistrationPrice="0" />
+  </CommandResponse>
+</ApiResponse>"#;
+
+        let premium = parse_namecheap_response(response, "shop.com").unwrap();
+        assert_eq!(premium.status, RegistryStatus::Premium);
+        assert_eq!(premium.price.as_deref(), Some("2500.0000"));
+        assert_eq!(premium.currency.as_deref(), Some("USD"));
+
+        let taken = parse_namecheap_response(response, "taken.com").unwrap();
+        assert_eq!(taken.status, RegistryStatus::Registered);
+        assert_eq!(taken.price, None);
+
+        let free = parse_namecheap_response(response, "FREE.com").unwrap();
+        assert_eq!(free.status, RegistryStatus::Available);
+        assert!(parse_namecheap_response(response, "other.com").is_err());
+
+        let error = r#"<ApiResponse Status="ERROR"><Errors><Error Number="1011102">Parameter APIKey is invalid</Error></Errors></ApiResponse>"#;
+        let error = parse_namecheap_response(error, "shop.com").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Namecheap API error: Parameter APIKey is invalid"
+        );
+    }
+
+    #[tokio::test]
+    async fn namecheap_errors_do_not_carry_the_api_key_test() {
+        // Accepts connections and hangs up without answering.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                drop(socket);
+            }
+        });
+
+        let provider = NamecheapProvider::new(NamecheapConfig {
+            api_user: "user".to_string(),
+            api_key: "s3cret-api-key".to_string(),
+            username: "user".to_string(),
+            client_ip: "127.0.0.1".to_string(),
+            endpoint: format!("http://{}/xml.response", addr),
+        });
+        let error = provider.check("shop.com").await.unwrap_err();
+        let error = format!("{:#}", error);
+        assert!(!error.contains("s3cret-api-key"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn mock_provider_test() {
+        let provider = MockProvider::new()
+            .with_status("taken.example", RegistryStatus::Registered)
+            .with_premium("shop.example", "2500.00", "USD");
+
+        let taken = provider.check("TAKEN.example").await.unwrap();
+        assert_eq!(taken.status, RegistryStatus::Registered);
+
+        let premium = provider.check("shop.example").await.unwrap();
+        assert_eq!(premium.status, RegistryStatus::Premium);
+        assert_eq!(premium.price.as_deref(), Some("2500.00"));
+
+        assert!(provider.check("unknown.example").await.is_err());
+
+        let provider = provider.with_default(RegistryStatus::Available);
+        let unknown = provider.check("unknown.example").await.unwrap();
+        assert_eq!(unknown.status, RegistryStatus::Available);
+    }
+}
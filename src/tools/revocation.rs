@@ -0,0 +1,742 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use reqwest::Client;
+use ring::digest::{digest, SHA1_FOR_LEGACY_USE_ONLY};
+use rustls::pki_types::CertificateDer;
+use serde::{Deserialize, Serialize};
+use x509_parser::der_parser::asn1_rs::{Any, BitString, Class};
+use x509_parser::extensions::{DistributionPointName, GeneralName, ParsedExtension};
+use x509_parser::prelude::{FromDer, SubjectPublicKeyInfo, X509Certificate};
+use x509_parser::revocation_list::CertificateRevocationList;
+use x509_parser::verify::verify_signature;
+use x509_parser::x509::AlgorithmIdentifier;
+
+use super::scheduler::Scheduler;
+
+/// id-pkix-ocsp-basic (1.3.6.1.5.5.7.48.1.1), DER contents of the OID.
+const OCSP_BASIC_RESPONSE: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+
+/// id-sha1 AlgorithmIdentifier with NULL parameters, as used in CertID.
+const SHA1_ALGORITHM: &[u8] = &[
+    0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00,
+];
+
+/// Clock skew tolerated on `thisUpdate`.
+const MAX_CLOCK_SKEW_MINUTES: i64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationStatus {
+    Good,
+    Revoked,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationSource {
+    StapledOcsp,
+    Ocsp,
+    Crl,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationInfo {
+    pub status: RevocationStatus,
+    /// Where the answer came from; absent when the status is unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<RevocationSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// CRLReason such as "keyCompromise", when the issuer gave one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Sources that were tried and could not give an answer.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// Fetches OCSP responses and CRLs, so tests can stand in for the issuer's
+/// responders.
+#[async_trait]
+pub trait RevocationFetcher: Send + Sync {
+    /// POST a DER OCSP request to `url` and return the DER response.
+    async fn ocsp(&self, url: &str, request: &[u8]) -> Result<Vec<u8>>;
+
+    /// Download the DER CRL at `url`.
+    async fn crl(&self, url: &str) -> Result<Vec<u8>>;
+}
+
+/// Fetches over HTTP through the shared request scheduler.
+pub struct HttpFetcher {
+    client: Client,
+}
+
+impl HttpFetcher {
+    pub fn new() -> Self {
+        HttpFetcher {
+            client: Client::builder()
+                .user_agent("Domain-MCP-Rust/1.0")
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+        }
+    }
+
+    async fn fetch(&self, request: reqwest::RequestBuilder) -> Result<Vec<u8>> {
+        let response = Scheduler::global()
+            .execute(&self.client, request.build()?)
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("HTTP {}", response.status()));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+impl Default for HttpFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RevocationFetcher for HttpFetcher {
+    async fn ocsp(&self, url: &str, request: &[u8]) -> Result<Vec<u8>> {
+        self.fetch(
+            self.client
+                .post(url)
+                .header("Content-Type", "application/ocsp-request")
+                .body(request.to_vec()),
+        )
+        .await
+    }
+
+    async fn crl(&self, url: &str) -> Result<Vec<u8>> {
+        self.fetch(self.client.get(url)).await
+    }
+}
+
+/// A definitive answer from one source.
+struct Verdict {
+    status: RevocationStatus,
+    revoked_at: Option<DateTime<Utc>>,
+    reason: Option<String>,
+}
+
+/// Work out whether the leaf of `chain` has been revoked: the stapled OCSP
+/// response first, then the OCSP responders from the AIA extension, then the
+/// CRL distribution points. The issuer must be among the presented
+/// certificates, since its key is needed to check the answers.
+pub async fn check(
+    chain: &[CertificateDer<'_>],
+    stapled_ocsp: Option<&[u8]>,
+    fetcher: &dyn RevocationFetcher,
+    now: DateTime<Utc>,
+) -> RevocationInfo {
+    let mut errors = Vec::new();
+    let unknown = |errors| RevocationInfo {
+        status: RevocationStatus::Unknown,
+        source: None,
+        revoked_at: None,
+        reason: None,
+        errors,
+    };
+
+    let Some(leaf) = chain
+        .first()
+        .and_then(|der| X509Certificate::from_der(der).ok())
+        .map(|(_, cert)| cert)
+    else {
+        return unknown(vec!["Leaf certificate could not be parsed".to_string()]);
+    };
+    let Some(issuer) = find_issuer(&leaf, &chain[1..]) else {
+        return unknown(vec!["Issuer certificate was not presented".to_string()]);
+    };
+
+    if let Some(response) = stapled_ocsp {
+        match evaluate_ocsp(response, &leaf, &issuer, now) {
+            Ok(verdict) if verdict.status != RevocationStatus::Unknown => {
+                return answer(verdict, RevocationSource::StapledOcsp, errors)
+            }
+            Ok(_) => errors.push(
+                "Stapled OCSP response: responder does not know this certificate".to_string(),
+            ),
+            Err(e) => errors.push(format!("Stapled OCSP response: {}", e)),
+        }
+    }
+
+    let (ocsp_urls, crl_urls) = revocation_urls(&leaf);
+    if ocsp_urls.is_empty() && crl_urls.is_empty() {
+        errors.push("Certificate names no OCSP responder or CRL distribution point".to_string());
+    }
+
+    if !ocsp_urls.is_empty() {
+        let request = ocsp_request(&leaf, &issuer);
+        for url in &ocsp_urls {
+            let result = match fetcher.ocsp(url, &request).await {
+                Ok(response) => evaluate_ocsp(&response, &leaf, &issuer, now),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(verdict) if verdict.status != RevocationStatus::Unknown => {
+                    return answer(verdict, RevocationSource::Ocsp, errors)
+                }
+                Ok(_) => errors.push(format!(
+                    "OCSP {}: responder does not know this certificate",
+                    url
+                )),
+                Err(e) => errors.push(format!("OCSP {}: {}", url, e)),
+            }
+        }
+    }
+
+    for url in &crl_urls {
+        let result = match fetcher.crl(url).await {
+            Ok(crl) => evaluate_crl(&crl, &leaf, &issuer, now),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(verdict) => return answer(verdict, RevocationSource::Crl, errors),
+            Err(e) => errors.push(format!("CRL {}: {}", url, e)),
+        }
+    }
+
+    unknown(errors)
+}
+
+fn answer(verdict: Verdict, source: RevocationSource, errors: Vec<String>) -> RevocationInfo {
+    RevocationInfo {
+        status: verdict.status,
+        source: Some(source),
+        revoked_at: verdict.revoked_at,
+        reason: verdict.reason,
+        errors,
+    }
+}
+
+fn find_issuer<'a>(
+    leaf: &X509Certificate,
+    candidates: &'a [CertificateDer<'_>],
+) -> Option<X509Certificate<'a>> {
+    candidates
+        .iter()
+        .filter_map(|der| X509Certificate::from_der(der).ok())
+        .map(|(_, cert)| cert)
+        .find(|cert| {
+            cert.subject().as_raw() == leaf.issuer().as_raw()
+                && leaf.verify_signature(Some(cert.public_key())).is_ok()
+        })
+}
+
+/// The http(s) OCSP responder and CRL URLs named in the certificate.
+fn revocation_urls(cert: &X509Certificate) -> (Vec<String>, Vec<String>) {
+    let mut ocsp_urls = Vec::new();
+    let mut crl_urls = Vec::new();
+
+    for extension in cert.extensions() {
+        match extension.parsed_extension() {
+            ParsedExtension::AuthorityInfoAccess(aia) => {
+                for description in aia.iter() {
+                    if description.access_method.to_id_string() == "1.3.6.1.5.5.7.48.1" {
+                        if let GeneralName::URI(uri) = description.access_location {
+                            ocsp_urls.push(uri.to_string());
+                        }
+                    }
+                }
+            }
+            ParsedExtension::CRLDistributionPoints(points) => {
+                for point in points.iter() {
+                    if let Some(DistributionPointName::FullName(names)) = &point.distribution_point
+                    {
+                        for name in names {
+                            if let GeneralName::URI(uri) = name {
+                                crl_urls.push(uri.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let is_http = |url: &String| url.starts_with("http://") || url.starts_with("https://");
+    ocsp_urls.retain(is_http);
+    crl_urls.retain(is_http);
+    (ocsp_urls, crl_urls)
+}
+
+/// DER OCSPRequest for one certificate, with a SHA-1 CertID and no nonce.
+fn ocsp_request(cert: &X509Certificate, issuer: &X509Certificate) -> Vec<u8> {
+    let name_hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, cert.issuer().as_raw());
+    let key_hash = digest(
+        &SHA1_FOR_LEGACY_USE_ONLY,
+        &issuer.public_key().subject_public_key.data,
+    );
+
+    let mut cert_id = SHA1_ALGORITHM.to_vec();
+    cert_id.extend(der(0x04, name_hash.as_ref()));
+    cert_id.extend(der(0x04, key_hash.as_ref()));
+    cert_id.extend(der(0x02, cert.raw_serial()));
+
+    // OCSPRequest { TBSRequest { requestList { Request { CertID } } } }
+    der(
+        0x30,
+        &der(0x30, &der(0x30, &der(0x30, &der(0x30, &cert_id)))),
+    )
+}
+
+fn der(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    let length = contents.len();
+    if length < 0x80 {
+        encoded.push(length as u8);
+    } else {
+        let bytes: Vec<u8> = length
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|byte| *byte == 0)
+            .collect();
+        encoded.push(0x80 | bytes.len() as u8);
+        encoded.extend(bytes);
+    }
+    encoded.extend_from_slice(contents);
+    encoded
+}
+
+/// Check an OCSP response's signature and freshness and return the status it
+/// gives for `cert`.
+fn evaluate_ocsp(
+    response: &[u8],
+    cert: &X509Certificate,
+    issuer: &X509Certificate,
+    now: DateTime<Utc>,
+) -> Result<Verdict> {
+    let outer = elements(single(response)?.contents)?;
+    let status = outer
+        .first()
+        .filter(|element| element.is_universal(10))
+        .and_then(|element| element.contents.first().copied())
+        .ok_or_else(|| anyhow::anyhow!("Malformed OCSP response"))?;
+    if status != 0 {
+        return Err(anyhow::anyhow!(
+            "responder returned {}",
+            ocsp_response_status(status)
+        ));
+    }
+
+    // responseBytes [0] EXPLICIT { responseType, response OCTET STRING }
+    let response_bytes = outer
+        .get(1)
+        .filter(|element| element.is_context(0))
+        .ok_or_else(|| anyhow::anyhow!("OCSP response has no response bytes"))?;
+    let response_bytes = elements(single(response_bytes.contents)?.contents)?;
+    match response_bytes.as_slice() {
+        [kind, body] if kind.contents == OCSP_BASIC_RESPONSE && body.is_universal(4) => {
+            evaluate_basic_response(body.contents, cert, issuer, now)
+        }
+        _ => Err(anyhow::anyhow!("Unsupported OCSP response type")),
+    }
+}
+
+fn evaluate_basic_response(
+    basic: &[u8],
+    cert: &X509Certificate,
+    issuer: &X509Certificate,
+    now: DateTime<Utc>,
+) -> Result<Verdict> {
+    // BasicOCSPResponse { tbsResponseData, signatureAlgorithm, signature, [0] certs }
+    let basic = elements(single(basic)?.contents)?;
+    let (tbs, algorithm, signature) = match basic.as_slice() {
+        [tbs, algorithm, signature, ..] => (tbs, algorithm, signature),
+        _ => return Err(anyhow::anyhow!("Malformed BasicOCSPResponse")),
+    };
+    let (_, algorithm) = AlgorithmIdentifier::from_der(algorithm.raw)
+        .map_err(|e| anyhow::anyhow!("Malformed OCSP signature algorithm: {}", e))?;
+    let (_, signature) = BitString::from_der(signature.raw)
+        .map_err(|e| anyhow::anyhow!("Malformed OCSP signature: {}", e))?;
+
+    let signed_by =
+        |key: &SubjectPublicKeyInfo| verify_signature(key, &algorithm, &signature, tbs.raw).is_ok();
+    let mut trusted = signed_by(issuer.public_key());
+    if !trusted {
+        // A delegated responder: certified by the issuer for OCSP signing.
+        let responders = match basic.get(3) {
+            Some(certs) if certs.is_context(0) => elements(single(certs.contents)?.contents)?,
+            _ => Vec::new(),
+        };
+        trusted = responders.iter().any(|responder| {
+            X509Certificate::from_der(responder.raw).is_ok_and(|(_, responder)| {
+                responder
+                    .verify_signature(Some(issuer.public_key()))
+                    .is_ok()
+                    && matches!(
+                        responder.extended_key_usage(),
+                        Ok(Some(eku)) if eku.value.ocsp_signing
+                    )
+                    && signed_by(responder.public_key())
+            })
+        });
+    }
+    if !trusted {
+        return Err(anyhow::anyhow!(
+            "response is not signed by the issuer or an authorized responder"
+        ));
+    }
+
+    // ResponseData { [0] version, responderID, producedAt, responses, [1] extensions }
+    let responses = elements(tbs.contents)?
+        .into_iter()
+        .find(|element| element.is_universal(16))
+        .ok_or_else(|| anyhow::anyhow!("OCSP response has no responses"))?;
+
+    for single_response in elements(responses.contents)? {
+        // SingleResponse { certID, certStatus, thisUpdate, [0] nextUpdate, [1] extensions }
+        let fields = elements(single_response.contents)?;
+        let (cert_id, cert_status, this_update) = match fields.as_slice() {
+            [cert_id, cert_status, this_update, ..] => (cert_id, cert_status, this_update),
+            _ => return Err(anyhow::anyhow!("Malformed SingleResponse")),
+        };
+        let serial = elements(cert_id.contents)?
+            .into_iter()
+            .find(|element| element.is_universal(2))
+            .map(|element| element.contents);
+        if serial != Some(cert.raw_serial()) {
+            continue;
+        }
+
+        let this_update = generalized_time(this_update.contents)?;
+        if this_update > now + Duration::minutes(MAX_CLOCK_SKEW_MINUTES) {
+            return Err(anyhow::anyhow!("response thisUpdate is in the future"));
+        }
+        if let Some(next_update) = fields.iter().skip(3).find(|field| field.is_context(0)) {
+            let next_update = generalized_time(single(next_update.contents)?.contents)?;
+            if next_update < now {
+                return Err(anyhow::anyhow!(
+                    "response is stale (nextUpdate {})",
+                    next_update.to_rfc3339()
+                ));
+            }
+        }
+
+        return match cert_status.tag {
+            0 => Ok(Verdict {
+                status: RevocationStatus::Good,
+                revoked_at: None,
+                reason: None,
+            }),
+            1 => {
+                // RevokedInfo { revocationTime, [0] EXPLICIT revocationReason }
+                let info = elements(cert_status.contents)?;
+                let revoked_at = info
+                    .first()
+                    .map(|time| generalized_time(time.contents))
+                    .transpose()?;
+                let reason = info
+                    .iter()
+                    .find(|element| element.is_context(0))
+                    .and_then(|reason| single(reason.contents).ok())
+                    .and_then(|reason| reason.contents.first().copied())
+                    .map(crl_reason);
+                Ok(Verdict {
+                    status: RevocationStatus::Revoked,
+                    revoked_at,
+                    reason,
+                })
+            }
+            _ => Ok(Verdict {
+                status: RevocationStatus::Unknown,
+                revoked_at: None,
+                reason: None,
+            }),
+        };
+    }
+
+    Err(anyhow::anyhow!("response does not cover this certificate"))
+}
+
+/// Check a CRL's signature and freshness and look `cert` up in it.
+fn evaluate_crl(
+    crl: &[u8],
+    cert: &X509Certificate,
+    issuer: &X509Certificate,
+    now: DateTime<Utc>,
+) -> Result<Verdict> {
+    let (_, crl) = CertificateRevocationList::from_der(crl)
+        .map_err(|e| anyhow::anyhow!("Malformed CRL: {}", e))?;
+    if crl.issuer().as_raw() != cert.issuer().as_raw() {
+        return Err(anyhow::anyhow!("CRL was issued by {}", crl.issuer()));
+    }
+    crl.verify_signature(issuer.public_key())
+        .map_err(|_| anyhow::anyhow!("CRL is not signed by the issuer"))?;
+    if let Some(next_update) = crl.next_update() {
+        let next_update = asn1_timestamp(next_update.timestamp())?;
+        if next_update < now {
+            return Err(anyhow::anyhow!(
+                "CRL is stale (nextUpdate {})",
+                next_update.to_rfc3339()
+            ));
+        }
+    }
+
+    let Some(entry) = crl
+        .iter_revoked_certificates()
+        .find(|entry| entry.raw_serial() == cert.raw_serial())
+    else {
+        return Ok(Verdict {
+            status: RevocationStatus::Good,
+            revoked_at: None,
+            reason: None,
+        });
+    };
+
+    Ok(Verdict {
+        status: RevocationStatus::Revoked,
+        revoked_at: Some(asn1_timestamp(entry.revocation_date.timestamp())?),
+        reason: entry.reason_code().map(|(_, reason)| crl_reason(reason.0)),
+    })
+}
+
+fn asn1_timestamp(timestamp: i64) -> Result<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp, 0)
+        .ok_or_else(|| anyhow::anyhow!("Invalid timestamp: {}", timestamp))
+}
+
+fn generalized_time(contents: &[u8]) -> Result<DateTime<Utc>> {
+    let text = std::str::from_utf8(contents)?;
+    NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M%S%.fZ")
+        .map(|time| time.and_utc())
+        .map_err(|_| anyhow::anyhow!("Invalid GeneralizedTime '{}'", text))
+}
+
+fn ocsp_response_status(status: u8) -> &'static str {
+    match status {
+        1 => "malformedRequest",
+        2 => "internalError",
+        3 => "tryLater",
+        5 => "sigRequired",
+        6 => "unauthorized",
+        _ => "an unknown status",
+    }
+}
+
+/// RFC 5280 CRLReason names.
+fn crl_reason(code: u8) -> String {
+    match code {
+        0 => "unspecified",
+        1 => "keyCompromise",
+        2 => "cACompromise",
+        3 => "affiliationChanged",
+        4 => "superseded",
+        5 => "cessationOfOperation",
+        6 => "certificateHold",
+        8 => "removeFromCRL",
+        9 => "privilegeWithdrawn",
+        10 => "aACompromise",
+        other => return format!("reason {}", other),
+    }
+    .to_string()
+}
+
+/// One DER element: tag, contents and full encoding.
+struct Element<'a> {
+    class: Class,
+    tag: u32,
+    contents: &'a [u8],
+    raw: &'a [u8],
+}
+
+impl Element<'_> {
+    fn is_universal(&self, tag: u32) -> bool {
+        self.class == Class::Universal && self.tag == tag
+    }
+
+    fn is_context(&self, tag: u32) -> bool {
+        self.class == Class::ContextSpecific && self.tag == tag
+    }
+}
+
+fn element(input: &[u8]) -> Result<(Element<'_>, &[u8])> {
+    let (rest, any) = Any::from_der(input).map_err(|e| anyhow::anyhow!("Malformed DER: {}", e))?;
+    let element = Element {
+        class: any.class(),
+        tag: any.tag().0,
+        contents: any.data,
+        raw: &input[..input.len() - rest.len()],
+    };
+    Ok((element, rest))
+}
+
+fn single(input: &[u8]) -> Result<Element<'_>> {
+    Ok(element(input)?.0)
+}
+
+fn elements(mut input: &[u8]) -> Result<Vec<Element<'_>>> {
+    let mut elements = Vec::new();
+    while !input.is_empty() {
+        let (next, rest) = element(input)?;
+        elements.push(next);
+        input = rest;
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const LEAF: &[u8] = include_bytes!("../../tests/fixtures/example-com-leaf.der");
+    const ROOT: &[u8] = include_bytes!("../../tests/fixtures/example-root-ca.der");
+    const OCSP_REQUEST: &[u8] = include_bytes!("../../tests/fixtures/example-com-ocsp-request.der");
+    const OCSP_GOOD: &[u8] = include_bytes!("../../tests/fixtures/example-com-ocsp-good.der");
+    const OCSP_REVOKED: &[u8] = include_bytes!("../../tests/fixtures/example-com-ocsp-revoked.der");
+    const OCSP_WRONG_SIGNER: &[u8] =
+        include_bytes!("../../tests/fixtures/example-com-ocsp-wrong-signer.der");
+    const CRL: &[u8] = include_bytes!("../../tests/fixtures/example-root-crl.der");
+    const CRL_REVOKED: &[u8] = include_bytes!("../../tests/fixtures/example-root-crl-revoked.der");
+
+    const OCSP_URL: &str = "http://ocsp.example-ca.test";
+    const CRL_URL: &str = "http://crl.example-ca.test/root.crl";
+
+    /// Serves canned answers in place of the issuer's OCSP responder and CRL
+    /// server, and records the OCSP requests it receives.
+    #[derive(Default)]
+    struct StandInResponder {
+        ocsp: Option<&'static [u8]>,
+        crl: Option<&'static [u8]>,
+        requests: Mutex<Vec<Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl RevocationFetcher for StandInResponder {
+        async fn ocsp(&self, url: &str, request: &[u8]) -> Result<Vec<u8>> {
+            assert_eq!(url, OCSP_URL);
+            self.requests.lock().unwrap().push(request.to_vec());
+            self.ocsp
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| anyhow::anyhow!("connection refused"))
+        }
+
+        async fn crl(&self, url: &str) -> Result<Vec<u8>> {
+            assert_eq!(url, CRL_URL);
+            self.crl
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| anyhow::anyhow!("connection refused"))
+        }
+    }
+
+    fn chain() -> Vec<CertificateDer<'static>> {
+        vec![CertificateDer::from(LEAF), CertificateDer::from(ROOT)]
+    }
+
+    fn revoked_at() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-03-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn ocsp_request_test() {
+        let (_, leaf) = X509Certificate::from_der(LEAF).unwrap();
+        let (_, root) = X509Certificate::from_der(ROOT).unwrap();
+
+        // Byte for byte what `openssl ocsp -no_nonce` sends.
+        assert_eq!(ocsp_request(&leaf, &root), OCSP_REQUEST);
+        assert_eq!(
+            revocation_urls(&leaf),
+            (vec![OCSP_URL.to_string()], vec![CRL_URL.to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn stapled_response_test() {
+        let responder = StandInResponder::default();
+        let info = check(&chain(), Some(OCSP_GOOD), &responder, Utc::now()).await;
+
+        assert_eq!(info.status, RevocationStatus::Good);
+        assert_eq!(info.source, Some(RevocationSource::StapledOcsp));
+        assert!(info.errors.is_empty());
+        assert!(responder.requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn ocsp_revoked_test() {
+        let responder = StandInResponder {
+            ocsp: Some(OCSP_REVOKED),
+            ..Default::default()
+        };
+        let info = check(&chain(), None, &responder, Utc::now()).await;
+
+        assert_eq!(info.status, RevocationStatus::Revoked);
+        assert_eq!(info.source, Some(RevocationSource::Ocsp));
+        assert_eq!(info.revoked_at, Some(revoked_at()));
+        assert_eq!(info.reason.as_deref(), Some("keyCompromise"));
+        assert_eq!(
+            *responder.requests.lock().unwrap(),
+            vec![OCSP_REQUEST.to_vec()]
+        );
+    }
+
+    #[tokio::test]
+    async fn crl_fallback_test() {
+        let responder = StandInResponder {
+            crl: Some(CRL_REVOKED),
+            ..Default::default()
+        };
+        let info = check(&chain(), None, &responder, Utc::now()).await;
+
+        assert_eq!(info.status, RevocationStatus::Revoked);
+        assert_eq!(info.source, Some(RevocationSource::Crl));
+        assert_eq!(info.revoked_at, Some(revoked_at()));
+        assert_eq!(info.reason.as_deref(), Some("keyCompromise"));
+        assert_eq!(
+            info.errors,
+            vec![format!("OCSP {}: connection refused", OCSP_URL)]
+        );
+
+        let responder = StandInResponder {
+            crl: Some(CRL),
+            ..Default::default()
+        };
+        let info = check(&chain(), None, &responder, Utc::now()).await;
+        assert_eq!(info.status, RevocationStatus::Good);
+        assert_eq!(info.source, Some(RevocationSource::Crl));
+    }
+
+    #[tokio::test]
+    async fn untrusted_ocsp_signer_test() {
+        let responder = StandInResponder {
+            ocsp: Some(OCSP_WRONG_SIGNER),
+            ..Default::default()
+        };
+        let info = check(&chain(), Some(OCSP_WRONG_SIGNER), &responder, Utc::now()).await;
+
+        assert_eq!(info.status, RevocationStatus::Unknown);
+        assert_eq!(info.source, None);
+        assert_eq!(info.errors.len(), 3);
+        assert!(info.errors[0].contains("not signed by the issuer"));
+        assert!(info.errors[1].contains("not signed by the issuer"));
+    }
+
+    #[tokio::test]
+    async fn stale_and_missing_issuer_test() {
+        let responder = StandInResponder {
+            ocsp: Some(OCSP_GOOD),
+            crl: Some(CRL),
+            ..Default::default()
+        };
+        let far_future = DateTime::parse_from_rfc3339("2200-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let info = check(&chain(), None, &responder, far_future).await;
+        assert_eq!(info.status, RevocationStatus::Unknown);
+        assert!(info.errors.iter().all(|error| error.contains("stale")));
+
+        let info = check(&chain()[..1], None, &responder, Utc::now()).await;
+        assert_eq!(info.status, RevocationStatus::Unknown);
+        assert_eq!(info.errors, vec!["Issuer certificate was not presented"]);
+    }
+}
@@ -0,0 +1,447 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// Concurrency and rate limits for a single upstream host.
+#[derive(Debug, Clone, Copy)]
+pub struct HostLimits {
+    pub concurrency: usize,
+    pub requests_per_second: f64,
+    pub burst: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    /// Maximum requests in flight across all hosts.
+    pub global_concurrency: usize,
+    /// Limits for hosts without an entry in `host_overrides`.
+    pub default_host_limits: HostLimits,
+    pub host_overrides: HashMap<String, HostLimits>,
+    /// Retries after the first attempt on 429/503 and transient network errors.
+    pub max_retries: u32,
+    pub base_backoff: Duration,
+    /// Cap on our own backoff; a server's `Retry-After` is not capped.
+    pub max_backoff: Duration,
+    /// Domains checked concurrently by bulk operations.
+    pub domain_concurrency: usize,
+    /// Upper bound on the time spent checking a single domain.
+    pub domain_timeout: Duration,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        let mut host_overrides = HashMap::new();
+        // The DoH resolver is built for volume; RDAP servers are not.
+        host_overrides.insert(
+            "cloudflare-dns.com".to_string(),
+            HostLimits {
+                concurrency: 32,
+                requests_per_second: 100.0,
+                burst: 100.0,
+            },
+        );
+
+        Self {
+            global_concurrency: 64,
+            default_host_limits: HostLimits {
+                concurrency: 4,
+                requests_per_second: 5.0,
+                burst: 10.0,
+            },
+            host_overrides,
+            max_retries: 3,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            domain_concurrency: 16,
+            domain_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SchedulerConfig {
+    /// Defaults, overridden by `DOMAIN_MCP_GLOBAL_CONCURRENCY`,
+    /// `DOMAIN_MCP_HOST_CONCURRENCY`, `DOMAIN_MCP_HOST_RATE_LIMIT` (requests
+    /// per second), `DOMAIN_MCP_DOMAIN_CONCURRENCY` and
+    /// `DOMAIN_MCP_DOMAIN_TIMEOUT_SECS`.
+    pub fn from_env() -> Self {
+        fn env<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok()?.parse().ok()
+        }
+
+        let mut config = Self::default();
+        if let Some(value) = env("DOMAIN_MCP_GLOBAL_CONCURRENCY") {
+            config.global_concurrency = value;
+        }
+        if let Some(value) = env("DOMAIN_MCP_HOST_CONCURRENCY") {
+            config.default_host_limits.concurrency = value;
+        }
+        if let Some(value) = env::<f64>("DOMAIN_MCP_HOST_RATE_LIMIT") {
+            config.default_host_limits.requests_per_second = value;
+            config.default_host_limits.burst = value.max(1.0);
+        }
+        if let Some(value) = env("DOMAIN_MCP_DOMAIN_CONCURRENCY") {
+            config.domain_concurrency = value;
+        }
+        if let Some(value) = env("DOMAIN_MCP_DOMAIN_TIMEOUT_SECS") {
+            config.domain_timeout = Duration::from_secs(value);
+        }
+        config
+    }
+
+    fn limits_for(&self, host: &str) -> HostLimits {
+        self.host_overrides
+            .get(host)
+            .copied()
+            .unwrap_or(self.default_host_limits)
+    }
+}
+
+/// Token bucket: holds up to `capacity` tokens, refilled continuously at
+/// `refill_per_second`.
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(refill_per_second: f64, capacity: f64, now: Instant) -> Self {
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_second,
+            last_refill: now,
+        }
+    }
+
+    /// Take a token, or return how long to wait before one is available.
+    pub fn try_acquire(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else if self.refill_per_second <= 0.0 {
+            Some(Duration::from_secs(1))
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.refill_per_second,
+            ))
+        }
+    }
+
+    /// Drain the bucket so the host gets a rest, e.g. after a 429.
+    pub fn drain(&mut self) {
+        self.tokens = 0.0;
+    }
+}
+
+struct HostState {
+    semaphore: Arc<Semaphore>,
+    bucket: Arc<Mutex<TokenBucket>>,
+}
+
+/// Shared request scheduler: a global and per-host concurrency limit, a
+/// token bucket per host, and retries with exponential backoff on 429/503
+/// that honor `Retry-After` up to the per-domain timeout.
+pub struct Scheduler {
+    config: SchedulerConfig,
+    global: Arc<Semaphore>,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl Scheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(config.global_concurrency.max(1))),
+            config,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The process-wide scheduler, configured from the environment.
+    pub fn global() -> &'static Scheduler {
+        static SCHEDULER: OnceLock<Scheduler> = OnceLock::new();
+        SCHEDULER.get_or_init(|| Scheduler::new(SchedulerConfig::from_env()))
+    }
+
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.config
+    }
+
+    fn host_state(&self, host: &str) -> (Arc<Semaphore>, Arc<Mutex<TokenBucket>>) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let state = hosts.entry(host.to_string()).or_insert_with(|| {
+            let limits = self.config.limits_for(host);
+            HostState {
+                semaphore: Arc::new(Semaphore::new(limits.concurrency.max(1))),
+                bucket: Arc::new(Mutex::new(TokenBucket::new(
+                    limits.requests_per_second,
+                    limits.burst.max(1.0),
+                    Instant::now(),
+                ))),
+            }
+        });
+        (state.semaphore.clone(), state.bucket.clone())
+    }
+
+    /// Send `request` with `client`, respecting the limits for its host.
+    ///
+    /// The returned response may still be a 429/503 when retries are
+    /// exhausted; callers decide what that means for them.
+    pub async fn execute(&self, client: &Client, request: Request) -> Result<Response> {
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let (host_semaphore, bucket) = self.host_state(&host);
+
+        let mut attempt = 0;
+        loop {
+            let attempt_request = request
+                .try_clone()
+                .ok_or_else(|| anyhow::anyhow!("Request body cannot be retried"))?;
+
+            let outcome = {
+                let _host = host_semaphore.acquire().await?;
+                loop {
+                    let wait = bucket
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .try_acquire(Instant::now());
+                    match wait {
+                        Some(wait) => tokio::time::sleep(wait).await,
+                        None => break,
+                    }
+                }
+                // Take the global slot last so requests waiting on a slow
+                // host's bucket don't starve everyone else.
+                let _global = self.global.acquire().await?;
+                client.execute(attempt_request).await
+            };
+
+            let retry_delay = match &outcome {
+                Ok(response) if is_retryable_status(response.status()) => {
+                    bucket.lock().unwrap_or_else(|e| e.into_inner()).drain();
+                    let retry_after = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| parse_retry_after(value, Utc::now()));
+                    match retry_after {
+                        // Retrying sooner than asked only gets throttled again,
+                        // so a wait longer than a domain may take gives up.
+                        Some(delay) if delay > self.config.domain_timeout => None,
+                        Some(delay) => Some(delay),
+                        None => Some(backoff_delay(&self.config, attempt)),
+                    }
+                }
+                Err(e) if e.is_timeout() || e.is_connect() => {
+                    Some(backoff_delay(&self.config, attempt))
+                }
+                _ => None,
+            };
+
+            match retry_delay {
+                Some(delay) if attempt < self.config.max_retries => {
+                    tracing::debug!(
+                        "Retrying request to {} in {:?} (attempt {})",
+                        host,
+                        delay,
+                        attempt + 1
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return Ok(outcome?),
+            }
+        }
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+/// Exponential backoff: `base * 2^attempt`, capped at `max_backoff`.
+pub fn backoff_delay(config: &SchedulerConfig, attempt: u32) -> Duration {
+    config
+        .base_backoff
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(config.max_backoff)
+}
+
+/// Parse a `Retry-After` value: delay-seconds or an HTTP-date (RFC 9110
+/// section 10.2.3).
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    Some(
+        date.signed_duration_since(now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn token_bucket_test() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, 2.0, start);
+
+        assert_eq!(bucket.try_acquire(start), None);
+        assert_eq!(bucket.try_acquire(start), None);
+
+        let wait = bucket.try_acquire(start).unwrap();
+        assert_eq!(wait, Duration::from_millis(500));
+
+        // Half a second later one token has been refilled.
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.try_acquire(later), None);
+
+        // Refill never exceeds the capacity.
+        let much_later = later + Duration::from_secs(60);
+        assert_eq!(bucket.try_acquire(much_later), None);
+        assert_eq!(bucket.try_acquire(much_later), None);
+        assert!(bucket.try_acquire(much_later).is_some());
+    }
+
+    #[test]
+    fn token_bucket_drain_test() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1.0, 5.0, start);
+        bucket.drain();
+        assert_eq!(bucket.try_acquire(start), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn parse_retry_after_test() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Mon, 01 Jan 2024 12:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        // A date in the past means "retry now".
+        assert_eq!(
+            parse_retry_after("Mon, 01 Jan 2024 11:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn backoff_delay_test() {
+        let config = SchedulerConfig::default();
+        assert_eq!(backoff_delay(&config, 0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(&config, 1), Duration::from_secs(1));
+        assert_eq!(backoff_delay(&config, 3), Duration::from_secs(4));
+        assert_eq!(backoff_delay(&config, 20), config.max_backoff);
+    }
+
+    #[test]
+    fn host_limits_test() {
+        let config = SchedulerConfig::default();
+        assert_eq!(config.limits_for("rdap.verisign.com").concurrency, 4);
+        assert_eq!(config.limits_for("cloudflare-dns.com").concurrency, 32);
+    }
+
+    #[tokio::test]
+    async fn host_state_is_shared_test() {
+        let scheduler = Scheduler::new(SchedulerConfig::default());
+        let (first, _) = scheduler.host_state("rdap.example");
+        let (second, _) = scheduler.host_state("rdap.example");
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.available_permits(), 4);
+    }
+
+    #[tokio::test]
+    async fn execute_retries_after_429_test() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let responses = [
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            ];
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = [0u8; 1024];
+                let _ = socket.read(&mut buffer).await.unwrap();
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let scheduler = Scheduler::new(SchedulerConfig::default());
+        let client = Client::new();
+        let request = client
+            .get(format!("http://{}/domain/example.com", addr))
+            .build()
+            .unwrap();
+
+        let response = scheduler.execute(&client, request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ok");
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn execute_returns_429_when_retry_after_is_too_long_test() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = [0u8; 1024];
+            let _ = socket.read(&mut buffer).await.unwrap();
+            socket
+                .write_all(
+                    b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .await
+                .unwrap();
+        });
+
+        // 120s is past the 30s per-domain timeout, so the 429 comes back.
+        let scheduler = Scheduler::new(SchedulerConfig::default());
+        let client = Client::new();
+        let request = client
+            .get(format!("http://{}/domain/example.com", addr))
+            .build()
+            .unwrap();
+
+        let started = Instant::now();
+        let response = scheduler.execute(&client, request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::expired::{self, ExpiredDomain};
+use super::tlds;
+use super::words;
+
+/// TLDs after .com that buyers treat as first rate.
+const SECOND_TIER_TLDS: &[&str] = &["net", "org", "io", "co", "ai"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Factor {
+    Length,
+    Tld,
+    DictionaryWords,
+    Pronounceability,
+    Characters,
+    Age,
+    History,
+}
+
+impl Factor {
+    /// Points available for the factor; together they make 100.
+    fn max_points(self) -> f64 {
+        match self {
+            Factor::Length | Factor::Tld | Factor::DictionaryWords => 20.0,
+            Factor::Pronounceability => 15.0,
+            Factor::Characters | Factor::Age => 10.0,
+            Factor::History => 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreFactor {
+    pub factor: Factor,
+    pub points: f64,
+    pub max_points: f64,
+    pub detail: String,
+}
+
+/// A 0-100 rating from what is known about a domain offline, comparable
+/// across every source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainScore {
+    pub total: f64,
+    pub factors: Vec<ScoreFactor>,
+}
+
+pub fn score(domain: &ExpiredDomain, now: DateTime<Utc>) -> DomainScore {
+    let name = domain.domain.to_lowercase();
+    let (label, suffix) = name.split_once('.').unwrap_or((&name, ""));
+    let segments = words::segment(label);
+
+    let factors = vec![
+        length(label),
+        tld(suffix),
+        dictionary_words(segments.as_deref()),
+        pronounceability(label, segments.is_some()),
+        characters(label),
+        age(domain.created.as_deref(), now),
+        history(domain.has_dns, domain.has_website),
+    ];
+    let total = factors.iter().map(|factor| factor.points).sum::<f64>();
+
+    DomainScore {
+        total: round(total),
+        factors,
+    }
+}
+
+fn factor(factor: Factor, fraction: f64, detail: String) -> ScoreFactor {
+    let max_points = factor.max_points();
+    ScoreFactor {
+        factor,
+        points: round(max_points * fraction.clamp(0.0, 1.0)),
+        max_points,
+        detail,
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// Full marks up to four characters, nothing from fourteen.
+fn length(label: &str) -> ScoreFactor {
+    let length = label.chars().count();
+    let fraction = 1.0 - length.saturating_sub(4) as f64 / 10.0;
+    factor(Factor::Length, fraction, format!("{} characters", length))
+}
+
+fn tld(suffix: &str) -> ScoreFactor {
+    let tld = suffix.rsplit('.').next().unwrap_or(suffix);
+    let (fraction, tier) = if tld == "com" {
+        (1.0, "tier 1")
+    } else if SECOND_TIER_TLDS.contains(&tld) {
+        (0.7, "tier 2")
+    } else if [tlds::POPULAR, tlds::NEW_GENERIC, tlds::COUNTRY_CODE]
+        .iter()
+        .any(|list| list.contains(&tld))
+    {
+        (0.4, "tier 3")
+    } else {
+        (0.15, "other")
+    };
+    factor(Factor::Tld, fraction, format!(".{} ({})", suffix, tier))
+}
+
+/// One word is best; each extra word is worth less.
+fn dictionary_words(segments: Option<&[&str]>) -> ScoreFactor {
+    match segments {
+        Some(segments) => {
+            let fraction = match segments.len() {
+                1 => 1.0,
+                2 => 0.7,
+                3 => 0.4,
+                _ => 0.0,
+            };
+            factor(Factor::DictionaryWords, fraction, segments.join(" + "))
+        }
+        None => factor(
+            Factor::DictionaryWords,
+            0.0,
+            "Not made of dictionary words".to_string(),
+        ),
+    }
+}
+
+/// Dictionary words are pronounceable by definition; otherwise penalize long
+/// consonant or vowel runs and an unusual share of vowels.
+fn pronounceability(label: &str, dictionary: bool) -> ScoreFactor {
+    if dictionary {
+        return factor(
+            Factor::Pronounceability,
+            1.0,
+            "Dictionary words".to_string(),
+        );
+    }
+    let letters: Vec<char> = label.chars().filter(char::is_ascii_alphabetic).collect();
+    if letters.is_empty() {
+        return factor(Factor::Pronounceability, 0.0, "No letters".to_string());
+    }
+
+    let is_vowel = |c: &char| "aeiouy".contains(*c);
+    let (mut consonant_run, mut vowel_run) = (0, 0);
+    let (mut longest_consonants, mut longest_vowels) = (0, 0);
+    for c in &letters {
+        if is_vowel(c) {
+            vowel_run += 1;
+            consonant_run = 0;
+        } else {
+            consonant_run += 1;
+            vowel_run = 0;
+        }
+        longest_consonants = longest_consonants.max(consonant_run);
+        longest_vowels = longest_vowels.max(vowel_run);
+    }
+    let vowel_share = letters.iter().filter(|c| is_vowel(c)).count() as f64 / letters.len() as f64;
+
+    let mut fraction = 1.0;
+    if longest_consonants >= 4 {
+        fraction -= 0.5;
+    } else if longest_consonants == 3 {
+        fraction -= 0.2;
+    }
+    if longest_vowels >= 3 {
+        fraction -= 0.25;
+    }
+    if !(0.2..=0.7).contains(&vowel_share) {
+        fraction -= 0.25;
+    }
+    factor(
+        Factor::Pronounceability,
+        fraction,
+        format!(
+            "Longest consonant run {}, {:.0}% vowels",
+            longest_consonants,
+            vowel_share * 100.0
+        ),
+    )
+}
+
+/// Each hyphen costs half the points and each digit a third.
+fn characters(label: &str) -> ScoreFactor {
+    let hyphens = label.chars().filter(|c| *c == '-').count();
+    let digits = label.chars().filter(char::is_ascii_digit).count();
+    let fraction = 1.0 - 0.5 * hyphens as f64 - digits as f64 / 3.0;
+    let detail = if hyphens + digits == 0 {
+        "Letters only".to_string()
+    } else {
+        format!("{} hyphens, {} digits", hyphens, digits)
+    };
+    factor(Factor::Characters, fraction, detail)
+}
+
+fn age(created: Option<&str>, now: DateTime<Utc>) -> ScoreFactor {
+    let Some(created) = created.and_then(expired::parse_date) else {
+        return factor(Factor::Age, 0.0, "Registration date unknown".to_string());
+    };
+    let years = (now - created).num_days() / 365;
+    let fraction = match years {
+        15.. => 1.0,
+        10..=14 => 0.8,
+        5..=9 => 0.6,
+        2..=4 => 0.4,
+        _ => 0.2,
+    };
+    factor(
+        Factor::Age,
+        fraction,
+        format!("Registered {} years ago", years),
+    )
+}
+
+fn history(has_dns: Option<bool>, has_website: Option<bool>) -> ScoreFactor {
+    let (fraction, detail) = match (has_dns, has_website) {
+        (_, Some(true)) => (1.0, "Had DNS and a website address"),
+        (Some(true), _) => (0.6, "Had DNS records"),
+        (Some(false), _) | (_, Some(false)) => (0.0, "No DNS records"),
+        (None, None) => (0.0, "DNS history unknown"),
+    };
+    factor(Factor::History, fraction, detail.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domain(name: &str) -> ExpiredDomain {
+        ExpiredDomain {
+            domain: name.to_string(),
+            status: "pending delete".to_string(),
+            source: "Test".to_string(),
+            created: None,
+            updated: None,
+            end_time: None,
+            appraisal: None,
+            starting_price: None,
+            bids: None,
+            has_dns: None,
+            has_website: None,
+            score: None,
+            matched_keywords: Vec::new(),
+        }
+    }
+
+    fn points(score: &DomainScore, factor: Factor) -> f64 {
+        score
+            .factors
+            .iter()
+            .find(|f| f.factor == factor)
+            .unwrap()
+            .points
+    }
+
+    #[test]
+    fn factors_add_up_to_one_hundred() {
+        let now = Utc::now();
+        let best = ExpiredDomain {
+            created: Some("2001-01-01".to_string()),
+            has_dns: Some(true),
+            has_website: Some(true),
+            ..domain("shop.com")
+        };
+        let score = score(&best, now);
+        assert_eq!(score.total, 100.0);
+        assert_eq!(
+            score.factors.iter().map(|f| f.max_points).sum::<f64>(),
+            100.0
+        );
+    }
+
+    #[test]
+    fn scores_each_factor() {
+        let now = Utc::now();
+
+        let compound = score(&domain("cloudshop.net"), now);
+        assert_eq!(points(&compound, Factor::Length), 10.0);
+        assert_eq!(points(&compound, Factor::Tld), 14.0);
+        assert_eq!(points(&compound, Factor::DictionaryWords), 14.0);
+        assert_eq!(points(&compound, Factor::Pronounceability), 15.0);
+        assert_eq!(points(&compound, Factor::Age), 0.0);
+
+        let junk = score(&domain("xq-zrt9k.biz"), now);
+        assert_eq!(points(&junk, Factor::DictionaryWords), 0.0);
+        assert_eq!(points(&junk, Factor::Tld), 8.0);
+        // One hyphen and one digit.
+        assert_eq!(points(&junk, Factor::Characters), 1.7);
+        assert!(points(&junk, Factor::Pronounceability) < 10.0);
+
+        assert!(compound.total > junk.total);
+        assert!(score(&domain("cloud.com"), now).total > compound.total);
+    }
+
+    #[test]
+    fn pronounceable_made_up_names_beat_consonant_clusters() {
+        let now = Utc::now();
+        let pronounceable = score(&domain("zumira.com"), now);
+        let cluster = score(&domain("xkcdqz.com"), now);
+        assert_eq!(points(&pronounceable, Factor::Pronounceability), 15.0);
+        assert!(points(&cluster, Factor::Pronounceability) <= 4.0);
+    }
+}
@@ -1,307 +1,628 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use ring::digest::{digest, SHA256};
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use x509_parser::extensions::{GeneralName, ParsedExtension};
+use x509_parser::objects::{oid2sn, oid_registry};
+use x509_parser::oid_registry::Oid;
+use x509_parser::prelude::{FromDer, X509Certificate, X509Name};
+use x509_parser::public_key::PublicKey;
+
+use super::cert_chain::{self, Endpoint};
+use super::ct::{self, EmbeddedSct};
+use super::dns::{self, MxRecord};
+use super::revocation::{self, HttpFetcher, RevocationFetcher, RevocationInfo, RevocationStatus};
+use super::starttls::Protocol;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SslCertificateInfo {
     pub domain: String,
-    pub issuer: String,
-    pub subject: String,
+    pub version: u32,
+    pub issuer: DistinguishedName,
+    pub subject: DistinguishedName,
     pub serial_number: String,
-    pub not_before: String,
-    pub not_after: String,
+    /// SHA-256 of the DER encoding, as colon separated hex.
+    pub fingerprint_sha256: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
     pub signature_algorithm: String,
+    pub public_key: PublicKeyInfo,
+    pub subject_alt_names: Vec<SubjectAltName>,
+    /// The DNS entries of `subject_alt_names`.
     pub san_domains: Vec<String>,
+    pub key_usage: Vec<String>,
+    pub extended_key_usage: Vec<String>,
+    pub is_ca: bool,
+    pub policy_oids: Vec<String>,
+    pub extensions: Vec<CertificateExtension>,
+    /// Signed certificate timestamps from the CT logs that logged the precertificate.
+    pub embedded_scts: Vec<EmbeddedSct>,
     pub is_valid: bool,
     pub days_until_expiry: Option<i64>,
+    /// Why the presented chain failed validation, if it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_error: Option<String>,
+    /// OCSP/CRL status; only set for certificates fetched from a server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<RevocationInfo>,
 }
 
-pub async fn get_certificate_info(domain: &str) -> Result<SslCertificateInfo> {
-    let port = 443;
-    let addr = format!("{}:{}", domain, port);
+/// An X.500 name, both as a string and as its sequence of RDNs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DistinguishedName {
+    pub text: String,
+    /// Each RDN is a set of attributes, almost always exactly one.
+    pub rdns: Vec<Vec<NameAttribute>>,
+}
 
-    let result = tokio::task::spawn_blocking({
-        let domain = domain.to_string();
-        let addr = addr.clone();
-        move || get_cert_info_blocking(&domain, &addr)
-    })
-    .await??;
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameAttribute {
+    pub oid: String,
+    /// Short name such as "CN" or "O", when the OID is well known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub value: String,
+}
 
-    Ok(result)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum SubjectAltName {
+    Dns(String),
+    Ip(String),
+    Email(String),
+    Uri(String),
+    DirectoryName(String),
+    RegisteredId(String),
+    Other(String),
 }
 
-fn get_cert_info_blocking(domain: &str, addr: &str) -> Result<SslCertificateInfo> {
-    use rustls::pki_types::ServerName;
-    use std::sync::Arc;
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicKeyInfo {
+    pub algorithm: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_size_bits: Option<usize>,
+    /// Named curve for EC keys.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curve: Option<String>,
+}
 
-    // Initialize the crypto provider
-    let _ = rustls::crypto::ring::default_provider().install_default();
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateExtension {
+    pub oid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub critical: bool,
+}
 
-    let mut root_store = rustls::RootCertStore::empty();
-    root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+/// The certificate each A/AAAA address of a domain serves for the same name.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressCertificateReport {
+    pub domain: String,
+    pub server_name: String,
+    pub port: u16,
+    pub addresses: Vec<AddressCertificate>,
+    /// Number of different leaf certificates seen across the addresses.
+    pub distinct_certificates: usize,
+    /// True when every address answered with the same leaf certificate.
+    pub consistent: bool,
+}
 
-    let config = rustls::ClientConfig::builder()
-        .with_root_certificates(root_store)
-        .with_no_client_auth();
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressCertificate {
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<SslCertificateInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
 
-    let server_name = ServerName::try_from(domain.to_string())?;
-    let mut conn = rustls::ClientConnection::new(Arc::new(config), server_name)?;
-    let mut tcp_stream = TcpStream::connect(addr)?;
-    let mut tls_stream = rustls::Stream::new(&mut conn, &mut tcp_stream);
+pub async fn get_certificate_info(endpoint: &Endpoint) -> Result<SslCertificateInfo> {
+    get_certificate_info_with(endpoint, &HttpFetcher::new()).await
+}
 
-    let request = format!(
-        "HEAD / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
-        domain
-    );
-    tls_stream.write_all(request.as_bytes())?;
+/// Fetch and validate the certificate at `endpoint`, checking revocation
+/// through `fetcher`.
+pub async fn get_certificate_info_with(
+    endpoint: &Endpoint,
+    fetcher: &dyn RevocationFetcher,
+) -> Result<SslCertificateInfo> {
+    let presented = cert_chain::fetch_presented_chain(endpoint).await?;
+    let mut cert = parse_x509_certificate(presented.chain[0].as_ref())?;
+    let now = Utc::now();
+
+    if let Some(error) = cert_chain::validate_chain(
+        &endpoint.server_name,
+        &presented.chain,
+        &cert_chain::web_pki_roots(),
+        now,
+    ) {
+        cert.is_valid = false;
+        cert.validation_error = Some(error.message);
+    }
 
-    let mut response = Vec::new();
-    let _ = tls_stream.read_to_end(&mut response);
+    let stapled_ocsp = Some(presented.ocsp_response.as_slice()).filter(|ocsp| !ocsp.is_empty());
+    let revocation = revocation::check(&presented.chain, stapled_ocsp, fetcher, now).await;
+    if revocation.status == RevocationStatus::Revoked {
+        cert.is_valid = false;
+    }
+    cert.revocation = Some(revocation);
 
-    let cert_chain = conn
-        .peer_certificates()
-        .ok_or_else(|| anyhow::anyhow!("No certificates found"))?;
+    Ok(cert)
+}
 
-    if cert_chain.is_empty() {
-        return Err(anyhow::anyhow!("Certificate chain is empty"));
+/// Fetch the certificate from every A and AAAA address of `domain`, asking
+/// each one for `endpoint.server_name`, to catch edges that serve different
+/// certificates for the same name.
+pub async fn check_all_addresses(
+    domain: &str,
+    endpoint: &Endpoint,
+) -> Result<AddressCertificateReport> {
+    let lookup = dns::lookup(domain).await?;
+    let addresses: Vec<IpAddr> = lookup
+        .a_records
+        .iter()
+        .chain(&lookup.aaaa_records)
+        .filter_map(|record| record.parse().ok())
+        .collect();
+    if addresses.is_empty() {
+        return Err(anyhow::anyhow!("No A or AAAA records found for {}", domain));
     }
 
-    let cert_der = &cert_chain[0];
-    let cert = parse_x509_certificate(cert_der.as_ref())?;
+    let addresses = join_all(addresses.into_iter().map(|address| async move {
+        let endpoint = endpoint.clone().with_address(address);
+        let (certificate, error) = match get_certificate_info(&endpoint).await {
+            Ok(certificate) => (Some(certificate), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        AddressCertificate {
+            address: address.to_string(),
+            certificate,
+            error,
+        }
+    }))
+    .await;
+
+    let distinct_certificates = distinct_fingerprints(&addresses);
+    let consistent = distinct_certificates == 1
+        && addresses
+            .iter()
+            .all(|address| address.certificate.is_some());
+
+    Ok(AddressCertificateReport {
+        domain: domain.to_string(),
+        server_name: endpoint.server_name.clone(),
+        port: endpoint.port,
+        addresses,
+        distinct_certificates,
+        consistent,
+    })
+}
 
-    Ok(cert)
+fn distinct_fingerprints(addresses: &[AddressCertificate]) -> usize {
+    addresses
+        .iter()
+        .filter_map(|address| address.certificate.as_ref())
+        .map(|certificate| certificate.fingerprint_sha256.as_str())
+        .collect::<HashSet<_>>()
+        .len()
 }
 
-fn parse_x509_certificate(cert_der: &[u8]) -> Result<SslCertificateInfo> {
-    use std::io::Write;
-    use std::process::Command;
-    use tempfile::NamedTempFile;
-
-    let mut temp_file = NamedTempFile::new()?;
-    temp_file.write_all(cert_der)?;
-    let temp_path = temp_file.path();
-
-    let output = Command::new("openssl")
-        .args([
-            "x509",
-            "-inform",
-            "DER",
-            "-in",
-            temp_path.to_str().unwrap(),
-            "-text",
-            "-noout",
-        ])
-        .output()?;
-
-    let cert_text = String::from_utf8_lossy(&output.stdout);
-
-    let issuer = extract_cert_field(&cert_text, "Issuer: ");
-    let subject = extract_cert_field(&cert_text, "Subject: ");
-    let serial = extract_cert_field(&cert_text, "Serial Number:");
-    let not_before = extract_cert_field(&cert_text, "Not Before:");
-    let not_after = extract_cert_field(&cert_text, "Not After :");
-    let sig_algo = extract_cert_field(&cert_text, "Signature Algorithm: ");
-
-    let san_domains = extract_san_domains(&cert_text);
-
-    let days_until_expiry = calculate_days_until_expiry(&not_after);
-    let is_valid = days_until_expiry.is_some_and(|days| days > 0);
+/// Fetch the SMTP STARTTLS certificate of every MX host into its record.
+/// Returns whether every host presented a valid certificate, or `None` when
+/// there is no mail host to check.
+pub async fn check_mx_certificates(mx_records: &mut [MxRecord]) -> Option<bool> {
+    let port = 25;
+    let checks = join_all(mx_records.iter_mut().map(|mx| async move {
+        let host = mx.exchange.trim_end_matches('.').to_lowercase();
+        // A null MX (RFC 7505) says the domain accepts no mail at all.
+        if host.is_empty() {
+            mx.certificate_error = Some("Null MX: the domain accepts no mail".to_string());
+            return None;
+        }
+
+        let endpoint = Endpoint::new(&host, port, Protocol::Smtp);
+        match get_certificate_info(&endpoint).await {
+            Ok(certificate) => {
+                let valid = certificate.is_valid;
+                mx.certificate = Some(certificate);
+                Some(valid)
+            }
+            Err(e) => {
+                mx.certificate_error = Some(e.to_string());
+                Some(false)
+            }
+        }
+    }))
+    .await;
+
+    let checked: Vec<bool> = checks.into_iter().flatten().collect();
+    (!checked.is_empty()).then(|| checked.iter().all(|valid| *valid))
+}
+
+pub(crate) fn parse_x509_certificate(cert_der: &[u8]) -> Result<SslCertificateInfo> {
+    let (_, cert) = X509Certificate::from_der(cert_der)
+        .map_err(|e| anyhow::anyhow!("Failed to parse certificate: {}", e))?;
+
+    let not_before = asn1_time_to_utc(cert.validity().not_before.timestamp())?;
+    let not_after = asn1_time_to_utc(cert.validity().not_after.timestamp())?;
+
+    let mut subject_alt_names = Vec::new();
+    let mut key_usage = Vec::new();
+    let mut extended_key_usage = Vec::new();
+    let mut is_ca = false;
+    let mut policy_oids = Vec::new();
+    let mut extensions = Vec::new();
+    let mut embedded_scts = Vec::new();
+
+    for extension in cert.extensions() {
+        extensions.push(CertificateExtension {
+            oid: extension.oid.to_id_string(),
+            name: oid_name(&extension.oid),
+            critical: extension.critical,
+        });
+
+        match extension.parsed_extension() {
+            ParsedExtension::SubjectAlternativeName(san) => {
+                subject_alt_names.extend(san.general_names.iter().map(subject_alt_name));
+            }
+            ParsedExtension::KeyUsage(usage) => {
+                key_usage = usage
+                    .to_string()
+                    .split(", ")
+                    .filter(|flag| !flag.is_empty())
+                    .map(|flag| flag.to_string())
+                    .collect();
+            }
+            ParsedExtension::ExtendedKeyUsage(usage) => {
+                let named = [
+                    (usage.any, "anyExtendedKeyUsage"),
+                    (usage.server_auth, "serverAuth"),
+                    (usage.client_auth, "clientAuth"),
+                    (usage.code_signing, "codeSigning"),
+                    (usage.email_protection, "emailProtection"),
+                    (usage.time_stamping, "timeStamping"),
+                    (usage.ocsp_signing, "OCSPSigning"),
+                ];
+                extended_key_usage = named
+                    .iter()
+                    .filter(|(present, _)| *present)
+                    .map(|(_, name)| name.to_string())
+                    .chain(usage.other.iter().map(|oid| oid.to_id_string()))
+                    .collect();
+            }
+            ParsedExtension::BasicConstraints(constraints) => is_ca = constraints.ca,
+            ParsedExtension::SCT(scts) => embedded_scts = ct::embedded_scts(scts),
+            ParsedExtension::CertificatePolicies(policies) => {
+                policy_oids = policies
+                    .iter()
+                    .map(|policy| policy.policy_id.to_id_string())
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+
+    let san_domains: Vec<String> = subject_alt_names
+        .iter()
+        .filter_map(|name| match name {
+            SubjectAltName::Dns(dns) => Some(dns.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let subject = distinguished_name(cert.subject());
+    let domain = common_name(&subject)
+        .or_else(|| san_domains.first().cloned())
+        .unwrap_or_default();
+
+    let now = Utc::now();
+    let days_until_expiry = Some(calculate_days_until_expiry(not_after, now));
+    let is_valid = not_before <= now && now < not_after;
 
     Ok(SslCertificateInfo {
-        domain: extract_cn_from_subject(&subject).unwrap_or_default(),
-        issuer,
+        domain,
+        version: cert.version().0 + 1,
+        issuer: distinguished_name(cert.issuer()),
         subject,
-        serial_number: serial,
+        serial_number: cert.raw_serial_as_string(),
+        fingerprint_sha256: fingerprint_sha256(cert_der),
         not_before,
         not_after,
-        signature_algorithm: sig_algo,
+        signature_algorithm: oid_name(&cert.signature_algorithm.algorithm)
+            .unwrap_or_else(|| cert.signature_algorithm.algorithm.to_id_string()),
+        public_key: public_key_info(&cert),
+        subject_alt_names,
         san_domains,
+        key_usage,
+        extended_key_usage,
+        is_ca,
+        policy_oids,
+        embedded_scts,
+        extensions,
         is_valid,
         days_until_expiry,
+        validation_error: None,
+        revocation: None,
     })
 }
 
-fn extract_cert_field(text: &str, field: &str) -> String {
-    text.lines()
-        .find(|line| line.contains(field))
-        .map(|line| line.split(field).nth(1).unwrap_or("").trim().to_string())
-        .unwrap_or_default()
+fn fingerprint_sha256(der: &[u8]) -> String {
+    digest(&SHA256, der)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
 }
 
-fn extract_san_domains(text: &str) -> Vec<String> {
-    let mut domains = Vec::new();
-    let mut in_san_section = false;
+fn asn1_time_to_utc(timestamp: i64) -> Result<DateTime<Utc>> {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .ok_or_else(|| anyhow::anyhow!("Certificate time {} is out of range", timestamp))
+}
 
-    for line in text.lines() {
-        if line.contains("X509v3 Subject Alternative Name:") {
-            in_san_section = true;
-            continue;
-        }
+fn oid_name(oid: &Oid) -> Option<String> {
+    oid2sn(oid, oid_registry())
+        .ok()
+        .map(|name| name.to_string())
+}
 
-        if in_san_section && line.starts_with("                ") {
-            let parts: Vec<&str> = line.split(',').collect();
-            for part in parts {
-                if let Some(dns) = part.trim().strip_prefix("DNS:") {
-                    domains.push(dns.to_string());
-                }
-            }
-            break;
-        }
+fn distinguished_name(name: &X509Name) -> DistinguishedName {
+    let rdns = name
+        .iter_rdn()
+        .map(|rdn| {
+            rdn.iter()
+                .map(|attribute| NameAttribute {
+                    oid: attribute.attr_type().to_id_string(),
+                    name: oid_name(attribute.attr_type()),
+                    // BMPString and friends aren't valid UTF-8 as stored; fall back to a lossy read.
+                    value: attribute
+                        .as_str()
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|_| {
+                            String::from_utf8_lossy(attribute.as_slice()).into_owned()
+                        }),
+                })
+                .collect()
+        })
+        .collect();
+
+    DistinguishedName {
+        text: name.to_string(),
+        rdns,
     }
-
-    domains
 }
 
-fn extract_cn_from_subject(subject: &str) -> Option<String> {
-    subject
-        .split(',')
-        .find(|part| part.trim().starts_with("CN"))
-        .and_then(|cn_part| cn_part.split('=').nth(1))
-        .map(|cn| cn.trim().to_string())
+fn common_name(name: &DistinguishedName) -> Option<String> {
+    name.rdns
+        .iter()
+        .flatten()
+        .find(|attribute| attribute.name.as_deref() == Some("commonName"))
+        .map(|attribute| attribute.value.clone())
 }
 
-fn calculate_days_until_expiry(not_after: &str) -> Option<i64> {
-    use chrono::NaiveDateTime;
+fn subject_alt_name(name: &GeneralName) -> SubjectAltName {
+    match name {
+        GeneralName::DNSName(dns) => SubjectAltName::Dns(dns.to_string()),
+        GeneralName::RFC822Name(email) => SubjectAltName::Email(email.to_string()),
+        GeneralName::URI(uri) => SubjectAltName::Uri(uri.to_string()),
+        GeneralName::IPAddress(bytes) => SubjectAltName::Ip(ip_address(bytes)),
+        GeneralName::DirectoryName(name) => SubjectAltName::DirectoryName(name.to_string()),
+        GeneralName::RegisteredID(oid) => SubjectAltName::RegisteredId(oid.to_id_string()),
+        other => SubjectAltName::Other(format!("{:?}", other)),
+    }
+}
 
-    let formats = ["%b %d %H:%M:%S %Y %Z", "%b %e %H:%M:%S %Y %Z"];
+fn ip_address(bytes: &[u8]) -> String {
+    if let Ok(octets) = <[u8; 4]>::try_from(bytes) {
+        IpAddr::V4(Ipv4Addr::from(octets)).to_string()
+    } else if let Ok(octets) = <[u8; 16]>::try_from(bytes) {
+        IpAddr::V6(Ipv6Addr::from(octets)).to_string()
+    } else {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
 
-    for format in &formats {
-        if let Ok(expiry) = NaiveDateTime::parse_from_str(not_after, format) {
-            let expiry_utc = DateTime::<Utc>::from_naive_utc_and_offset(expiry, Utc);
-            let now = Utc::now();
-            let duration = expiry_utc.signed_duration_since(now);
-            return Some(duration.num_days());
-        }
+fn public_key_info(cert: &X509Certificate) -> PublicKeyInfo {
+    let spki = cert.public_key();
+    let parsed = spki.parsed();
+
+    let algorithm = match &parsed {
+        Ok(PublicKey::RSA(_)) => "RSA".to_string(),
+        Ok(PublicKey::EC(_)) => "EC".to_string(),
+        Ok(PublicKey::DSA(_)) => "DSA".to_string(),
+        _ => oid_name(&spki.algorithm.algorithm)
+            .unwrap_or_else(|| spki.algorithm.algorithm.to_id_string()),
+    };
+
+    let key_size_bits = match &parsed {
+        // Ed25519 and friends: the key size is the raw key length.
+        Ok(PublicKey::Unknown(key)) if !key.is_empty() => Some(key.len() * 8),
+        Ok(key) if key.key_size() > 0 => Some(key.key_size()),
+        _ => None,
+    };
+
+    let curve = match &parsed {
+        Ok(PublicKey::EC(_)) => spki
+            .algorithm
+            .parameters
+            .as_ref()
+            .and_then(|parameters| parameters.as_oid().ok())
+            .map(|oid| oid_name(&oid).unwrap_or_else(|| oid.to_id_string())),
+        _ => None,
+    };
+
+    PublicKeyInfo {
+        algorithm,
+        key_size_bits,
+        curve,
     }
+}
 
-    None
+pub(crate) fn calculate_days_until_expiry(not_after: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+    not_after.signed_duration_since(now).num_days()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const LEAF: &[u8] = include_bytes!("../../tests/fixtures/example-com-leaf.der");
+    const ROOT: &[u8] = include_bytes!("../../tests/fixtures/example-root-ca.der");
+    const EC_SELF_SIGNED: &[u8] = include_bytes!("../../tests/fixtures/ec-self-signed.der");
+    const CT_LEAF: &[u8] = include_bytes!("../../tests/fixtures/ct-example-com-leaf.der");
+
     #[test]
-    fn extract_cert_field_test() {
-        let sample_cert_text = r#"Certificate:
-    Data:
-        Version: 3 (0x2)
-        Serial Number: 12345
-        Signature Algorithm: sha256WithRSAEncryption
-        Issuer: CN=Test CA, O=Test Organization
-        Validity
-            Not Before: Jan  1 12:00:00 2024 GMT
-            Not After : Jan  1 12:00:00 2025 GMT
-        Subject: CN=example.com, O=Example Organization
-"#;
+    fn parse_leaf_certificate_test() {
+        let cert = parse_x509_certificate(LEAF).unwrap();
 
+        assert_eq!(cert.domain, "example.com");
+        assert_eq!(cert.version, 3);
+        assert_eq!(cert.serial_number, "1a:2b:3c:4d");
         assert_eq!(
-            extract_cert_field(sample_cert_text, "Issuer: "),
-            "CN=Test CA, O=Test Organization"
+            cert.fingerprint_sha256,
+            "0c:8c:e2:e6:29:13:28:14:13:98:34:80:7a:4b:74:c5:e5:40:c6:8f:95:4b:0b:af:95:6e:1e:9a:d3:f1:7d:98"
         );
+        assert_eq!(cert.signature_algorithm, "sha256WithRSAEncryption");
         assert_eq!(
-            extract_cert_field(sample_cert_text, "Subject: "),
-            "CN=example.com, O=Example Organization"
+            cert.issuer.text,
+            "C=US, O=Example Trust, CN=Example Root CA"
         );
         assert_eq!(
-            extract_cert_field(sample_cert_text, "Serial Number:"),
-            "12345"
+            cert.subject.rdns[1],
+            vec![NameAttribute {
+                oid: "2.5.4.10".to_string(),
+                name: Some("organizationName".to_string()),
+                value: "Example Widgets, Inc.".to_string(),
+            }]
         );
+        assert!(cert.is_valid);
+        assert!(cert.days_until_expiry.unwrap() > 0);
+        assert!(!cert.is_ca);
+    }
+
+    #[test]
+    fn subject_alt_names_test() {
+        let cert = parse_x509_certificate(LEAF).unwrap();
+
         assert_eq!(
-            extract_cert_field(sample_cert_text, "Not Before:"),
-            "Jan  1 12:00:00 2024 GMT"
+            cert.subject_alt_names,
+            vec![
+                SubjectAltName::Dns("example.com".to_string()),
+                SubjectAltName::Dns("www.example.com".to_string()),
+                SubjectAltName::Dns("*.api.example.com".to_string()),
+                SubjectAltName::Ip("192.0.2.10".to_string()),
+                SubjectAltName::Ip("2001:db8::1".to_string()),
+                SubjectAltName::Email("hostmaster@example.com".to_string()),
+                SubjectAltName::Uri("https://example.com/".to_string()),
+            ]
         );
         assert_eq!(
-            extract_cert_field(sample_cert_text, "Not After :"),
-            "Jan  1 12:00:00 2025 GMT"
+            cert.san_domains,
+            vec!["example.com", "www.example.com", "*.api.example.com"]
         );
+    }
+
+    #[test]
+    fn extensions_and_policies_test() {
+        let cert = parse_x509_certificate(LEAF).unwrap();
+
+        assert_eq!(
+            cert.key_usage,
+            vec!["Digital Signature", "Key Encipherment"]
+        );
+        assert_eq!(cert.extended_key_usage, vec!["serverAuth", "clientAuth"]);
         assert_eq!(
-            extract_cert_field(sample_cert_text, "Signature Algorithm: "),
-            "sha256WithRSAEncryption"
+            cert.policy_oids,
+            vec!["2.23.140.1.2.2", "1.3.6.1.4.1.44947.1.1.1"]
         );
 
-        // Test field that doesn't exist
-        assert_eq!(extract_cert_field(sample_cert_text, "NonExistent: "), "");
+        let basic_constraints = cert
+            .extensions
+            .iter()
+            .find(|extension| extension.oid == "2.5.29.19")
+            .unwrap();
+        assert!(basic_constraints.critical);
+        assert_eq!(basic_constraints.name.as_deref(), Some("basicConstraints"));
+        assert_eq!(cert.extensions.len(), 9);
+
+        let root = parse_x509_certificate(ROOT).unwrap();
+        assert!(root.is_ca);
+        assert_eq!(root.issuer, root.subject);
     }
 
     #[test]
-    fn calculate_days_until_expiry_test() {
-        // Test valid date formats
-        let future_date = "Jan  1 12:00:00 2030 GMT";
-        let days = calculate_days_until_expiry(future_date);
-        assert!(days.is_some());
-        assert!(days.unwrap() > 0);
-
-        let past_date = "Jan  1 12:00:00 2020 GMT";
-        let days = calculate_days_until_expiry(past_date);
-        assert!(days.is_some());
-        assert!(days.unwrap() < 0);
-
-        // Test alternative format
-        let future_date_alt = "Jan 15 12:00:00 2030 GMT";
-        let days = calculate_days_until_expiry(future_date_alt);
-        assert!(days.is_some());
-        assert!(days.unwrap() > 0);
-
-        // Test invalid format
-        let invalid_date = "Invalid Date Format";
-        let days = calculate_days_until_expiry(invalid_date);
-        assert!(days.is_none());
+    fn public_key_test() {
+        let rsa = parse_x509_certificate(LEAF).unwrap();
+        assert_eq!(
+            rsa.public_key,
+            PublicKeyInfo {
+                algorithm: "RSA".to_string(),
+                key_size_bits: Some(2048),
+                curve: None,
+            }
+        );
+
+        let ec = parse_x509_certificate(EC_SELF_SIGNED).unwrap();
+        assert_eq!(ec.public_key.algorithm, "EC");
+        assert_eq!(ec.public_key.key_size_bits, Some(256));
+        assert_eq!(ec.public_key.curve.as_deref(), Some("prime256v1"));
     }
 
     #[test]
-    fn extract_san_domains_test() {
-        let sample_cert_text = r#"Certificate:
-    Data:
-        Version: 3 (0x2)
-        Serial Number: 12345
-        X509v3 extensions:
-            X509v3 Subject Alternative Name:
-                DNS:example.com, DNS:www.example.com, DNS:api.example.com
-            X509v3 Key Usage: critical
-                Digital Signature, Key Encipherment
-"#;
-
-        let domains = extract_san_domains(sample_cert_text);
-        assert_eq!(domains.len(), 3);
-        assert!(domains.contains(&"example.com".to_string()));
-        assert!(domains.contains(&"www.example.com".to_string()));
-        assert!(domains.contains(&"api.example.com".to_string()));
-
-        // Test certificate without SAN
-        let no_san_cert = r#"Certificate:
-    Data:
-        Version: 3 (0x2)
-        Serial Number: 12345
-        X509v3 extensions:
-            X509v3 Key Usage: critical
-                Digital Signature, Key Encipherment
-"#;
-
-        let no_domains = extract_san_domains(no_san_cert);
-        assert_eq!(no_domains.len(), 0);
+    fn multi_valued_rdn_test() {
+        let cert = parse_x509_certificate(EC_SELF_SIGNED).unwrap();
+
+        assert_eq!(cert.domain, "ec.example.net");
+        let values: Vec<Vec<&str>> = cert
+            .subject
+            .rdns
+            .iter()
+            .map(|rdn| rdn.iter().map(|a| a.value.as_str()).collect())
+            .collect();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0], vec!["ec.example.net"]);
+        assert_eq!(values[1].len(), 2);
     }
 
     #[test]
-    fn extract_cn_from_subject_test() {
-        let subject = "CN=example.com, O=Example Organization, C=US";
-        assert_eq!(
-            extract_cn_from_subject(subject),
-            Some("example.com".to_string())
-        );
-
-        let subject_no_cn = "O=Example Organization, C=US";
-        assert_eq!(extract_cn_from_subject(subject_no_cn), None);
+    fn embedded_scts_test() {
+        let cert = parse_x509_certificate(CT_LEAF).unwrap();
+
+        assert_eq!(cert.embedded_scts.len(), 2);
+        let sct = &cert.embedded_scts[0];
+        assert_eq!(sct.version, 1);
+        assert_eq!(sct.log_id, "oaGhoaGhoaGhoaGhoaGhoaGhoaGhoaGhoaGhoaGhoaE=");
+        assert_eq!(sct.timestamp.timestamp_millis(), 1_700_000_000_123);
+        assert_eq!(sct.signature_algorithm, "ecdsa-with-SHA256");
+        assert_eq!(cert.embedded_scts[1].signature_algorithm, "rsa-with-SHA256");
+
+        assert!(parse_x509_certificate(LEAF)
+            .unwrap()
+            .embedded_scts
+            .is_empty());
+    }
 
-        let subject_different_order = "O=Example Organization, CN=test.com, C=US";
-        assert_eq!(
-            extract_cn_from_subject(subject_different_order),
-            Some("test.com".to_string())
-        );
+    #[test]
+    fn parse_invalid_certificate_test() {
+        assert!(parse_x509_certificate(b"not a certificate").is_err());
+    }
 
-        let empty_subject = "";
-        assert_eq!(extract_cn_from_subject(empty_subject), None);
+    #[test]
+    fn calculate_days_until_expiry_test() {
+        let now = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let future = DateTime::parse_from_rfc3339("2025-01-31T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let past = DateTime::parse_from_rfc3339("2024-12-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(calculate_days_until_expiry(future, now), 30);
+        assert_eq!(calculate_days_until_expiry(past, now), -31);
     }
 }
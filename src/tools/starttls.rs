@@ -0,0 +1,512 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// How to reach the TLS layer of a service: directly, or by upgrading a
+/// plaintext session with the protocol's STARTTLS command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    Tls,
+    Smtp,
+    Imap,
+    Pop3,
+    Ftp,
+    Xmpp,
+    Ldap,
+    Postgres,
+}
+
+/// Upper bound on a single protocol line or XML chunk we are willing to buffer.
+const MAX_RESPONSE: usize = 64 * 1024;
+
+const LDAP_START_TLS_OID: &[u8] = b"1.3.6.1.4.1.1466.20037";
+
+impl Protocol {
+    /// Parse a protocol name and return it with the service's usual port.
+    /// Implicit-TLS names such as "smtps" map to `Tls` on their own port.
+    pub fn parse(name: &str) -> Result<(Self, u16)> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "tls" | "https" => Ok((Protocol::Tls, 443)),
+            "smtps" => Ok((Protocol::Tls, 465)),
+            "imaps" => Ok((Protocol::Tls, 993)),
+            "pop3s" => Ok((Protocol::Tls, 995)),
+            "ftps" => Ok((Protocol::Tls, 990)),
+            "ldaps" => Ok((Protocol::Tls, 636)),
+            "smtp" => Ok((Protocol::Smtp, 25)),
+            "submission" => Ok((Protocol::Smtp, 587)),
+            "imap" => Ok((Protocol::Imap, 143)),
+            "pop3" => Ok((Protocol::Pop3, 110)),
+            "ftp" => Ok((Protocol::Ftp, 21)),
+            "xmpp" => Ok((Protocol::Xmpp, 5222)),
+            "ldap" => Ok((Protocol::Ldap, 389)),
+            "postgres" | "postgresql" => Ok((Protocol::Postgres, 5432)),
+            _ => Err(anyhow::anyhow!(
+                "Unknown protocol '{}'. Use tls, https, smtp, submission, imap, pop3, ftp, xmpp, ldap, postgres, smtps, imaps, pop3s, ftps or ldaps",
+                name
+            )),
+        }
+    }
+}
+
+/// Run the plaintext part of `protocol` on `stream` until the server is ready
+/// for a TLS ClientHello. Reads byte by byte so nothing past the server's
+/// go-ahead is consumed.
+pub async fn upgrade<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    protocol: Protocol,
+    host: &str,
+) -> Result<()> {
+    match protocol {
+        Protocol::Tls => Ok(()),
+        Protocol::Smtp => upgrade_smtp(stream).await,
+        Protocol::Imap => upgrade_imap(stream).await,
+        Protocol::Pop3 => upgrade_pop3(stream).await,
+        Protocol::Ftp => upgrade_ftp(stream).await,
+        Protocol::Xmpp => upgrade_xmpp(stream, host).await,
+        Protocol::Ldap => upgrade_ldap(stream).await,
+        Protocol::Postgres => upgrade_postgres(stream).await,
+    }
+}
+
+async fn upgrade_smtp<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<()> {
+    expect_reply(stream, "220", "SMTP greeting").await?;
+    stream.write_all(b"EHLO domain-mcp\r\n").await?;
+    let capabilities = expect_reply(stream, "250", "EHLO").await?;
+    if !capabilities.iter().any(|line| {
+        line.get(4..)
+            .unwrap_or("")
+            .trim()
+            .eq_ignore_ascii_case("STARTTLS")
+    }) {
+        return Err(anyhow::anyhow!("SMTP server does not offer STARTTLS"));
+    }
+    stream.write_all(b"STARTTLS\r\n").await?;
+    expect_reply(stream, "220", "STARTTLS").await?;
+    Ok(())
+}
+
+async fn upgrade_ftp<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<()> {
+    expect_reply(stream, "220", "FTP greeting").await?;
+    stream.write_all(b"AUTH TLS\r\n").await?;
+    expect_reply(stream, "234", "AUTH TLS").await?;
+    Ok(())
+}
+
+async fn upgrade_imap<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<()> {
+    let greeting = read_line(stream).await?;
+    if !greeting.starts_with("* OK") {
+        return Err(anyhow::anyhow!("Unexpected IMAP greeting: {}", greeting));
+    }
+    stream.write_all(b"a1 STARTTLS\r\n").await?;
+    loop {
+        let line = read_line(stream).await?;
+        if let Some(status) = line.strip_prefix("a1 ") {
+            if status.starts_with("OK") {
+                return Ok(());
+            }
+            return Err(anyhow::anyhow!("IMAP STARTTLS refused: {}", status));
+        }
+    }
+}
+
+async fn upgrade_pop3<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<()> {
+    let greeting = read_line(stream).await?;
+    if !greeting.starts_with("+OK") {
+        return Err(anyhow::anyhow!("Unexpected POP3 greeting: {}", greeting));
+    }
+    stream.write_all(b"STLS\r\n").await?;
+    let reply = read_line(stream).await?;
+    if !reply.starts_with("+OK") {
+        return Err(anyhow::anyhow!("POP3 STLS refused: {}", reply));
+    }
+    Ok(())
+}
+
+async fn upgrade_xmpp<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, host: &str) -> Result<()> {
+    // The host goes into an XML attribute; no valid domain needs these.
+    if host.contains(['\'', '"', '<', '>', '&']) {
+        return Err(anyhow::anyhow!("'{}' is not a valid XMPP domain", host));
+    }
+    let header = format!(
+        "<?xml version='1.0'?><stream:stream to='{}' xmlns='jabber:client' \
+         xmlns:stream='http://etherx.jabber.org/streams' version='1.0'>",
+        host
+    );
+    stream.write_all(header.as_bytes()).await?;
+
+    let features = read_until_any(stream, &["</stream:features>", "</stream:stream>"]).await?;
+    if !features.contains("urn:ietf:params:xml:ns:xmpp-tls") {
+        return Err(anyhow::anyhow!("XMPP server does not offer STARTTLS"));
+    }
+
+    stream
+        .write_all(b"<starttls xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>")
+        .await?;
+    let reply = read_until_any(stream, &["<proceed", "<failure"]).await?;
+    if reply.ends_with("<failure") {
+        return Err(anyhow::anyhow!("XMPP STARTTLS refused"));
+    }
+    // Consume the rest of the <proceed .../> element.
+    read_until_any(stream, &[">"]).await?;
+    Ok(())
+}
+
+async fn upgrade_ldap<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<()> {
+    // LDAPMessage { messageID 1, ExtendedRequest { requestName StartTLS } }
+    let mut request_name = vec![0x80, LDAP_START_TLS_OID.len() as u8];
+    request_name.extend_from_slice(LDAP_START_TLS_OID);
+    let mut extended_request = vec![0x77, request_name.len() as u8];
+    extended_request.extend_from_slice(&request_name);
+    let mut message = vec![0x30, (extended_request.len() + 3) as u8, 0x02, 0x01, 0x01];
+    message.extend_from_slice(&extended_request);
+    stream.write_all(&message).await?;
+
+    let response = read_ber_element(stream).await?;
+    // SEQUENCE { INTEGER id, [APPLICATION 24] { ENUMERATED resultCode, ... } }
+    let result_code = find_ldap_result_code(&response)
+        .ok_or_else(|| anyhow::anyhow!("Malformed LDAP StartTLS response"))?;
+    if result_code != 0 {
+        return Err(anyhow::anyhow!(
+            "LDAP StartTLS refused with result code {}",
+            result_code
+        ));
+    }
+    Ok(())
+}
+
+async fn upgrade_postgres<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<()> {
+    // SSLRequest: length 8, code 80877103
+    stream
+        .write_all(&[0x00, 0x00, 0x00, 0x08, 0x04, 0xd2, 0x16, 0x2f])
+        .await?;
+    let mut reply = [0u8; 1];
+    stream.read_exact(&mut reply).await?;
+    match reply[0] {
+        b'S' => Ok(()),
+        b'N' => Err(anyhow::anyhow!("PostgreSQL server does not accept SSL")),
+        other => Err(anyhow::anyhow!(
+            "Unexpected PostgreSQL SSLRequest reply 0x{:02x}",
+            other
+        )),
+    }
+}
+
+async fn read_line<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        if stream.read(&mut byte).await? == 0 {
+            return Err(anyhow::anyhow!(
+                "Connection closed during STARTTLS negotiation"
+            ));
+        }
+        if byte[0] == b'\n' {
+            break;
+        }
+        line.push(byte[0]);
+        if line.len() > MAX_RESPONSE {
+            return Err(anyhow::anyhow!("STARTTLS response line too long"));
+        }
+    }
+    Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+}
+
+/// Read an SMTP/FTP style reply, following "250-" continuation lines, and
+/// fail unless it carries `code`.
+async fn expect_reply<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    code: &str,
+    step: &str,
+) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    loop {
+        let line = read_line(stream).await?;
+        let done = line.len() < 4 || line.as_bytes()[3] != b'-';
+        if !line.starts_with(code) {
+            return Err(anyhow::anyhow!("{} failed: {}", step, line));
+        }
+        lines.push(line);
+        if done {
+            return Ok(lines);
+        }
+    }
+}
+
+async fn read_until_any<S: AsyncRead + Unpin>(stream: &mut S, markers: &[&str]) -> Result<String> {
+    let mut buffer = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        if stream.read(&mut byte).await? == 0 {
+            return Err(anyhow::anyhow!(
+                "Connection closed during STARTTLS negotiation"
+            ));
+        }
+        buffer.push(byte[0]);
+        if markers
+            .iter()
+            .any(|marker| buffer.ends_with(marker.as_bytes()))
+        {
+            return Ok(String::from_utf8_lossy(&buffer).into_owned());
+        }
+        if buffer.len() > MAX_RESPONSE {
+            return Err(anyhow::anyhow!("STARTTLS response too long"));
+        }
+    }
+}
+
+/// Read one complete BER TLV from the stream.
+async fn read_ber_element<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await?;
+    let mut element = header.to_vec();
+
+    let length = if header[1] & 0x80 == 0 {
+        header[1] as usize
+    } else {
+        let count = (header[1] & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            return Err(anyhow::anyhow!("Unsupported BER length encoding"));
+        }
+        let mut bytes = vec![0u8; count];
+        stream.read_exact(&mut bytes).await?;
+        element.extend_from_slice(&bytes);
+        bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize)
+    };
+    if length > MAX_RESPONSE {
+        return Err(anyhow::anyhow!("LDAP response too long"));
+    }
+
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body).await?;
+    element.extend_from_slice(&body);
+    Ok(element)
+}
+
+/// Walk SEQUENCE -> messageID -> ExtendedResponse and return its resultCode.
+fn find_ldap_result_code(message: &[u8]) -> Option<u8> {
+    fn header(data: &[u8]) -> Option<(u8, usize, usize)> {
+        let tag = *data.first()?;
+        let first = *data.get(1)? as usize;
+        if first & 0x80 == 0 {
+            Some((tag, 2, first))
+        } else {
+            let count = first & 0x7f;
+            let length = data
+                .get(2..2 + count)?
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+            Some((tag, 2 + count, length))
+        }
+    }
+
+    let (tag, offset, _) = header(message)?;
+    if tag != 0x30 {
+        return None;
+    }
+    let rest = &message[offset..];
+    let (_, id_offset, id_length) = header(rest)?;
+    let rest = rest.get(id_offset + id_length..)?;
+    let (tag, response_offset, _) = header(rest)?;
+    if tag != 0x78 {
+        return None;
+    }
+    let rest = &rest[response_offset..];
+    let (tag, code_offset, _) = header(rest)?;
+    if tag != 0x0a {
+        return None;
+    }
+    rest.get(code_offset).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::ReadBuf;
+
+    struct ScriptedStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl ScriptedStream {
+        fn new(input: &[u8]) -> Self {
+            ScriptedStream {
+                input: Cursor::new(input.to_vec()),
+                output: Vec::new(),
+            }
+        }
+
+        fn remaining(&self) -> usize {
+            self.input.get_ref().len() - self.input.position() as usize
+        }
+    }
+
+    impl AsyncRead for ScriptedStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            let read = Read::read(&mut self.get_mut().input, buf.initialize_unfilled())?;
+            buf.advance(read);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for ScriptedStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.get_mut().output.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn parse_protocol_test() {
+        assert_eq!(Protocol::parse("smtp").unwrap(), (Protocol::Smtp, 25));
+        assert_eq!(Protocol::parse("IMAPS").unwrap(), (Protocol::Tls, 993));
+        assert_eq!(
+            Protocol::parse("postgresql").unwrap(),
+            (Protocol::Postgres, 5432)
+        );
+        assert_eq!(Protocol::parse("").unwrap(), (Protocol::Tls, 443));
+        assert!(Protocol::parse("gopher").is_err());
+    }
+
+    #[tokio::test]
+    async fn smtp_upgrade_test() {
+        // The trailing byte stands in for the server's first TLS record and
+        // must be left unread.
+        let mut stream = ScriptedStream::new(
+            b"220 mx.example.com ESMTP\r\n250-mx.example.com\r\n250-PIPELINING\r\n250-STARTTLS\r\n250 8BITMIME\r\n220 2.0.0 Ready to start TLS\r\n\x16",
+        );
+        upgrade(&mut stream, Protocol::Smtp, "mx.example.com")
+            .await
+            .unwrap();
+
+        assert_eq!(stream.output, b"EHLO domain-mcp\r\nSTARTTLS\r\n");
+        assert_eq!(stream.remaining(), 1);
+    }
+
+    #[tokio::test]
+    async fn smtp_without_starttls_test() {
+        let mut stream = ScriptedStream::new(
+            b"220 mx.example.com ESMTP\r\n250-mx.example.com\r\n250 8BITMIME\r\n",
+        );
+        let error = upgrade(&mut stream, Protocol::Smtp, "mx.example.com")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("does not offer STARTTLS"));
+    }
+
+    #[tokio::test]
+    async fn imap_pop3_ftp_upgrade_test() {
+        let mut imap = ScriptedStream::new(
+            b"* OK [CAPABILITY IMAP4rev1 STARTTLS] ready\r\na1 OK Begin TLS negotiation now\r\n",
+        );
+        upgrade(&mut imap, Protocol::Imap, "imap.example.com")
+            .await
+            .unwrap();
+        assert_eq!(imap.output, b"a1 STARTTLS\r\n");
+
+        let mut pop3 = ScriptedStream::new(b"+OK POP3 ready\r\n+OK Begin TLS\r\n");
+        upgrade(&mut pop3, Protocol::Pop3, "pop.example.com")
+            .await
+            .unwrap();
+        assert_eq!(pop3.output, b"STLS\r\n");
+
+        let mut ftp = ScriptedStream::new(b"220-Welcome\r\n220 FTP ready\r\n234 AUTH TLS OK\r\n");
+        upgrade(&mut ftp, Protocol::Ftp, "ftp.example.com")
+            .await
+            .unwrap();
+        assert_eq!(ftp.output, b"AUTH TLS\r\n");
+
+        let mut refused = ScriptedStream::new(b"* OK ready\r\na1 BAD unknown command\r\n");
+        assert!(upgrade(&mut refused, Protocol::Imap, "imap.example.com")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn xmpp_upgrade_test() {
+        let mut stream = ScriptedStream::new(
+            b"<?xml version='1.0'?><stream:stream from='example.com' id='1' version='1.0'>\
+              <stream:features><starttls xmlns='urn:ietf:params:xml:ns:xmpp-tls'><required/></starttls>\
+              </stream:features><proceed xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>\x16",
+        );
+        upgrade(&mut stream, Protocol::Xmpp, "example.com")
+            .await
+            .unwrap();
+
+        let sent = String::from_utf8(stream.output.clone()).unwrap();
+        assert!(sent.contains("to='example.com'"));
+        assert!(sent.ends_with("<starttls xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>"));
+        assert_eq!(stream.remaining(), 1);
+
+        let mut stream = ScriptedStream::new(b"");
+        assert!(
+            upgrade(&mut stream, Protocol::Xmpp, "example.com' xmlns='evil")
+                .await
+                .is_err()
+        );
+        assert!(stream.output.is_empty());
+    }
+
+    #[tokio::test]
+    async fn ldap_upgrade_test() {
+        // ExtendedResponse { resultCode success, matchedDN "", diagnosticMessage "" }
+        let success = [
+            0x30, 0x0c, 0x02, 0x01, 0x01, 0x78, 0x07, 0x0a, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00,
+        ];
+        let mut stream = ScriptedStream::new(&success);
+        upgrade(&mut stream, Protocol::Ldap, "ldap.example.com")
+            .await
+            .unwrap();
+        assert_eq!(stream.output[0], 0x30);
+        assert_eq!(stream.output[1] as usize, stream.output.len() - 2);
+        assert!(stream
+            .output
+            .windows(LDAP_START_TLS_OID.len())
+            .any(|window| window == LDAP_START_TLS_OID));
+
+        let mut refused = success;
+        refused[9] = 0x02;
+        let mut stream = ScriptedStream::new(&refused);
+        let error = upgrade(&mut stream, Protocol::Ldap, "ldap.example.com")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("result code 2"));
+    }
+
+    #[tokio::test]
+    async fn postgres_upgrade_test() {
+        let mut stream = ScriptedStream::new(b"S");
+        upgrade(&mut stream, Protocol::Postgres, "db.example.com")
+            .await
+            .unwrap();
+        assert_eq!(
+            stream.output,
+            vec![0x00, 0x00, 0x00, 0x08, 0x04, 0xd2, 0x16, 0x2f]
+        );
+
+        let mut stream = ScriptedStream::new(b"N");
+        assert!(upgrade(&mut stream, Protocol::Postgres, "db.example.com")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,434 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::domain::{self, AvailabilityVerdict};
+use super::words;
+
+const PREFIXES: &[&str] = &["get", "try", "my", "go", "the", "use", "hey", "join"];
+const SUFFIXES: &[&str] = &[
+    "hq", "app", "hub", "ly", "ify", "labs", "now", "base", "kit", "io",
+];
+const DEFAULT_TLDS: &[&str] = &["com", "io", "co", "net", "app", "dev"];
+/// ccTLDs commonly used to spell a word across the dot ("delicio.us").
+const HACK_TLDS: &[&str] = &[
+    "us", "ly", "io", "me", "co", "it", "is", "at", "to", "in", "es", "ai",
+];
+
+const DEFAULT_LIMIT: usize = 20;
+const DEFAULT_MAX_CANDIDATES: usize = 60;
+
+/// How a candidate label was derived from the seed keywords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    Exact,
+    Compound,
+    DomainHack,
+    Synonym,
+    Plural,
+    Prefix,
+    Suffix,
+    Blend,
+    Hyphenated,
+}
+
+impl Strategy {
+    fn weight(self) -> f64 {
+        match self {
+            Strategy::Exact => 1.0,
+            Strategy::Compound => 0.9,
+            Strategy::DomainHack => 0.85,
+            Strategy::Synonym => 0.8,
+            Strategy::Plural => 0.75,
+            Strategy::Prefix | Strategy::Suffix => 0.7,
+            Strategy::Blend => 0.6,
+            Strategy::Hyphenated => 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    pub domain: String,
+    pub strategy: Strategy,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DomainSuggestion {
+    pub domain: String,
+    pub strategy: Strategy,
+    pub score: f64,
+    pub confidence: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuggestionResult {
+    pub keywords: Vec<String>,
+    pub candidates_generated: usize,
+    pub candidates_checked: usize,
+    pub suggestions: Vec<DomainSuggestion>,
+}
+
+pub async fn suggest_domains(
+    keywords: &[String],
+    tlds: Option<&[String]>,
+    limit: Option<usize>,
+    max_candidates: Option<usize>,
+) -> Result<SuggestionResult> {
+    let tlds: Vec<String> = match tlds {
+        Some(tlds) if !tlds.is_empty() => tlds
+            .iter()
+            .map(|tld| tld.trim().trim_start_matches('.').to_lowercase())
+            .collect(),
+        _ => DEFAULT_TLDS.iter().map(|tld| tld.to_string()).collect(),
+    };
+
+    let candidates = generate_candidates(keywords, &tlds);
+    if candidates.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No valid domain candidates could be generated from the keywords"
+        ));
+    }
+
+    let candidates_generated = candidates.len();
+    let to_check: Vec<Candidate> = candidates
+        .into_iter()
+        .take(max_candidates.unwrap_or(DEFAULT_MAX_CANDIDATES))
+        .collect();
+
+    let bulk = domain::bulk_check(to_check.iter().map(|c| c.domain.clone()).collect()).await?;
+    let availability: HashMap<&str, f64> = bulk
+        .domains
+        .iter()
+        .filter(|d| d.verdict == AvailabilityVerdict::Available)
+        .map(|d| (d.domain.as_str(), d.confidence))
+        .collect();
+
+    let suggestions = to_check
+        .iter()
+        .filter_map(|candidate| {
+            availability
+                .get(candidate.domain.as_str())
+                .map(|confidence| DomainSuggestion {
+                    domain: candidate.domain.clone(),
+                    strategy: candidate.strategy,
+                    score: candidate.score,
+                    confidence: *confidence,
+                })
+        })
+        .take(limit.unwrap_or(DEFAULT_LIMIT))
+        .collect();
+
+    Ok(SuggestionResult {
+        keywords: keywords.to_vec(),
+        candidates_generated,
+        candidates_checked: to_check.len(),
+        suggestions,
+    })
+}
+
+/// Generate candidate domains for the seed keywords across `tlds`, ranked by
+/// score (highest first). TLDs earlier in the list are preferred.
+pub fn generate_candidates(keywords: &[String], tlds: &[String]) -> Vec<Candidate> {
+    let phrases: Vec<Vec<String>> = keywords
+        .iter()
+        .map(|keyword| keyword_words(keyword))
+        .filter(|words| !words.is_empty())
+        .collect();
+
+    // Labels are recorded with the first (strongest) strategy that produced them.
+    let mut labels: Vec<(String, Strategy)> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut add = |label: String, strategy: Strategy| {
+        if is_valid_label(&label) && seen.insert(label.clone()) {
+            labels.push((label, strategy));
+        }
+    };
+
+    let mut base_words = Vec::new();
+    for words in &phrases {
+        if words.len() == 1 {
+            add(words[0].clone(), Strategy::Exact);
+            base_words.push(words[0].clone());
+        } else {
+            add(words.concat(), Strategy::Compound);
+            add(words.join("-"), Strategy::Hyphenated);
+        }
+    }
+
+    for (i, a) in base_words.iter().enumerate() {
+        for b in base_words.iter().skip(i + 1) {
+            add(format!("{}{}", a, b), Strategy::Compound);
+            add(format!("{}{}", b, a), Strategy::Compound);
+        }
+    }
+
+    for word in &base_words {
+        if let Some((label, tld)) = domain_hack(word) {
+            add(format!("{}.{}", label, tld), Strategy::DomainHack);
+        }
+    }
+
+    let mut synonym_words = Vec::new();
+    for word in &base_words {
+        for synonym in words::synonyms(word) {
+            add(synonym.to_string(), Strategy::Synonym);
+            synonym_words.push(synonym.to_string());
+        }
+    }
+
+    for word in base_words.iter().chain(synonym_words.iter()) {
+        add(pluralize(word), Strategy::Plural);
+    }
+
+    for word in &base_words {
+        for prefix in PREFIXES {
+            add(format!("{}{}", prefix, word), Strategy::Prefix);
+        }
+        for suffix in SUFFIXES {
+            add(with_suffix(word, suffix), Strategy::Suffix);
+        }
+    }
+
+    for (i, a) in base_words.iter().enumerate() {
+        for (j, b) in base_words.iter().enumerate() {
+            if i != j {
+                if let Some(blended) = blend(a, b) {
+                    add(blended, Strategy::Blend);
+                }
+            }
+        }
+    }
+
+    for (i, a) in base_words.iter().enumerate() {
+        for b in base_words.iter().skip(i + 1) {
+            add(format!("{}-{}", a, b), Strategy::Hyphenated);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (label, strategy) in labels {
+        if strategy == Strategy::DomainHack {
+            // The hack already carries its own TLD.
+            candidates.push(Candidate {
+                score: score(&label, strategy, 0),
+                domain: label,
+                strategy,
+            });
+            continue;
+        }
+        for (index, tld) in tlds.iter().enumerate() {
+            candidates.push(Candidate {
+                domain: format!("{}.{}", label, tld),
+                strategy,
+                score: score(&label, strategy, index),
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+fn keyword_words(keyword: &str) -> Vec<String> {
+    keyword
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_' || c == '.')
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn is_valid_label(label: &str) -> bool {
+    let name = label.split('.').next().unwrap_or(label);
+    !name.is_empty()
+        && name.len() <= 63
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && !name.contains("--")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+fn score(label: &str, strategy: Strategy, tld_index: usize) -> f64 {
+    let name_length = label.split('.').next().unwrap_or(label).len();
+    let tld_weight = (1.0 - 0.05 * tld_index as f64).max(0.6);
+    let length_weight = if name_length <= 6 {
+        1.0
+    } else {
+        (1.0 - 0.05 * (name_length - 6) as f64).max(0.4)
+    };
+
+    let score = strategy.weight() * tld_weight * length_weight;
+    (score * 100.0).round() / 100.0
+}
+
+fn pluralize(word: &str) -> String {
+    let ends_with_consonant_y = word.ends_with('y')
+        && word
+            .chars()
+            .rev()
+            .nth(1)
+            .is_some_and(|c| !"aeiou".contains(c));
+
+    if word.ends_with('s')
+        || word.ends_with('x')
+        || word.ends_with('z')
+        || word.ends_with("ch")
+        || word.ends_with("sh")
+    {
+        format!("{}es", word)
+    } else if ends_with_consonant_y {
+        format!("{}ies", &word[..word.len() - 1])
+    } else {
+        format!("{}s", word)
+    }
+}
+
+fn with_suffix(word: &str, suffix: &str) -> String {
+    // "cloud" + "dash" shares its "d" and becomes "cloudash"; anything else
+    // is joined unchanged so the seed stays a real word.
+    match (word.chars().last(), suffix.chars().next()) {
+        (Some(last), Some(first)) if last == first => format!("{}{}", word, &suffix[1..]),
+        _ => format!("{}{}", word, suffix),
+    }
+}
+
+/// Portmanteau of two words: merge on a shared overlap when there is one
+/// ("smart" + "artist" → "smartist"), otherwise join the front of `a` to the
+/// back of `b`.
+fn blend(a: &str, b: &str) -> Option<String> {
+    if a.len() < 3 || b.len() < 3 {
+        return None;
+    }
+
+    for overlap in (2..a.len().min(b.len())).rev() {
+        if a.ends_with(&b[..overlap]) {
+            return Some(format!("{}{}", a, &b[overlap..]));
+        }
+    }
+
+    let front = &a[..a.len().div_ceil(2).max(3).min(a.len())];
+    let back = &b[b.len() / 2..];
+    let blended = format!("{}{}", front, back);
+
+    if blended == a || blended == b || blended.len() < 4 {
+        None
+    } else {
+        Some(blended)
+    }
+}
+
+/// Split a word across the dot when it ends in a ccTLD ("delicious" →
+/// "delicio.us").
+fn domain_hack(word: &str) -> Option<(String, &'static str)> {
+    HACK_TLDS
+        .iter()
+        .find(|tld| word.len() >= tld.len() + 3 && word.ends_with(*tld))
+        .map(|tld| (word[..word.len() - tld.len()].to_string(), *tld))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn pluralize_test() {
+        assert_eq!(pluralize("cloud"), "clouds");
+        assert_eq!(pluralize("box"), "boxes");
+        assert_eq!(pluralize("bench"), "benches");
+        assert_eq!(pluralize("city"), "cities");
+        assert_eq!(pluralize("day"), "days");
+    }
+
+    #[test]
+    fn blend_test() {
+        assert_eq!(blend("smart", "artist"), Some("smartist".to_string()));
+        assert_eq!(blend("cloud", "kitchen"), Some("clochen".to_string()));
+        assert_eq!(blend("ab", "kitchen"), None);
+    }
+
+    #[test]
+    fn with_suffix_test() {
+        assert_eq!(with_suffix("shop", "ify"), "shopify");
+        assert_eq!(with_suffix("cloud", "hq"), "cloudhq");
+        // Only a letter the seed and suffix share is merged.
+        assert_eq!(with_suffix("cloud", "dash"), "cloudash");
+        assert_eq!(with_suffix("snap", "pay"), "snapay");
+        assert_eq!(with_suffix("easy", "app"), "easyapp");
+        assert_eq!(with_suffix("cube", "ify"), "cubeify");
+    }
+
+    #[test]
+    fn domain_hack_test() {
+        assert_eq!(
+            domain_hack("delicious"),
+            Some(("delicio".to_string(), "us"))
+        );
+        assert_eq!(domain_hack("cloud"), None);
+    }
+
+    #[test]
+    fn is_valid_label_test() {
+        assert!(is_valid_label("cloud-kitchen"));
+        assert!(is_valid_label("delicio.us"));
+        assert!(!is_valid_label("-cloud"));
+        assert!(!is_valid_label("cloud--kitchen"));
+        assert!(!is_valid_label(&"a".repeat(64)));
+    }
+
+    #[test]
+    fn generate_candidates_test() {
+        let candidates =
+            generate_candidates(&strings(&["cloud", "Kitchen"]), &strings(&["com", "io"]));
+
+        let find = |domain: &str| candidates.iter().find(|c| c.domain == domain);
+
+        assert_eq!(find("cloud.com").unwrap().strategy, Strategy::Exact);
+        assert_eq!(
+            find("cloudkitchen.com").unwrap().strategy,
+            Strategy::Compound
+        );
+        assert_eq!(
+            find("cloud-kitchen.io").unwrap().strategy,
+            Strategy::Hyphenated
+        );
+        assert_eq!(find("clouds.com").unwrap().strategy, Strategy::Plural);
+        assert_eq!(find("getcloud.com").unwrap().strategy, Strategy::Prefix);
+        assert_eq!(find("cloudhq.com").unwrap().strategy, Strategy::Suffix);
+        assert_eq!(find("sky.com").unwrap().strategy, Strategy::Synonym);
+        assert!(candidates.iter().any(|c| c.strategy == Strategy::Blend));
+
+        // Ranked best first, with the preferred TLD ahead of the alternative.
+        assert_eq!(candidates[0].domain, "cloud.com");
+        assert!(find("cloud.com").unwrap().score > find("cloud.io").unwrap().score);
+        assert!(candidates
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        let unique: HashSet<&str> = candidates.iter().map(|c| c.domain.as_str()).collect();
+        assert_eq!(unique.len(), candidates.len());
+    }
+
+    #[test]
+    fn generate_candidates_multiword_keyword_test() {
+        let candidates = generate_candidates(&strings(&["food truck"]), &strings(&["com"]));
+        assert!(candidates
+            .iter()
+            .any(|c| c.domain == "foodtruck.com" && c.strategy == Strategy::Compound));
+        assert!(candidates.iter().any(|c| c.domain == "food-truck.com"));
+
+        assert!(generate_candidates(&strings(&["  ", "!!"]), &strings(&["com"])).is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+pub const POPULAR: &[&str] = &[
+    "com", "net", "org", "io", "co", "ai", "app", "dev", "info", "biz", "me", "xyz", "online",
+    "site", "tech", "store", "shop", "us",
+];
+
+pub const COUNTRY_CODE: &[&str] = &[
+    "us", "uk", "ca", "de", "fr", "es", "it", "nl", "be", "ch", "at", "se", "no", "dk", "fi", "pl",
+    "pt", "ie", "au", "nz", "jp", "in", "br", "mx", "co", "io", "me", "tv", "ai", "ly",
+];
+
+pub const NEW_GENERIC: &[&str] = &[
+    "app", "dev", "xyz", "online", "site", "tech", "store", "shop", "blog", "cloud", "page",
+    "agency", "design", "studio", "digital", "email", "live", "life", "world", "space", "website",
+    "fun", "art", "club",
+];
+
+/// A set of TLDs to check a label against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TldSet {
+    Explicit(Vec<String>),
+    Popular,
+    CountryCode,
+    NewGeneric,
+    /// Every TLD listed in the IANA RDAP bootstrap registry.
+    All,
+}
+
+impl TldSet {
+    /// Parse a named group: "popular", "ccTLDs", "new gTLDs" or "all".
+    pub fn parse(name: &str) -> Result<Self> {
+        let normalized: String = name
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+
+        match normalized.as_str() {
+            "popular" => Ok(TldSet::Popular),
+            "cctld" | "cctlds" | "countrycode" => Ok(TldSet::CountryCode),
+            "newgtld" | "newgtlds" | "gtlds" => Ok(TldSet::NewGeneric),
+            "all" | "allfromthebootstrap" | "bootstrap" => Ok(TldSet::All),
+            _ => Err(anyhow::anyhow!(
+                "Unknown TLD set '{}'. Use \"popular\", \"ccTLDs\", \"new gTLDs\", \"all\" or an explicit list",
+                name
+            )),
+        }
+    }
+
+    /// The set chosen by a tool call: an explicit list or a named group, not
+    /// both. Neither means "popular".
+    pub fn from_params(tlds: Option<Vec<String>>, tld_set: Option<&str>) -> Result<Self> {
+        match (tlds.filter(|tlds| !tlds.is_empty()), tld_set) {
+            (Some(_), Some(_)) => Err(anyhow::anyhow!(
+                "Pass either an explicit TLD list or a named TLD set, not both"
+            )),
+            (Some(tlds), None) => Ok(TldSet::Explicit(tlds)),
+            (None, Some(name)) => TldSet::parse(name),
+            (None, None) => Ok(TldSet::Popular),
+        }
+    }
+
+    /// Expand the set into TLDs. `All` needs the bootstrap registry.
+    pub fn resolve(&self, bootstrap: Option<&HashMap<String, String>>) -> Result<Vec<String>> {
+        let tlds: Vec<String> = match self {
+            TldSet::Explicit(tlds) => tlds
+                .iter()
+                .map(|tld| tld.trim().trim_start_matches('.').to_lowercase())
+                .filter(|tld| !tld.is_empty())
+                .collect(),
+            TldSet::Popular => POPULAR.iter().map(|tld| tld.to_string()).collect(),
+            TldSet::CountryCode => COUNTRY_CODE.iter().map(|tld| tld.to_string()).collect(),
+            TldSet::NewGeneric => NEW_GENERIC.iter().map(|tld| tld.to_string()).collect(),
+            TldSet::All => {
+                let bootstrap = bootstrap.ok_or_else(|| {
+                    anyhow::anyhow!("The IANA RDAP bootstrap registry is unavailable")
+                })?;
+                let mut tlds: Vec<String> = bootstrap.keys().cloned().collect();
+                tlds.sort();
+                tlds
+            }
+        };
+
+        let mut unique = Vec::new();
+        for tld in tlds {
+            if !unique.contains(&tld) {
+                unique.push(tld);
+            }
+        }
+
+        if unique.is_empty() {
+            return Err(anyhow::anyhow!("The TLD set is empty"));
+        }
+
+        Ok(unique)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(TldSet::parse("popular").unwrap(), TldSet::Popular);
+        assert_eq!(TldSet::parse("ccTLDs").unwrap(), TldSet::CountryCode);
+        assert_eq!(TldSet::parse("new gTLDs").unwrap(), TldSet::NewGeneric);
+        assert_eq!(TldSet::parse("new_gtlds").unwrap(), TldSet::NewGeneric);
+        assert_eq!(
+            TldSet::parse("all from the bootstrap").unwrap(),
+            TldSet::All
+        );
+        assert!(TldSet::parse("favourites").is_err());
+    }
+
+    #[test]
+    fn from_params_test() {
+        let io = || Some(vec!["io".to_string()]);
+        assert_eq!(
+            TldSet::from_params(io(), None).unwrap(),
+            TldSet::Explicit(vec!["io".to_string()])
+        );
+        assert_eq!(
+            TldSet::from_params(Some(Vec::new()), Some("ccTLDs")).unwrap(),
+            TldSet::CountryCode
+        );
+        assert_eq!(TldSet::from_params(None, None).unwrap(), TldSet::Popular);
+        assert!(TldSet::from_params(io(), Some("popular")).is_err());
+        assert!(TldSet::from_params(None, Some("favourites")).is_err());
+    }
+
+    #[test]
+    fn resolve_test() {
+        let explicit = TldSet::Explicit(vec![
+            ".COM".to_string(),
+            "io".to_string(),
+            "com".to_string(),
+            " ".to_string(),
+        ]);
+        assert_eq!(explicit.resolve(None).unwrap(), vec!["com", "io"]);
+
+        assert!(TldSet::All.resolve(None).is_err());
+
+        let bootstrap = HashMap::from([
+            (
+                "net".to_string(),
+                "https://rdap.verisign.com/net/v1".to_string(),
+            ),
+            (
+                "com".to_string(),
+                "https://rdap.verisign.com/com/v1".to_string(),
+            ),
+        ]);
+        assert_eq!(
+            TldSet::All.resolve(Some(&bootstrap)).unwrap(),
+            vec!["com", "net"]
+        );
+
+        assert!(TldSet::Explicit(Vec::new()).resolve(None).is_err());
+    }
+}
@@ -0,0 +1,840 @@
+use anyhow::Result;
+use rustls::crypto::ring::{ALL_CIPHER_SUITES, ALL_KX_GROUPS};
+use rustls::crypto::{CryptoProvider, SupportedKxGroup};
+use rustls::pki_types::ServerName;
+use rustls::{
+    ClientConfig, ClientConnection, HandshakeKind, ProtocolVersion, SupportedCipherSuite,
+    SupportedProtocolVersion,
+};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::cert_chain::ChainRecorder;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to wait for session tickets after the handshake.
+const TICKET_WAIT: Duration = Duration::from_millis(500);
+
+const ALPN_PROTOCOLS: &[&str] = &["h2", "http/1.1"];
+
+const SSL_3_0: u16 = 0x0300;
+const TLS_1_0: u16 = 0x0301;
+const TLS_1_1: u16 = 0x0302;
+const TLS_1_2: u16 = 0x0303;
+
+/// Cipher suites offered in the hand-built pre-TLS 1.2 ClientHello.
+const LEGACY_CIPHER_SUITES: &[(u16, &str)] = &[
+    (0xc014, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0xc013, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0xc00a, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA"),
+    (0xc009, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA"),
+    (0x0039, "TLS_DHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0x0033, "TLS_DHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0x0035, "TLS_RSA_WITH_AES_256_CBC_SHA"),
+    (0x002f, "TLS_RSA_WITH_AES_128_CBC_SHA"),
+    (0x000a, "TLS_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0x0005, "TLS_RSA_WITH_RC4_128_SHA"),
+    (0x0004, "TLS_RSA_WITH_RC4_128_MD5"),
+];
+
+/// Every TLS 1.2 suite worth reporting, weak ones included. rustls only
+/// speaks the ECDHE AEAD suites, so support and preference for TLS 1.2 are
+/// also read from the ServerHello to a hand-built ClientHello offering these.
+const TLS12_CIPHER_SUITES: &[(u16, &str)] = &[
+    (0xc02c, "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384"),
+    (0xc030, "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384"),
+    (0xcca9, "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xcca8, "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xc02b, "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256"),
+    (0xc02f, "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"),
+    (0x009f, "TLS_DHE_RSA_WITH_AES_256_GCM_SHA384"),
+    (0x009e, "TLS_DHE_RSA_WITH_AES_128_GCM_SHA256"),
+    (0xccaa, "TLS_DHE_RSA_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xc024, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA384"),
+    (0xc028, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA384"),
+    (0xc023, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA256"),
+    (0xc027, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256"),
+    (0x006b, "TLS_DHE_RSA_WITH_AES_256_CBC_SHA256"),
+    (0x0067, "TLS_DHE_RSA_WITH_AES_128_CBC_SHA256"),
+    (0x009d, "TLS_RSA_WITH_AES_256_GCM_SHA384"),
+    (0x009c, "TLS_RSA_WITH_AES_128_GCM_SHA256"),
+    (0x003d, "TLS_RSA_WITH_AES_256_CBC_SHA256"),
+    (0x003c, "TLS_RSA_WITH_AES_128_CBC_SHA256"),
+    (0xc014, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0xc013, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0xc00a, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA"),
+    (0xc009, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA"),
+    (0x0039, "TLS_DHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0x0033, "TLS_DHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0x0035, "TLS_RSA_WITH_AES_256_CBC_SHA"),
+    (0x002f, "TLS_RSA_WITH_AES_128_CBC_SHA"),
+    (0x000a, "TLS_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0x0005, "TLS_RSA_WITH_RC4_128_SHA"),
+    (0x0004, "TLS_RSA_WITH_RC4_128_MD5"),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsScanResult {
+    pub host: String,
+    pub port: u16,
+    pub protocols: Vec<ProtocolSupport>,
+    /// True when the server refused SSLv3, TLS 1.0 and TLS 1.1, false when
+    /// it accepted any of them, and unknown when a probe got no answer.
+    pub legacy_protocols_disabled: Option<bool>,
+    pub cipher_suites: Vec<CipherSuitePreference>,
+    pub alpn_protocols: Vec<String>,
+    pub key_exchange_groups: Vec<String>,
+    pub ocsp_stapling: bool,
+    pub session_resumption: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolSupport {
+    pub version: String,
+    /// Unknown when the probe never got an answer from the server, e.g. the
+    /// connection failed or timed out.
+    pub supported: Option<bool>,
+    /// The suite the server picked when the version was accepted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cipher_suite: Option<String>,
+    /// Why the version was refused, or why the probe got no answer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherSuitePreference {
+    pub version: String,
+    /// Accepted suites in the order the server picked them. This is the
+    /// server's preference only when `server_enforces_order` is true.
+    pub suites: Vec<String>,
+    /// Whether the server picks by its own order rather than the client's.
+    /// Unknown with fewer than two accepted suites.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_enforces_order: Option<bool>,
+}
+
+struct Target {
+    host: String,
+    addr: SocketAddr,
+}
+
+impl Target {
+    fn connect(&self) -> Result<TcpStream> {
+        let stream = TcpStream::connect_timeout(&self.addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        Ok(stream)
+    }
+}
+
+/// What a completed rustls handshake negotiated.
+struct Negotiated {
+    cipher_suite: Option<SupportedCipherSuite>,
+    kx_group: Option<&'static dyn SupportedKxGroup>,
+    alpn: Option<String>,
+    resumed: bool,
+    ocsp_stapled: bool,
+}
+
+/// A rustls client restricted to the given versions, suites, groups and ALPN
+/// protocols, that accepts any certificate so probing works on broken chains.
+struct Probe {
+    config: Arc<ClientConfig>,
+    recorder: Arc<ChainRecorder>,
+}
+
+impl Probe {
+    fn new(
+        versions: &[&'static SupportedProtocolVersion],
+        cipher_suites: Vec<SupportedCipherSuite>,
+        kx_groups: Vec<&'static dyn SupportedKxGroup>,
+        alpn: &[&str],
+    ) -> Result<Self> {
+        let provider = Arc::new(CryptoProvider {
+            cipher_suites,
+            kx_groups,
+            ..rustls::crypto::ring::default_provider()
+        });
+        let recorder = Arc::new(ChainRecorder::new(provider.clone()));
+
+        let mut config = ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(versions)?
+            .dangerous()
+            .with_custom_certificate_verifier(recorder.clone())
+            .with_no_client_auth();
+        config.alpn_protocols = alpn.iter().map(|p| p.as_bytes().to_vec()).collect();
+
+        Ok(Probe {
+            config: Arc::new(config),
+            recorder,
+        })
+    }
+
+    fn with_defaults(versions: &[&'static SupportedProtocolVersion]) -> Result<Self> {
+        Self::new(
+            versions,
+            ALL_CIPHER_SUITES.to_vec(),
+            ALL_KX_GROUPS.to_vec(),
+            &[],
+        )
+    }
+
+    /// Handshake once. With `wait_for_tickets`, linger briefly afterwards so
+    /// TLS 1.3 session tickets reach the resumption store.
+    fn handshake(&self, target: &Target, wait_for_tickets: bool) -> Result<Negotiated> {
+        let server_name = ServerName::try_from(target.host.clone())?;
+        let mut conn = ClientConnection::new(self.config.clone(), server_name)?;
+        let mut stream = target.connect()?;
+
+        while conn.is_handshaking() {
+            conn.complete_io(&mut stream)?;
+        }
+
+        if wait_for_tickets {
+            stream.set_read_timeout(Some(TICKET_WAIT))?;
+            while let Ok(read) = conn.read_tls(&mut stream) {
+                if read == 0 || conn.process_new_packets().is_err() {
+                    break;
+                }
+            }
+        }
+
+        let presented = self.recorder.take();
+        Ok(Negotiated {
+            cipher_suite: conn.negotiated_cipher_suite(),
+            kx_group: conn.negotiated_key_exchange_group(),
+            alpn: conn
+                .alpn_protocol()
+                .map(|p| String::from_utf8_lossy(p).into_owned()),
+            resumed: conn.handshake_kind() == Some(HandshakeKind::Resumed),
+            ocsp_stapled: !presented.ocsp_response.is_empty(),
+        })
+    }
+}
+
+pub async fn scan(host: &str, port: u16) -> Result<TlsScanResult> {
+    let host = host.trim().trim_end_matches('.').to_lowercase();
+    tokio::task::spawn_blocking(move || scan_blocking(&host, port)).await?
+}
+
+fn scan_blocking(host: &str, port: u16) -> Result<TlsScanResult> {
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No addresses found for {}", host))?;
+    let target = Target {
+        host: host.to_string(),
+        addr,
+    };
+
+    // A baseline handshake establishes that the endpoint speaks TLS at all,
+    // and a second one on the same config tests resumption.
+    let baseline = Probe::with_defaults(rustls::ALL_VERSIONS)?;
+    let first = baseline
+        .handshake(&target, true)
+        .map_err(|e| anyhow::anyhow!("TLS handshake with {}:{} failed: {}", host, port, e))?;
+    let session_resumption = baseline
+        .handshake(&target, false)
+        .map(|negotiated| negotiated.resumed)
+        .unwrap_or(false);
+
+    let mut protocols: Vec<ProtocolSupport> = [
+        (SSL_3_0, "SSLv3"),
+        (TLS_1_0, "TLSv1.0"),
+        (TLS_1_1, "TLSv1.1"),
+    ]
+    .iter()
+    .map(|&(version, name)| probe_hello_version(&target, version, name, LEGACY_CIPHER_SUITES))
+    .collect();
+
+    let modern = [&rustls::version::TLS12, &rustls::version::TLS13];
+    let mut cipher_suites = Vec::new();
+    for version in modern {
+        let name = version_name(version.version);
+        let tls12 = version.version == ProtocolVersion::TLSv1_2;
+        let support =
+            match Probe::with_defaults(&[version]).and_then(|p| p.handshake(&target, false)) {
+                Ok(negotiated) => ProtocolSupport {
+                    version: name.clone(),
+                    supported: Some(true),
+                    cipher_suite: negotiated.cipher_suite.map(suite_name),
+                    detail: None,
+                },
+                // The server may only have suites rustls lacks, such as CBC.
+                Err(e) if tls12 && is_tls_error(&e) => {
+                    probe_hello_version(&target, TLS_1_2, &name, TLS12_CIPHER_SUITES)
+                }
+                Err(e) => ProtocolSupport {
+                    version: name.clone(),
+                    // Only a TLS-level failure says the server refused it.
+                    supported: is_tls_error(&e).then_some(false),
+                    cipher_suite: None,
+                    detail: Some(e.to_string()),
+                },
+            };
+
+        if support.supported == Some(true) {
+            cipher_suites.push(if tls12 {
+                hello_cipher_preference(&target, TLS_1_2, &name, TLS12_CIPHER_SUITES)
+            } else {
+                cipher_preference(&target, version)
+            });
+        }
+        protocols.push(support);
+    }
+
+    let legacy: Vec<Option<bool>> = protocols
+        .iter()
+        .filter(|p| matches!(p.version.as_str(), "SSLv3" | "TLSv1.0" | "TLSv1.1"))
+        .map(|p| p.supported)
+        .collect();
+    let legacy_protocols_disabled = if legacy.contains(&Some(true)) {
+        Some(false)
+    } else if legacy.iter().all(|supported| *supported == Some(false)) {
+        Some(true)
+    } else {
+        None
+    };
+
+    let alpn_protocols = ALPN_PROTOCOLS
+        .iter()
+        .filter(|protocol| {
+            Probe::new(
+                rustls::ALL_VERSIONS,
+                ALL_CIPHER_SUITES.to_vec(),
+                ALL_KX_GROUPS.to_vec(),
+                &[protocol],
+            )
+            .and_then(|p| p.handshake(&target, false))
+            .is_ok_and(|negotiated| negotiated.alpn.as_deref() == Some(**protocol))
+        })
+        .map(|protocol| protocol.to_string())
+        .collect();
+
+    let key_exchange_groups = ALL_KX_GROUPS
+        .iter()
+        .filter(|group| {
+            Probe::new(
+                rustls::ALL_VERSIONS,
+                ALL_CIPHER_SUITES.to_vec(),
+                vec![**group],
+                &[],
+            )
+            .and_then(|p| p.handshake(&target, false))
+            .is_ok_and(|negotiated| {
+                negotiated
+                    .kx_group
+                    .is_some_and(|negotiated| negotiated.name() == group.name())
+            })
+        })
+        .map(|group| format!("{:?}", group.name()))
+        .collect();
+
+    Ok(TlsScanResult {
+        host: host.to_string(),
+        port,
+        protocols,
+        legacy_protocols_disabled,
+        cipher_suites,
+        alpn_protocols,
+        key_exchange_groups,
+        ocsp_stapling: first.ocsp_stapled,
+        session_resumption,
+    })
+}
+
+/// Find the server's preference order by repeatedly offering every suite not
+/// yet picked, then offer the result reversed to see whose order wins.
+fn cipher_preference(
+    target: &Target,
+    version: &'static SupportedProtocolVersion,
+) -> CipherSuitePreference {
+    let mut remaining: Vec<SupportedCipherSuite> = ALL_CIPHER_SUITES
+        .iter()
+        .filter(|suite| suite.version() == version)
+        .copied()
+        .collect();
+    let mut ordered: Vec<SupportedCipherSuite> = Vec::new();
+
+    while !remaining.is_empty() {
+        let chosen = Probe::new(&[version], remaining.clone(), ALL_KX_GROUPS.to_vec(), &[])
+            .and_then(|p| p.handshake(target, false))
+            .ok()
+            .and_then(|negotiated| negotiated.cipher_suite);
+        let Some(chosen) = chosen else {
+            break;
+        };
+        remaining.retain(|suite| suite.suite() != chosen.suite());
+        ordered.push(chosen);
+    }
+
+    let server_enforces_order = if ordered.len() >= 2 {
+        let reversed: Vec<SupportedCipherSuite> = ordered.iter().rev().copied().collect();
+        Probe::new(&[version], reversed, ALL_KX_GROUPS.to_vec(), &[])
+            .and_then(|p| p.handshake(target, false))
+            .ok()
+            .and_then(|negotiated| negotiated.cipher_suite)
+            .map(|chosen| chosen.suite() == ordered[0].suite())
+    } else {
+        None
+    };
+
+    CipherSuitePreference {
+        version: version_name(version.version),
+        suites: ordered.into_iter().map(suite_name).collect(),
+        server_enforces_order,
+    }
+}
+
+/// Like [`cipher_preference`], but offering `suites` in a hand-built
+/// ClientHello and reading the pick from the ServerHello, so suites rustls
+/// cannot complete a handshake with are ordered too.
+fn hello_cipher_preference(
+    target: &Target,
+    version: u16,
+    name: &str,
+    suites: &[(u16, &str)],
+) -> CipherSuitePreference {
+    let pick = |offered: &[u16]| -> Option<u16> {
+        let mut stream = target.connect().ok()?;
+        let hello = client_hello(&target.host, version, client_random(), offered);
+        stream.write_all(&hello).ok()?;
+        match read_legacy_response(&mut stream, version).ok()? {
+            LegacyOutcome::Accepted { cipher_suite } => Some(cipher_suite),
+            LegacyOutcome::Rejected(_) => None,
+        }
+    };
+
+    let mut remaining: Vec<u16> = suite_ids(suites);
+    let mut ordered: Vec<u16> = Vec::new();
+    while let Some(chosen) = pick(&remaining) {
+        // A pick that was not offered would loop forever.
+        if !remaining.contains(&chosen) {
+            break;
+        }
+        remaining.retain(|suite| *suite != chosen);
+        ordered.push(chosen);
+        if remaining.is_empty() {
+            break;
+        }
+    }
+
+    let server_enforces_order = if ordered.len() >= 2 {
+        let reversed: Vec<u16> = ordered.iter().rev().copied().collect();
+        pick(&reversed).map(|chosen| chosen == ordered[0])
+    } else {
+        None
+    };
+
+    CipherSuitePreference {
+        version: name.to_string(),
+        suites: ordered.into_iter().map(hello_suite_name).collect(),
+        server_enforces_order,
+    }
+}
+
+fn version_name(version: ProtocolVersion) -> String {
+    match version {
+        ProtocolVersion::SSLv3 => "SSLv3".to_string(),
+        ProtocolVersion::TLSv1_0 => "TLSv1.0".to_string(),
+        ProtocolVersion::TLSv1_1 => "TLSv1.1".to_string(),
+        ProtocolVersion::TLSv1_2 => "TLSv1.2".to_string(),
+        ProtocolVersion::TLSv1_3 => "TLSv1.3".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn suite_name(suite: SupportedCipherSuite) -> String {
+    format!("{:?}", suite.suite())
+}
+
+/// Whether a handshake failed at the TLS level, such as on an alert or an
+/// incompatible server, rather than on the connection.
+fn is_tls_error(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<rustls::Error>().is_some()
+        || error
+            .downcast_ref::<std::io::Error>()
+            .and_then(|e| e.get_ref())
+            .is_some_and(|inner| inner.is::<rustls::Error>())
+}
+
+/// rustls can't speak anything older than TLS 1.2, nor TLS 1.2 with non-AEAD
+/// suites, so those are probed with a hand-built ClientHello offering
+/// `suites` and only the ServerHello is read.
+///
+/// The version counts as refused only when the server answers with an alert
+/// or another version, or closes the connection after the ClientHello. A
+/// probe that cannot connect or gets no answer in time is unknown.
+fn probe_hello_version(
+    target: &Target,
+    version: u16,
+    name: &str,
+    suites: &[(u16, &str)],
+) -> ProtocolSupport {
+    let support =
+        |supported: Option<bool>, cipher_suite: Option<String>, detail: Option<String>| {
+            ProtocolSupport {
+                version: name.to_string(),
+                supported,
+                cipher_suite,
+                detail,
+            }
+        };
+
+    let mut stream = match target.connect() {
+        Ok(stream) => stream,
+        Err(e) => return support(None, None, Some(format!("Could not connect: {}", e))),
+    };
+    let hello = client_hello(&target.host, version, client_random(), &suite_ids(suites));
+    if let Err(e) = stream.write_all(&hello) {
+        return support(
+            None,
+            None,
+            Some(format!("Could not send ClientHello: {}", e)),
+        );
+    }
+
+    match read_legacy_response(&mut stream, version) {
+        Ok(LegacyOutcome::Accepted { cipher_suite }) => {
+            support(Some(true), Some(hello_suite_name(cipher_suite)), None)
+        }
+        Ok(LegacyOutcome::Rejected(detail)) => support(Some(false), None, Some(detail)),
+        Err(e) => {
+            let closed = e.downcast_ref::<std::io::Error>().is_some_and(|e| {
+                matches!(
+                    e.kind(),
+                    std::io::ErrorKind::UnexpectedEof
+                        | std::io::ErrorKind::ConnectionReset
+                        | std::io::ErrorKind::ConnectionAborted
+                )
+            });
+            if closed {
+                support(
+                    Some(false),
+                    None,
+                    Some(format!("Server closed the connection: {}", e)),
+                )
+            } else {
+                support(
+                    None,
+                    None,
+                    Some(format!("No answer from the server: {}", e)),
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LegacyOutcome {
+    Accepted { cipher_suite: u16 },
+    Rejected(String),
+}
+
+fn client_random() -> [u8; 32] {
+    // Only needs to differ between connections; nothing is ever encrypted.
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut random = [0u8; 32];
+    for (i, byte) in random.iter_mut().enumerate() {
+        *byte = (nanos >> ((i % 16) * 8)) as u8 ^ (i as u8).wrapping_mul(0x9d);
+    }
+    random
+}
+
+fn suite_ids(suites: &[(u16, &str)]) -> Vec<u16> {
+    suites.iter().map(|(id, _)| *id).collect()
+}
+
+fn client_hello(host: &str, version: u16, random: [u8; 32], suites: &[u16]) -> Vec<u8> {
+    let mut extensions = Vec::new();
+
+    if host.parse::<std::net::IpAddr>().is_err() {
+        let name = host.as_bytes();
+        let list_len = name.len() + 3;
+        extensions.extend_from_slice(&0x0000u16.to_be_bytes());
+        extensions.extend_from_slice(&((list_len + 2) as u16).to_be_bytes());
+        extensions.extend_from_slice(&(list_len as u16).to_be_bytes());
+        extensions.push(0x00);
+        extensions.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        extensions.extend_from_slice(name);
+    }
+    // supported_groups: secp256r1, secp384r1
+    extensions.extend_from_slice(&[0x00, 0x0a, 0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18]);
+    // ec_point_formats: uncompressed
+    extensions.extend_from_slice(&[0x00, 0x0b, 0x00, 0x02, 0x01, 0x00]);
+    // renegotiation_info: empty
+    extensions.extend_from_slice(&[0xff, 0x01, 0x00, 0x01, 0x00]);
+    if version >= TLS_1_2 {
+        // signature_algorithms: ECDSA, RSA-PSS and PKCS#1 with SHA-2, then SHA-1
+        extensions.extend_from_slice(&[
+            0x00, 0x0d, 0x00, 0x18, 0x00, 0x16, 0x04, 0x03, 0x05, 0x03, 0x06, 0x03, 0x08, 0x04,
+            0x08, 0x05, 0x08, 0x06, 0x04, 0x01, 0x05, 0x01, 0x06, 0x01, 0x02, 0x01, 0x02, 0x03,
+        ]);
+    }
+
+    let mut body = Vec::new();
+    body.extend_from_slice(&version.to_be_bytes());
+    body.extend_from_slice(&random);
+    body.push(0x00);
+    body.extend_from_slice(&((suites.len() * 2) as u16).to_be_bytes());
+    for suite in suites {
+        body.extend_from_slice(&suite.to_be_bytes());
+    }
+    body.extend_from_slice(&[0x01, 0x00]);
+    body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+    body.extend_from_slice(&extensions);
+
+    let mut handshake = vec![0x01];
+    handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend_from_slice(&body);
+
+    // Record layer version stays at TLS 1.0 for compatibility, as browsers did.
+    let mut record = vec![0x16];
+    record.extend_from_slice(&version.min(TLS_1_0).to_be_bytes());
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend_from_slice(&handshake);
+    record
+}
+
+fn read_legacy_response<R: Read>(stream: &mut R, requested: u16) -> Result<LegacyOutcome> {
+    let mut header = [0u8; 5];
+    stream.read_exact(&mut header)?;
+    let length = u16::from_be_bytes([header[3], header[4]]) as usize;
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body)?;
+
+    Ok(parse_legacy_response(header[0], &body, requested))
+}
+
+fn parse_legacy_response(content_type: u8, body: &[u8], requested: u16) -> LegacyOutcome {
+    match content_type {
+        0x15 => {
+            let description = body.get(1).copied().unwrap_or_default();
+            LegacyOutcome::Rejected(format!("Server sent alert {}", description))
+        }
+        0x16 if body.first() == Some(&0x02) && body.len() >= 39 => {
+            let version = u16::from_be_bytes([body[4], body[5]]);
+            if version != requested {
+                return LegacyOutcome::Rejected(format!(
+                    "Server answered with version 0x{:04x}",
+                    version
+                ));
+            }
+            let session_id_len = body[38] as usize;
+            match body.get(39 + session_id_len..41 + session_id_len) {
+                Some(suite) => LegacyOutcome::Accepted {
+                    cipher_suite: u16::from_be_bytes([suite[0], suite[1]]),
+                },
+                None => LegacyOutcome::Rejected("Truncated ServerHello".to_string()),
+            }
+        }
+        other => LegacyOutcome::Rejected(format!("Unexpected TLS record type {}", other)),
+    }
+}
+
+fn hello_suite_name(suite: u16) -> String {
+    TLS12_CIPHER_SUITES
+        .iter()
+        .find(|(id, _)| *id == suite)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("0x{:04x}", suite))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn server_hello(version: u16, cipher_suite: u16) -> Vec<u8> {
+        let mut body = vec![0x02, 0x00, 0x00, 0x00];
+        body.extend_from_slice(&version.to_be_bytes());
+        body.extend_from_slice(&[0xab; 32]);
+        body.push(0x00);
+        body.extend_from_slice(&cipher_suite.to_be_bytes());
+        body.push(0x00);
+        let length = (body.len() - 4) as u32;
+        body[1..4].copy_from_slice(&length.to_be_bytes()[1..]);
+
+        let mut record = vec![0x16];
+        record.extend_from_slice(&version.to_be_bytes());
+        record.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        record.extend_from_slice(&body);
+        record
+    }
+
+    #[test]
+    fn client_hello_test() {
+        let hello = client_hello(
+            "example.com",
+            TLS_1_1,
+            [7; 32],
+            &suite_ids(LEGACY_CIPHER_SUITES),
+        );
+
+        assert_eq!(hello[0], 0x16);
+        assert_eq!(&hello[1..3], &[0x03, 0x01]);
+        assert_eq!(
+            u16::from_be_bytes([hello[3], hello[4]]) as usize,
+            hello.len() - 5
+        );
+        assert_eq!(hello[5], 0x01);
+        assert_eq!(&hello[9..11], &[0x03, 0x02]);
+        assert!(hello
+            .windows("example.com".len())
+            .any(|window| window == b"example.com"));
+
+        let without_sni = client_hello("192.0.2.1", TLS_1_0, [7; 32], &[0x002f]);
+        assert!(without_sni.len() < hello.len());
+
+        // TLS 1.2 servers need signature_algorithms to pick a suite.
+        let signature_algorithms = [0x00, 0x0d, 0x00, 0x18];
+        let has_signature_algorithms = |hello: &[u8]| {
+            hello
+                .windows(signature_algorithms.len())
+                .any(|window| window == signature_algorithms)
+        };
+        let tls12 = client_hello("example.com", TLS_1_2, [7; 32], &[0x002f]);
+        assert!(has_signature_algorithms(&tls12));
+        assert!(!has_signature_algorithms(&hello));
+    }
+
+    #[test]
+    fn parse_legacy_response_test() {
+        let hello = server_hello(TLS_1_0, 0xc013);
+        assert_eq!(
+            parse_legacy_response(hello[0], &hello[5..], TLS_1_0),
+            LegacyOutcome::Accepted {
+                cipher_suite: 0xc013
+            }
+        );
+
+        assert!(matches!(
+            parse_legacy_response(hello[0], &hello[5..], TLS_1_1),
+            LegacyOutcome::Rejected(_)
+        ));
+
+        assert_eq!(
+            parse_legacy_response(0x15, &[0x02, 0x46], TLS_1_0),
+            LegacyOutcome::Rejected("Server sent alert 70".to_string())
+        );
+    }
+
+    #[test]
+    fn probe_hello_version_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = std::thread::spawn(move || {
+            // First connection accepts TLS 1.0, second refuses TLS 1.1 and
+            // the third hangs up on SSLv3.
+            for response in [
+                server_hello(TLS_1_0, 0x002f),
+                vec![0x15, 0x03, 0x02, 0x00, 0x02, 0x02, 0x46],
+                Vec::new(),
+            ] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut header = [0u8; 5];
+                stream.read_exact(&mut header).unwrap();
+                let mut hello = vec![0u8; u16::from_be_bytes([header[3], header[4]]) as usize];
+                stream.read_exact(&mut hello).unwrap();
+                stream.write_all(&response).unwrap();
+            }
+        });
+
+        let target = Target {
+            host: "localhost".to_string(),
+            addr,
+        };
+        let tls10 = probe_hello_version(&target, TLS_1_0, "TLSv1.0", LEGACY_CIPHER_SUITES);
+        let tls11 = probe_hello_version(&target, TLS_1_1, "TLSv1.1", LEGACY_CIPHER_SUITES);
+        let ssl3 = probe_hello_version(&target, SSL_3_0, "SSLv3", LEGACY_CIPHER_SUITES);
+        server.join().unwrap();
+
+        assert_eq!(tls10.supported, Some(true));
+        assert_eq!(
+            tls10.cipher_suite.as_deref(),
+            Some("TLS_RSA_WITH_AES_128_CBC_SHA")
+        );
+        assert_eq!(tls11.supported, Some(false));
+        assert_eq!(tls11.detail.as_deref(), Some("Server sent alert 70"));
+        assert_eq!(ssl3.supported, Some(false));
+    }
+
+    #[test]
+    fn hello_cipher_preference_orders_cbc_suites_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // A TLS 1.2 server with only CBC suites, picking by its own order.
+        let server = std::thread::spawn(move || {
+            let preference = [0xc013u16, 0x002f];
+            for _ in 0..4 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut header = [0u8; 5];
+                stream.read_exact(&mut header).unwrap();
+                let mut hello = vec![0u8; u16::from_be_bytes([header[3], header[4]]) as usize];
+                stream.read_exact(&mut hello).unwrap();
+                let suites_len = u16::from_be_bytes([hello[39], hello[40]]) as usize;
+                let offered: Vec<u16> = hello[41..41 + suites_len]
+                    .chunks(2)
+                    .map(|suite| u16::from_be_bytes([suite[0], suite[1]]))
+                    .collect();
+                let response = match preference.iter().find(|suite| offered.contains(suite)) {
+                    Some(suite) => server_hello(TLS_1_2, *suite),
+                    None => vec![0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28],
+                };
+                stream.write_all(&response).unwrap();
+            }
+        });
+
+        let target = Target {
+            host: "localhost".to_string(),
+            addr,
+        };
+        let preference = hello_cipher_preference(&target, TLS_1_2, "TLSv1.2", TLS12_CIPHER_SUITES);
+        server.join().unwrap();
+
+        assert_eq!(preference.version, "TLSv1.2");
+        assert_eq!(
+            preference.suites,
+            vec![
+                "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA",
+                "TLS_RSA_WITH_AES_128_CBC_SHA"
+            ]
+        );
+        assert_eq!(preference.server_enforces_order, Some(true));
+    }
+
+    #[test]
+    fn unreachable_legacy_probe_is_unknown_test() {
+        // Nothing listens on a port that was just released.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let target = Target {
+            host: "localhost".to_string(),
+            addr,
+        };
+
+        let support = probe_hello_version(&target, TLS_1_0, "TLSv1.0", LEGACY_CIPHER_SUITES);
+        assert_eq!(support.supported, None);
+        assert!(support.detail.unwrap().starts_with("Could not connect"));
+    }
+
+    #[test]
+    fn names_test() {
+        assert_eq!(version_name(ProtocolVersion::TLSv1_3), "TLSv1.3");
+        assert_eq!(hello_suite_name(0x0005), "TLS_RSA_WITH_RC4_128_SHA");
+        assert_eq!(hello_suite_name(0x1234), "0x1234");
+        assert_eq!(suite_name(ALL_CIPHER_SUITES[0]), "TLS13_AES_256_GCM_SHA384");
+    }
+}
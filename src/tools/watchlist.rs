@@ -0,0 +1,692 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::domain;
+use super::drop_cache;
+use super::expired::{self, DropListPresence};
+use super::rdap::{self, RdapClient, RdapLookup};
+use super::scheduler::Scheduler;
+use super::whois;
+
+/// Where a domain is in the registration lifecycle, from its EPP statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lifecycle {
+    Registered,
+    /// Expired, and the registrar may still renew or delete it.
+    AutoRenewPeriod,
+    /// Deleted; the registrant can still restore it.
+    RedemptionPeriod,
+    /// About to be purged and released, usually within five days.
+    PendingDelete,
+    /// The registry has no record of it.
+    Available,
+}
+
+impl Lifecycle {
+    /// The latest stage any of `statuses` shows, such as "pendingDelete" or
+    /// RDAP's "pending delete".
+    pub fn from_statuses(statuses: &[String]) -> Self {
+        let normalized: Vec<String> = statuses
+            .iter()
+            .map(|status| {
+                status
+                    .chars()
+                    .filter(char::is_ascii_alphanumeric)
+                    .collect::<String>()
+                    .to_lowercase()
+            })
+            .collect();
+        let has = |name: &str| normalized.iter().any(|status| status.contains(name));
+
+        if has("pendingdelete") {
+            Lifecycle::PendingDelete
+        } else if has("redemptionperiod") {
+            Lifecycle::RedemptionPeriod
+        } else if has("autorenewperiod") {
+            Lifecycle::AutoRenewPeriod
+        } else {
+            Lifecycle::Registered
+        }
+    }
+}
+
+/// What the registry says about a domain now.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryStatus {
+    pub lifecycle: Lifecycle,
+    pub statuses: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry_date: Option<String>,
+}
+
+/// Somewhere to read a domain's registry status.
+#[async_trait]
+pub trait StatusLookup: Send + Sync {
+    async fn status(&self, domain: &str) -> Result<RegistryStatus>;
+}
+
+/// RDAP, falling back to the whois command when RDAP cannot answer.
+pub struct RdapStatusLookup;
+
+#[async_trait]
+impl StatusLookup for RdapStatusLookup {
+    async fn status(&self, domain: &str) -> Result<RegistryStatus> {
+        match RdapClient::new().lookup(domain).await {
+            Ok(RdapLookup::Found(rdap_domain)) => {
+                let statuses = rdap::extract_status(&rdap_domain);
+                Ok(RegistryStatus {
+                    lifecycle: Lifecycle::from_statuses(&statuses),
+                    expiry_date: rdap::extract_expiry_date(&rdap_domain),
+                    statuses,
+                })
+            }
+            Ok(RdapLookup::NotFound { .. }) => Ok(RegistryStatus {
+                lifecycle: Lifecycle::Available,
+                statuses: Vec::new(),
+                expiry_date: None,
+            }),
+            Err(e) => {
+                let info = whois::lookup_command_line_whois(domain)
+                    .await
+                    .map_err(|whois_error| anyhow::anyhow!("{:#}; whois: {:#}", e, whois_error))?;
+                if info.status.is_empty() {
+                    return Err(anyhow::anyhow!("{:#}; whois gave no status", e));
+                }
+                Ok(RegistryStatus {
+                    lifecycle: Lifecycle::from_statuses(&info.status),
+                    statuses: info.status,
+                    expiry_date: info.expiry_date,
+                })
+            }
+        }
+    }
+}
+
+/// A domain being watched and what the last check found.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watch {
+    pub domain: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub added_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_checked: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<RegistryStatus>,
+    /// Drop lists the domain was on at the last check.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drop_lists: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WatchFile {
+    watches: Vec<Watch>,
+}
+
+/// The watchlist file.
+pub struct WatchStore {
+    path: PathBuf,
+}
+
+impl WatchStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// `DOMAIN_MCP_WATCHLIST_PATH`, or `domain-mcp/watchlist.json` in the
+    /// user's data directory.
+    pub fn from_env() -> Self {
+        let path = std::env::var_os("DOMAIN_MCP_WATCHLIST_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                std::env::var_os("XDG_DATA_HOME")
+                    .map(PathBuf::from)
+                    .or_else(|| {
+                        std::env::var_os("HOME")
+                            .map(|home| PathBuf::from(home).join(".local").join("share"))
+                    })
+                    .unwrap_or_else(std::env::temp_dir)
+                    .join("domain-mcp")
+                    .join("watchlist.json")
+            });
+        Self::new(path)
+    }
+
+    pub async fn load(&self) -> Result<Vec<Watch>> {
+        match tokio::fs::read(&self.path).await {
+            Ok(bytes) => {
+                let file: WatchFile = serde_json::from_slice(&bytes)
+                    .map_err(|e| anyhow::anyhow!("{}: {}", self.path.display(), e))?;
+                Ok(file.watches)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(anyhow::anyhow!(
+                "Failed to read {}: {}",
+                self.path.display(),
+                e
+            )),
+        }
+    }
+
+    async fn save(&self, watches: Vec<Watch>) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let json = serde_json::to_vec_pretty(&WatchFile { watches })?;
+        drop_cache::write_atomic(&self.path, &json).await
+    }
+}
+
+/// Serializes changes to the watchlist file within this process.
+fn store_lock() -> &'static tokio::sync::Mutex<()> {
+    static LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    &LOCK
+}
+
+/// Start watching `domain`, or update its note if it is already watched.
+pub async fn watch(store: &WatchStore, domain: &str, note: Option<String>) -> Result<Watch> {
+    let domain = domain::normalize_domain(domain);
+    if !domain.contains('.') || domain.starts_with('.') || domain.ends_with('.') {
+        return Err(anyhow::anyhow!("'{}' is not a domain name", domain));
+    }
+
+    let _guard = store_lock().lock().await;
+    let mut watches = store.load().await?;
+    let watch = match watches.iter_mut().find(|watch| watch.domain == domain) {
+        Some(existing) => {
+            if note.is_some() {
+                existing.note = note;
+            }
+            existing.clone()
+        }
+        None => {
+            let watch = Watch {
+                domain,
+                note,
+                added_at: Utc::now(),
+                last_checked: None,
+                status: None,
+                drop_lists: Vec::new(),
+            };
+            watches.push(watch.clone());
+            watch
+        }
+    };
+    store.save(watches).await?;
+    Ok(watch)
+}
+
+/// Stop watching `domain`; returns whether it was watched.
+pub async fn unwatch(store: &WatchStore, domain: &str) -> Result<bool> {
+    let domain = domain::normalize_domain(domain);
+    let _guard = store_lock().lock().await;
+    let mut watches = store.load().await?;
+    let before = watches.len();
+    watches.retain(|watch| watch.domain != domain);
+    let removed = watches.len() < before;
+    if removed {
+        store.save(watches).await?;
+    }
+    Ok(removed)
+}
+
+pub async fn list(store: &WatchStore) -> Result<Vec<Watch>> {
+    store.load().await
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Lifecycle,
+    ExpiryDate,
+    AddedToDropList,
+    RemovedFromDropList,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchChange {
+    pub kind: ChangeKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchCheck {
+    pub domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<RegistryStatus>,
+    pub drop_lists: Vec<String>,
+    /// Differences from the previous check; empty on the first one.
+    pub changes: Vec<WatchChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchReport {
+    pub checked_at: DateTime<Utc>,
+    pub checked: usize,
+    pub changed: usize,
+    /// Drop lists that could not be loaded; presence on them is unchanged.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unavailable_drop_lists: Vec<String>,
+    /// Watches with changes first.
+    pub watches: Vec<WatchCheck>,
+}
+
+/// Re-check every watched domain against the registry and the drop lists.
+pub async fn check(store: &WatchStore) -> Result<WatchReport> {
+    let watches = snapshot(store).await?;
+    let domains: Vec<String> = watches.iter().map(|w| w.domain.clone()).collect();
+    let presence = expired::drop_list_presence(&domains).await?;
+    check_watches(store, &RdapStatusLookup, watches, &presence).await
+}
+
+pub async fn check_with(
+    store: &WatchStore,
+    lookup: &dyn StatusLookup,
+    presence: &DropListPresence,
+) -> Result<WatchReport> {
+    let watches = snapshot(store).await?;
+    check_watches(store, lookup, watches, presence).await
+}
+
+async fn snapshot(store: &WatchStore) -> Result<Vec<Watch>> {
+    let _guard = store_lock().lock().await;
+    store.load().await
+}
+
+/// Look up `watches` without holding the store lock, so the watchlist can be
+/// edited meanwhile, then merge the results into the file as it is now.
+/// Watches removed during the check are dropped from the report, and ones
+/// added during it are left for the next check.
+async fn check_watches(
+    store: &WatchStore,
+    lookup: &dyn StatusLookup,
+    mut watches: Vec<Watch>,
+    presence: &DropListPresence,
+) -> Result<WatchReport> {
+    let now = Utc::now();
+    let concurrency = Scheduler::global().config().domain_concurrency.max(1);
+
+    let domains: Vec<(usize, String)> = watches
+        .iter()
+        .map(|watch| watch.domain.clone())
+        .enumerate()
+        .collect();
+    let statuses: Vec<(usize, Result<RegistryStatus>)> = stream::iter(domains)
+        .map(|(position, domain)| async move { (position, lookup.status(&domain).await) })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let mut checks: Vec<(usize, WatchCheck)> = statuses
+        .into_iter()
+        .map(|(position, status)| {
+            let check = update(&mut watches[position], status, presence, now);
+            (position, check)
+        })
+        .collect();
+
+    let _guard = store_lock().lock().await;
+    let mut current = store.load().await?;
+    // A watch removed and added again during the check starts afresh.
+    let same = |a: &Watch, b: &Watch| a.domain == b.domain && a.added_at == b.added_at;
+    for watch in &mut current {
+        if let Some(checked) = watches.iter().find(|checked| same(checked, watch)) {
+            watch.last_checked = checked.last_checked;
+            watch.status = checked.status.clone();
+            watch.drop_lists = checked.drop_lists.clone();
+        }
+    }
+    checks.retain_mut(|(position, check)| {
+        match current
+            .iter()
+            .find(|watch| same(watch, &watches[*position]))
+        {
+            Some(watch) => {
+                check.note = watch.note.clone();
+                true
+            }
+            None => false,
+        }
+    });
+    store.save(current).await?;
+
+    checks.sort_by_key(|(position, check)| (check.changes.is_empty(), *position));
+    let checks: Vec<WatchCheck> = checks.into_iter().map(|(_, check)| check).collect();
+    Ok(WatchReport {
+        checked_at: now,
+        checked: checks.len(),
+        changed: checks.iter().filter(|c| !c.changes.is_empty()).count(),
+        unavailable_drop_lists: presence
+            .unavailable
+            .iter()
+            .map(|(name, error)| format!("{}: {}", name, error))
+            .collect(),
+        watches: checks,
+    })
+}
+
+/// Record the new state on `watch` and describe what changed. A failed
+/// lookup keeps the previous status, and lists that could not be loaded
+/// keep their previous presence.
+fn update(
+    watch: &mut Watch,
+    status: Result<RegistryStatus>,
+    presence: &DropListPresence,
+    now: DateTime<Utc>,
+) -> WatchCheck {
+    let first_check = watch.last_checked.is_none();
+    let mut changes = Vec::new();
+    let mut error = None;
+
+    match status {
+        Ok(status) => {
+            if let Some(previous) = &watch.status {
+                if previous.lifecycle != status.lifecycle {
+                    changes.push(WatchChange {
+                        kind: ChangeKind::Lifecycle,
+                        detail: format!(
+                            "{} -> {}",
+                            lifecycle_name(previous.lifecycle),
+                            lifecycle_name(status.lifecycle)
+                        ),
+                    });
+                }
+                if previous.expiry_date != status.expiry_date {
+                    changes.push(WatchChange {
+                        kind: ChangeKind::ExpiryDate,
+                        detail: format!(
+                            "{} -> {}",
+                            previous.expiry_date.as_deref().unwrap_or("none"),
+                            status.expiry_date.as_deref().unwrap_or("none")
+                        ),
+                    });
+                }
+            }
+            watch.status = Some(status);
+        }
+        Err(e) => error = Some(format!("{:#}", e)),
+    }
+
+    let listed = presence
+        .listed
+        .get(&watch.domain)
+        .cloned()
+        .unwrap_or_default();
+    let mut drop_lists: Vec<String> = watch
+        .drop_lists
+        .iter()
+        .filter(|name| !presence.checked.contains(name))
+        .cloned()
+        .collect();
+    for name in &presence.checked {
+        let was = watch.drop_lists.contains(name);
+        let is = listed.contains(name);
+        if is {
+            drop_lists.push(name.clone());
+        }
+        if first_check || was == is {
+            continue;
+        }
+        changes.push(WatchChange {
+            kind: if is {
+                ChangeKind::AddedToDropList
+            } else {
+                ChangeKind::RemovedFromDropList
+            },
+            detail: name.clone(),
+        });
+    }
+    drop_lists.sort();
+    watch.drop_lists = drop_lists;
+    watch.last_checked = Some(now);
+
+    WatchCheck {
+        domain: watch.domain.clone(),
+        note: watch.note.clone(),
+        status: watch.status.clone(),
+        drop_lists: watch.drop_lists.clone(),
+        changes,
+        error,
+    }
+}
+
+fn lifecycle_name(lifecycle: Lifecycle) -> String {
+    serde_json::to_value(lifecycle)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Answers from a table the test can change between checks.
+    struct StubLookup {
+        statuses: Mutex<HashMap<String, RegistryStatus>>,
+    }
+
+    impl StubLookup {
+        fn new() -> Self {
+            Self {
+                statuses: Mutex::new(HashMap::new()),
+            }
+        }
+
+        fn set(&self, domain: &str, statuses: &[&str], expiry: &str) {
+            let statuses: Vec<String> = statuses.iter().map(|s| s.to_string()).collect();
+            self.statuses.lock().unwrap().insert(
+                domain.to_string(),
+                RegistryStatus {
+                    lifecycle: Lifecycle::from_statuses(&statuses),
+                    statuses,
+                    expiry_date: Some(expiry.to_string()),
+                },
+            );
+        }
+    }
+
+    #[async_trait]
+    impl StatusLookup for StubLookup {
+        async fn status(&self, domain: &str) -> Result<RegistryStatus> {
+            self.statuses
+                .lock()
+                .unwrap()
+                .get(domain)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("RDAP lookup failed for domain: {}", domain))
+        }
+    }
+
+    fn store(test: &str) -> WatchStore {
+        let path =
+            std::env::temp_dir().join(format!("watchlist-{}-{}.json", test, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        WatchStore::new(path)
+    }
+
+    fn presence(checked: &[&str], listed: &[(&str, &str)]) -> DropListPresence {
+        let mut presence = DropListPresence {
+            checked: checked.iter().map(|s| s.to_string()).collect(),
+            ..DropListPresence::default()
+        };
+        for (domain, list) in listed {
+            presence
+                .listed
+                .entry(domain.to_string())
+                .or_default()
+                .push(list.to_string());
+        }
+        presence
+    }
+
+    #[test]
+    fn lifecycle_from_statuses() {
+        let lifecycle = |statuses: &[&str]| {
+            Lifecycle::from_statuses(&statuses.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(lifecycle(&["active"]), Lifecycle::Registered);
+        assert_eq!(
+            lifecycle(&["client transfer prohibited", "auto renew period"]),
+            Lifecycle::AutoRenewPeriod
+        );
+        assert_eq!(
+            lifecycle(&["redemptionPeriod https://icann.org/epp#redemptionPeriod"]),
+            Lifecycle::RedemptionPeriod
+        );
+        assert_eq!(
+            lifecycle(&["redemption period", "pending delete"]),
+            Lifecycle::PendingDelete
+        );
+    }
+
+    #[tokio::test]
+    async fn watches_persist_and_update() {
+        let store = store("persist");
+        watch(&store, "https://Example.com/", None).await.unwrap();
+        watch(&store, "shop.io", Some("Bid up to $500".to_string()))
+            .await
+            .unwrap();
+        let updated = watch(&store, "example.com", Some("Main target".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(updated.note.as_deref(), Some("Main target"));
+        assert!(watch(&store, "localhost", None).await.is_err());
+
+        let watches = list(&store).await.unwrap();
+        let domains: Vec<&str> = watches.iter().map(|w| w.domain.as_str()).collect();
+        assert_eq!(domains, vec!["example.com", "shop.io"]);
+
+        assert!(unwatch(&store, "shop.io").await.unwrap());
+        assert!(!unwatch(&store, "shop.io").await.unwrap());
+        assert_eq!(list(&store).await.unwrap().len(), 1);
+        let _ = std::fs::remove_file(&store.path);
+    }
+
+    #[tokio::test]
+    async fn reports_lifecycle_and_drop_list_changes() {
+        let store = store("changes");
+        watch(&store, "target.com", None).await.unwrap();
+        watch(&store, "steady.com", None).await.unwrap();
+        let lookup = StubLookup::new();
+        lookup.set("target.com", &["active"], "2024-01-01");
+        lookup.set("steady.com", &["active"], "2030-01-01");
+
+        // The first check is the baseline.
+        let report = check_with(&store, &lookup, &presence(&["dynadot"], &[]))
+            .await
+            .unwrap();
+        assert_eq!((report.checked, report.changed), (2, 0));
+
+        lookup.set("target.com", &["pending delete"], "2024-01-01");
+        let report = check_with(
+            &store,
+            &lookup,
+            &presence(&["dynadot", "namejet"], &[("target.com", "dynadot")]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.changed, 1);
+        let target = &report.watches[0];
+        assert_eq!(target.domain, "target.com");
+        let changes: Vec<(ChangeKind, &str)> = target
+            .changes
+            .iter()
+            .map(|c| (c.kind, c.detail.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (ChangeKind::Lifecycle, "registered -> pending_delete"),
+                (ChangeKind::AddedToDropList, "dynadot"),
+            ]
+        );
+
+        // A failed lookup and an unloadable list leave the last state alone.
+        lookup.statuses.lock().unwrap().remove("target.com");
+        let report = check_with(&store, &lookup, &presence(&["namejet"], &[]))
+            .await
+            .unwrap();
+        let target = report
+            .watches
+            .iter()
+            .find(|w| w.domain == "target.com")
+            .unwrap();
+        assert!(target.changes.is_empty());
+        assert!(target.error.is_some());
+        assert_eq!(
+            target.status.as_ref().unwrap().lifecycle,
+            Lifecycle::PendingDelete
+        );
+        assert_eq!(target.drop_lists, vec!["dynadot"]);
+        let _ = std::fs::remove_file(&store.path);
+    }
+
+    /// Edits the watchlist while the check is looking `target.com` up.
+    struct EditingLookup {
+        store: WatchStore,
+    }
+
+    #[async_trait]
+    impl StatusLookup for EditingLookup {
+        async fn status(&self, domain: &str) -> Result<RegistryStatus> {
+            if domain == "target.com" {
+                unwatch(&self.store, "gone.com").await?;
+                watch(&self.store, "late.com", None).await?;
+                watch(&self.store, "target.com", Some("Raised".to_string())).await?;
+            }
+            Ok(RegistryStatus {
+                lifecycle: Lifecycle::Registered,
+                statuses: vec!["active".to_string()],
+                expiry_date: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn merges_results_into_edits_made_during_a_check() {
+        let store = store("merge");
+        watch(&store, "target.com", Some("Bid".to_string()))
+            .await
+            .unwrap();
+        watch(&store, "gone.com", None).await.unwrap();
+        let lookup = EditingLookup {
+            store: WatchStore::new(store.path.clone()),
+        };
+
+        let report = check_with(&store, &lookup, &presence(&[], &[]))
+            .await
+            .unwrap();
+        let reported: Vec<(&str, Option<&str>)> = report
+            .watches
+            .iter()
+            .map(|w| (w.domain.as_str(), w.note.as_deref()))
+            .collect();
+        assert_eq!(reported, vec![("target.com", Some("Raised"))]);
+
+        let watches = list(&store).await.unwrap();
+        let saved: Vec<(&str, Option<&str>, bool)> = watches
+            .iter()
+            .map(|w| (w.domain.as_str(), w.note.as_deref(), w.status.is_some()))
+            .collect();
+        assert_eq!(
+            saved,
+            vec![
+                ("target.com", Some("Raised"), true),
+                ("late.com", None, false)
+            ]
+        );
+        let _ = std::fs::remove_file(&store.path);
+    }
+}
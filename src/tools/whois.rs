@@ -72,7 +72,7 @@ pub async fn lookup(domain: &str) -> Result<WhoisInfo> {
 }
 
 // Fallback command-line whois implementation
-async fn lookup_command_line_whois(domain: &str) -> Result<WhoisInfo> {
+pub(crate) async fn lookup_command_line_whois(domain: &str) -> Result<WhoisInfo> {
     use std::process::Command;
 
     let output = tokio::task::spawn_blocking({
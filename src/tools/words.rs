@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+const WORDS: &str = include_str!("data/words.txt");
+const SYNONYMS: &str = include_str!("data/synonyms.txt");
+
+fn content_lines(text: &'static str) -> impl Iterator<Item = &'static str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn synonym_table() -> &'static HashMap<&'static str, Vec<&'static str>> {
+    static TABLE: OnceLock<HashMap<&'static str, Vec<&'static str>>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        content_lines(SYNONYMS)
+            .filter_map(|line| {
+                let (word, synonyms) = line.split_once(':')?;
+                let synonyms = synonyms
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .collect();
+                Some((word.trim(), synonyms))
+            })
+            .collect()
+    })
+}
+
+/// The bundled dictionary: the word list plus every word in the synonym table.
+pub fn dictionary() -> &'static HashSet<&'static str> {
+    static DICTIONARY: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    DICTIONARY.get_or_init(|| {
+        let mut words: HashSet<&'static str> = content_lines(WORDS).collect();
+        for (word, synonyms) in synonym_table() {
+            words.insert(word);
+            words.extend(synonyms.iter().copied());
+        }
+        words
+    })
+}
+
+pub fn is_dictionary_word(word: &str) -> bool {
+    dictionary().contains(word)
+}
+
+/// Split `text` into dictionary words, using as few words as possible, or
+/// `None` when it cannot be made entirely of dictionary words.
+pub fn segment(text: &str) -> Option<Vec<&str>> {
+    if text.is_empty() {
+        return None;
+    }
+    // best[i]: the fewest words covering text[..i], and where the last starts.
+    let mut best: Vec<Option<(usize, usize)>> = vec![None; text.len() + 1];
+    best[0] = Some((0, 0));
+    for end in 1..=text.len() {
+        if !text.is_char_boundary(end) {
+            continue;
+        }
+        for start in 0..end {
+            let Some((count, _)) = best[start] else {
+                continue;
+            };
+            if !text.is_char_boundary(start) || !is_dictionary_word(&text[start..end]) {
+                continue;
+            }
+            if best[end].is_none_or(|(best_count, _)| count + 1 < best_count) {
+                best[end] = Some((count + 1, start));
+            }
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut end = text.len();
+    while end > 0 {
+        let (_, start) = best[end]?;
+        words.push(&text[start..end]);
+        end = start;
+    }
+    words.reverse();
+    Some(words)
+}
+
+pub fn synonyms(word: &str) -> &'static [&'static str] {
+    synonym_table()
+        .get(word)
+        .map(|synonyms| synonyms.as_slice())
+        .unwrap_or(&[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dictionary_test() {
+        assert!(is_dictionary_word("cloud"));
+        assert!(is_dictionary_word("bazaar"));
+        assert!(!is_dictionary_word("xqzv"));
+        assert!(!dictionary().iter().any(|word| word.starts_with('#')));
+    }
+
+    #[test]
+    fn segment_test() {
+        assert_eq!(segment("cloudshop"), Some(vec!["cloud", "shop"]));
+        assert_eq!(segment("cloud"), Some(vec!["cloud"]));
+        assert_eq!(segment("cloudxqzv"), None);
+        assert_eq!(segment(""), None);
+    }
+
+    #[test]
+    fn synonyms_test() {
+        assert!(synonyms("fast").contains(&"swift"));
+        assert!(synonyms("xqzv").is_empty());
+    }
+}
@@ -67,6 +67,19 @@ fn test_mcp_server_full_flow() -> Result<(), Box<dyn std::error::Error>> {
         "bulk_domain_check",
         "get_dns_records",
         "search_expired_domains",
+        "suggest_domains",
+        "check_name_across_tlds",
+        "analyze_certificate_chain",
+        "tls_scan",
+        "ct_search",
+        "caa_check",
+        "certificate_expiry_report",
+        "http_probe",
+        "hsts_preload_check",
+        "import_drop_list",
+        "watch_domain",
+        "list_watches",
+        "check_watches",
     ];
 
     for expected_tool in expected_tools.iter() {